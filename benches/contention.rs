@@ -0,0 +1,61 @@
+//! Throughput of acquisitions under contention, for each [`ContentionStrategy`].
+//!
+//! Run with `cargo bench --bench contention`.
+
+use std::{
+    sync::{Arc, Barrier},
+    thread,
+    time::{Duration, Instant},
+};
+use threshold_bucket::{refill::RateConfig, Bucket, ContentionStrategy};
+
+const THREADS: usize = 32;
+const ATTEMPTS: u64 = 100_000;
+
+fn run(strategy: ContentionStrategy) -> Duration {
+    let initial = THREADS as u64 * ATTEMPTS;
+    let bucket = Bucket::builder()
+        .rate(RateConfig {
+            quantity: 1.into(),
+            interval: Duration::from_secs(3600),
+            max: initial.into(),
+        })
+        .initial(initial)
+        .contention_strategy(strategy)
+        .build()
+        .unwrap();
+    let barrier = Arc::new(Barrier::new(THREADS + 1));
+
+    let handles: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let bucket = bucket.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                barrier.wait();
+                for _ in 0..ATTEMPTS {
+                    let _ = bucket.quick_acquire_one();
+                }
+            })
+        })
+        .collect();
+
+    barrier.wait();
+    let start = Instant::now();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    start.elapsed()
+}
+
+fn main() {
+    for strategy in [
+        ContentionStrategy::SpinOnly,
+        ContentionStrategy::MutexFallback { threshold: 8 },
+    ] {
+        let elapsed = run(strategy);
+        let per_op = elapsed / (THREADS as u64 * ATTEMPTS) as u32;
+        println!(
+            "{strategy:?}: {elapsed:?} total, {per_op:?} per acquisition with {THREADS} threads"
+        );
+    }
+}
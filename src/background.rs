@@ -0,0 +1,79 @@
+//! Background refills and timers on a tokio or async-std runtime
+
+#[cfg(all(feature = "async-std", not(feature = "tokio")))]
+use std::future::Future;
+#[cfg(feature = "tokio")]
+use std::task::Waker;
+use std::{
+    pin::Pin,
+    sync::{Arc, Weak},
+    time::Duration,
+};
+
+#[cfg(feature = "tokio")]
+use tokio::{runtime::Handle, task::JoinHandle, time::Instant};
+
+use crate::inner::Inner;
+
+/// Delay between refills when the refill doesn't know when the next one is due
+const DEFAULT_PERIOD: Duration = Duration::from_secs(1);
+
+/// Shortest delay between refills, to avoid spinning when a refill is already due
+const MIN_PERIOD: Duration = Duration::from_millis(1);
+
+/// Timer of the async runtime
+#[cfg(feature = "tokio")]
+pub(crate) type Sleep = Pin<Box<tokio::time::Sleep>>;
+
+/// Timer of the async runtime
+#[cfg(all(feature = "async-std", not(feature = "tokio")))]
+pub(crate) type Sleep = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Sleep for `duration` on the async runtime
+pub(crate) fn sleep(duration: Duration) -> Sleep {
+    #[cfg(feature = "tokio")]
+    return Box::pin(tokio::time::sleep(duration));
+    #[cfg(all(feature = "async-std", not(feature = "tokio")))]
+    return Box::pin(async_std::task::sleep(duration));
+}
+
+/// Spawn a task refilling `inner` on schedule, until the bucket is dropped
+#[cfg(feature = "tokio")]
+pub(crate) fn spawn(handle: &Handle, inner: &Arc<Inner>) -> JoinHandle<()> {
+    let inner = Arc::downgrade(inner);
+    handle.spawn(run(inner))
+}
+
+/// Spawn a task refilling `inner` on schedule, until the bucket is dropped
+#[cfg(all(feature = "async-std", not(feature = "tokio")))]
+pub(crate) fn spawn(inner: &Arc<Inner>) -> async_std::task::JoinHandle<()> {
+    let inner = Arc::downgrade(inner);
+    async_std::task::spawn(run(inner))
+}
+
+/// Wake `waker` after `duration`, from a task on the current runtime
+///
+/// This panics if called outside of a tokio runtime.
+#[cfg(feature = "tokio")]
+pub(crate) fn wake_after(waker: Waker, duration: Duration) {
+    let deadline = Instant::now() + duration;
+    tokio::spawn(async move {
+        tokio::time::sleep_until(deadline).await;
+        waker.wake();
+    });
+}
+
+async fn run(inner: Weak<Inner>) {
+    loop {
+        // Only hold the bucket while refilling, so it can be dropped while sleeping
+        let period = match inner.upgrade() {
+            Some(inner) => {
+                inner.available_after_refill();
+                inner.next_refill_in().unwrap_or(DEFAULT_PERIOD)
+            }
+            None => return,
+        };
+
+        sleep(period.max(MIN_PERIOD)).await;
+    }
+}
@@ -1,36 +1,452 @@
-use std::sync::Arc;
+use std::{
+    fmt, fs, io,
+    marker::PhantomData,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use crate::{
-    permit::{always::AlwaysPermitter, threshold::ThresholdConfig, PermitConfig, Permitter},
-    refill::{rate::RateConfig, RefillConfig},
-    Bucket, BuildError,
+    clock::Clock,
+    contention::ContentionStrategy,
+    cost::CostMap,
+    denomination::Denomination,
+    inner::DEFAULT_MAX_RETRIES,
+    metrics::MetricsObserver,
+    permit::{
+        always::AlwaysPermitter, custom::CustomPermitter, fair::FairPermitter,
+        threshold::ThresholdConfig, ttl::TtlPermitter, PermitConfig, Permitter, PriorityConfig,
+        WeightedThresholdConfig,
+    },
+    refill::{
+        rate::RateConfig, BurstConfig, ExponentialConfig, QuotaConfig, Refill, RefillConfig,
+        SlidingWindowConfig,
+    },
+    snapshot::CHECKPOINT_LEN,
+    Bucket, BucketSnapshot, BuildError, ChildBucket, ReadWriteBucket, TokenCount,
 };
 
 /// Builder for a [`Bucket`]
-#[derive(Default)]
-pub struct Builder {
-    initial: Option<u64>,
+///
+/// The type parameters track whether a refill and a maximum number of tokens were set, so that
+/// [`Builder::build`] is only available once they are:
+///
+/// ```rust,compile_fail
+/// use std::time::Duration;
+/// use threshold_bucket::Bucket;
+///
+/// // Missing the maximum number of tokens, set with `Builder::max`
+/// let bucket = Bucket::builder()
+///     .refill_rate(10, Duration::from_secs(1))
+///     .build();
+/// ```
+///
+/// Without type parameters, `Builder` is a builder ready to build, such as the one returned by
+/// [`Builder::rate`].
+#[derive(Clone)]
+pub struct Builder<R = RefillReady, M = MaxSet> {
+    initial: Option<Initial>,
+    credit_limit: Option<u64>,
+    max_cas_retries: Option<u32>,
+    contention_strategy: Option<ContentionStrategy>,
     refill_config: Option<RefillConfig>,
+    /// `(quantity, interval)` set with [`Builder::refill_rate`], completed by [`Builder::max`]
+    refill_rate: Option<(u64, Duration)>,
+    max: Option<u64>,
+    shared_max: Option<Arc<AtomicU64>>,
     permit_config: Option<PermitConfig>,
+    denomination: Option<Denomination>,
+    cost_map: Option<CostMap>,
+    clock: Option<Arc<dyn Clock + Send + Sync>>,
+    elapsed: Option<Duration>,
+    permit_ttl: Option<Duration>,
+    metrics: Option<Arc<dyn MetricsObserver + Send + Sync>>,
+    #[cfg(feature = "tracing")]
+    trace_name: Option<Arc<str>>,
+    state: PhantomData<(R, M)>,
 }
 
-impl Builder {
+/// [`Builder`] state without a refill
+#[derive(Clone, Copy, Debug)]
+pub struct NoRefill;
+
+/// [`Builder`] state with a refill
+#[derive(Clone, Copy, Debug)]
+pub struct RefillReady;
+
+/// [`Builder`] state without a maximum number of tokens
+#[derive(Clone, Copy, Debug)]
+pub struct NoMax;
+
+/// [`Builder`] state with a maximum number of tokens, set with [`Builder::max`] or included in
+/// the refill configuration
+#[derive(Clone, Copy, Debug)]
+pub struct MaxSet;
+
+impl Builder<NoRefill, NoMax> {
+    /// Create a new [`Builder`], without a refill or a maximum number of tokens
+    ///
+    /// You must call [`Builder::rate`] or an equivalent method before [`Builder::build`]. This is
+    /// also available as [`Bucket::builder`].
+    // `Builder` doesn't implement `Default`, as the default builder can't build a bucket
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            initial: None,
+            credit_limit: None,
+            max_cas_retries: None,
+            contention_strategy: None,
+            refill_config: None,
+            refill_rate: None,
+            max: None,
+            shared_max: None,
+            permit_config: None,
+            denomination: None,
+            cost_map: None,
+            clock: None,
+            elapsed: None,
+            permit_ttl: None,
+            metrics: None,
+            #[cfg(feature = "tracing")]
+            trace_name: None,
+            state: PhantomData,
+        }
+    }
+
+    /// Restore a [`Bucket`] from a checkpoint written by
+    /// [`Bucket::checkpoint`](crate::Bucket::checkpoint), refilling with `config`
+    ///
+    /// This behaves as [`Builder::restore_from_snapshot`] with [`Builder::rate`]. The refill
+    /// configuration isn't part of the checkpoint, so it's passed separately. This returns an
+    /// [`io::ErrorKind::InvalidData`] error if the file isn't a checkpoint, or if it was written
+    /// by a bucket with a different maximum number of tokens than `config`, and an
+    /// [`io::ErrorKind::InvalidInput`] error if `config` can't build a bucket.
+    pub fn restore_checkpoint(path: &Path, config: RateConfig) -> io::Result<Bucket> {
+        let bytes = fs::read(path)?;
+        let bytes = bytes.try_into().map_err(|bytes: Vec<u8>| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "checkpoint must be {CHECKPOINT_LEN} bytes, found {}",
+                    bytes.len()
+                ),
+            )
+        })?;
+        let (snapshot, max) = BucketSnapshot::from_checkpoint(bytes);
+        let expected = u64::from(config.max);
+        if max != expected {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("checkpoint max ({max}) doesn't match the refill max ({expected})"),
+            ));
+        }
+
+        Self::new()
+            .rate(config)
+            .restore_from_snapshot(snapshot)
+            .build()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))
+    }
+}
+
+/// Initial number of tokens, resolved when building the [`Bucket`]
+#[derive(Clone, Copy, Debug)]
+enum Initial {
+    Tokens(u64),
+    Full,
+    Empty,
+}
+
+impl<R, M> Builder<R, M> {
+    /// Move the configuration to a builder in another state
+    fn into_state<R2, M2>(self) -> Builder<R2, M2> {
+        Builder {
+            initial: self.initial,
+            credit_limit: self.credit_limit,
+            max_cas_retries: self.max_cas_retries,
+            contention_strategy: self.contention_strategy,
+            refill_config: self.refill_config,
+            refill_rate: self.refill_rate,
+            max: self.max,
+            shared_max: self.shared_max,
+            permit_config: self.permit_config,
+            denomination: self.denomination,
+            cost_map: self.cost_map,
+            clock: self.clock,
+            elapsed: self.elapsed,
+            permit_ttl: self.permit_ttl,
+            metrics: self.metrics,
+            #[cfg(feature = "tracing")]
+            trace_name: self.trace_name,
+            state: PhantomData,
+        }
+    }
+
     /// Set the initial number of tokens in the [`Bucket`]
     pub fn initial(self, initial: u64) -> Self {
         Self {
-            initial: Some(initial),
+            initial: Some(Initial::Tokens(initial)),
             ..self
         }
     }
 
-    /// Use constant refill rate
-    pub fn rate(self, config: RateConfig) -> Self {
+    /// Start the [`Bucket`] with the maximum number of tokens
+    pub fn initial_full(self) -> Self {
+        Self {
+            initial: Some(Initial::Full),
+            ..self
+        }
+    }
+
+    /// Start the [`Bucket`] without any tokens
+    ///
+    /// This is the default, except for [`Builder::refill_burst`], which starts with the burst, and
+    /// [`Builder::quota`], which starts with the maximum number of tokens.
+    pub fn initial_empty(self) -> Self {
+        Self {
+            initial: Some(Initial::Empty),
+            ..self
+        }
+    }
+
+    /// Allow borrowing up to `credit_limit` tokens when there aren't enough tokens available
+    ///
+    /// Borrowed tokens are repaid by future refills, before they become available again.
+    pub fn credit_limit(self, credit_limit: u64) -> Self {
+        Self {
+            credit_limit: Some(credit_limit),
+            ..self
+        }
+    }
+
+    /// Set the maximum number of attempts to update the number of available tokens under
+    /// contention, before returning [`Error::HighContention`](crate::Error::HighContention)
+    ///
+    /// Defaults to 65536. Lower values fail faster in latency-sensitive systems, while higher
+    /// values are more suitable for write-heavy workloads.
+    pub fn max_cas_retries(self, limit: u32) -> Self {
+        Self {
+            max_cas_retries: Some(limit),
+            ..self
+        }
+    }
+
+    /// Set the behaviour of acquisitions when other threads keep updating the number of available
+    /// tokens
+    ///
+    /// Defaults to [`ContentionStrategy::SpinOnly`].
+    pub fn contention_strategy(self, strategy: ContentionStrategy) -> Self {
+        Self {
+            contention_strategy: Some(strategy),
+            ..self
+        }
+    }
+
+    /// Restore the state of a bucket from a [`BucketSnapshot`]
+    ///
+    /// The bucket starts with the snapshot's available tokens, and continues refilling from the
+    /// time of the snapshot, without adding tokens for the time since then. Refills use the
+    /// same boundaries as before the snapshot, so the next refill isn't pushed back by a restart.
+    ///
+    /// For custom refills set with [`Builder::with_refill`], the elapsed time passed to
+    /// [`Refill::refill`] starts at the time of the snapshot.
+    pub fn restore_from_snapshot(self, snapshot: BucketSnapshot) -> Self {
+        Self {
+            initial: Some(Initial::Tokens(snapshot.available)),
+            elapsed: Some(Duration::from_millis(snapshot.elapsed_millis)),
+            ..self
+        }
+    }
+
+    /// Restore the state of a bucket from a [`BucketSnapshot`]
+    #[deprecated(note = "use `Builder::restore_from_snapshot` instead")]
+    pub fn restore(self, snapshot: BucketSnapshot) -> Self {
+        self.restore_from_snapshot(snapshot)
+    }
+
+    /// Use a custom source of time instead of the system clock
+    ///
+    /// This is mostly useful for tests, with a [`ManualClock`](crate::clock::ManualClock) to
+    /// simulate the passage of time without sleeping.
+    pub fn with_clock<C>(self, clock: C) -> Self
+    where
+        C: Clock + Send + Sync + 'static,
+    {
+        Self {
+            clock: Some(Arc::new(clock)),
+            ..self
+        }
+    }
+
+    /// Notify `observer` of permit decisions, acquisitions and refills
+    ///
+    /// See the [`metrics`](crate::metrics) module.
+    pub fn with_metrics(self, observer: Arc<dyn MetricsObserver + Send + Sync>) -> Self {
         Self {
+            metrics: Some(observer),
+            ..self
+        }
+    }
+
+    /// Include `name` in all tracing events of the [`Bucket`], as the `bucket` field
+    ///
+    /// Acquisitions run in a `bucket.try_acquire` span and emit `DEBUG` events, or `INFO` events
+    /// when denied and `WARN` events under contention. Refills emit `TRACE` events.
+    #[cfg(feature = "tracing")]
+    pub fn with_trace_name(self, name: &str) -> Self {
+        Self {
+            trace_name: Some(name.into()),
+            ..self
+        }
+    }
+
+    /// Use constant refill rate
+    pub fn rate(self, config: RateConfig) -> Builder<RefillReady, MaxSet> {
+        Builder {
             refill_config: Some(RefillConfig::Rate(config)),
+            refill_rate: None,
+            ..self.into_state()
+        }
+    }
+
+    /// Add `quantity` tokens every `interval`
+    ///
+    /// The maximum number of tokens must be set with [`Builder::max`], before or after this call.
+    /// This replaces any refill set before, such as with [`Builder::rate`], but keeps its maximum
+    /// number of tokens unless [`Builder::max`] is set.
+    pub fn refill_rate(self, quantity: u64, interval: Duration) -> Builder<RefillReady, M> {
+        // The refill being replaced is kept for its maximum, so that its `MaxSet` state still
+        // holds
+        Builder {
+            refill_rate: Some((quantity, interval)),
+            ..self.into_state()
+        }
+    }
+
+    /// Set the maximum number of tokens for [`Builder::refill_rate`]
+    ///
+    /// Other refills, such as [`Builder::rate`], already include their maximum number of tokens.
+    /// Setting a different one with this method fails with [`BuildError::ConflictingMax`] when
+    /// building the [`Bucket`].
+    pub fn max(self, max: u64) -> Builder<R, MaxSet> {
+        Builder {
+            max: Some(max),
+            ..self.into_state()
+        }
+    }
+
+    /// Read the maximum number of tokens from `max`, which can be changed from outside the
+    /// [`Bucket`] at any time
+    ///
+    /// This replaces the maximum of the refill configuration. When `max` is lowered below the
+    /// number of available tokens, no tokens are added until enough tokens are acquired. When it
+    /// is raised, tokens accumulate up to the new maximum.
+    pub fn shared_max(self, max: Arc<AtomicU64>) -> Self {
+        Self {
+            shared_max: Some(max),
             ..self
         }
     }
 
+    /// Add `rate` tokens every second, up to `max` tokens
+    ///
+    /// This is a shorthand for [`Builder::rate`] with [`RateConfig::per_second`].
+    pub fn refill_rate_per_second(self, rate: u64, max: u64) -> Builder<RefillReady, MaxSet> {
+        self.rate(RateConfig::per_second(rate, max))
+    }
+
+    /// Add `rate` tokens every minute, up to `max` tokens
+    ///
+    /// This is a shorthand for [`Builder::rate`] with [`RateConfig::per_minute`].
+    pub fn refill_rate_per_minute(self, rate: u64, max: u64) -> Builder<RefillReady, MaxSet> {
+        self.rate(RateConfig::per_minute(rate, max))
+    }
+
+    /// Add `rate` tokens every hour, up to `max` tokens
+    ///
+    /// This is a shorthand for [`Builder::rate`] with [`RateConfig::per_hour`].
+    pub fn refill_rate_per_hour(self, rate: u64, max: u64) -> Builder<RefillReady, MaxSet> {
+        self.rate(RateConfig::per_hour(rate, max))
+    }
+
+    /// Use sliding window refill
+    pub fn sliding_window(self, config: SlidingWindowConfig) -> Builder<RefillReady, MaxSet> {
+        Builder {
+            refill_config: Some(RefillConfig::SlidingWindow(config)),
+            refill_rate: None,
+            ..self.into_state()
+        }
+    }
+
+    /// Start with `burst` tokens, then add `quantity` tokens every `interval`
+    ///
+    /// The initial number of tokens can still be overridden with [`Builder::initial`].
+    pub fn refill_burst(
+        self,
+        burst: u64,
+        quantity: u64,
+        interval: Duration,
+        max: u64,
+    ) -> Builder<RefillReady, MaxSet> {
+        Builder {
+            refill_config: Some(RefillConfig::Burst(BurstConfig {
+                burst,
+                sustained_quantity: quantity,
+                sustained_interval: interval,
+                max,
+            })),
+            refill_rate: None,
+            ..self.into_state()
+        }
+    }
+
+    /// Use an exponentially increasing refill rate
+    pub fn refill_exponential(self, config: ExponentialConfig) -> Builder<RefillReady, MaxSet> {
+        Builder {
+            refill_config: Some(RefillConfig::Exponential(config)),
+            refill_rate: None,
+            ..self.into_state()
+        }
+    }
+
+    /// Go back to the maximum number of tokens at a fixed interval, instead of adding tokens
+    /// gradually
+    ///
+    /// Unless set with [`Builder::initial`], the bucket starts with the maximum number of tokens.
+    pub fn quota(self, config: QuotaConfig) -> Builder<RefillReady, MaxSet> {
+        Builder {
+            refill_config: Some(RefillConfig::Quota(config)),
+            refill_rate: None,
+            ..self.into_state()
+        }
+    }
+
+    /// Go back to `max` tokens every `interval`, counting from the creation of the bucket
+    ///
+    /// This is a shorthand for [`Builder::quota`] without wall clock alignment.
+    pub fn refill_quota(self, max: u64, interval: Duration) -> Builder<RefillReady, MaxSet> {
+        self.quota(QuotaConfig {
+            max,
+            reset_interval: interval,
+            wall_clock_aligned: false,
+        })
+    }
+
+    /// Use a custom [`Refill`] algorithm
+    pub fn with_refill<F>(self, refill: F) -> Builder<RefillReady, MaxSet>
+    where
+        F: Refill + Send + Sync + 'static,
+    {
+        Builder {
+            refill_config: Some(RefillConfig::Custom(Arc::new(refill))),
+            refill_rate: None,
+            ..self.into_state()
+        }
+    }
+
     /// Use threshold-based permit allocation
     pub fn threshold(self, config: ThresholdConfig) -> Self {
         Self {
@@ -39,17 +455,364 @@ impl Builder {
         }
     }
 
+    /// Use priority-based permit allocation, with a different threshold per priority tier
+    pub fn priority(self, config: PriorityConfig) -> Self {
+        Self {
+            permit_config: Some(PermitConfig::Priority(config)),
+            ..self
+        }
+    }
+
+    /// Use weight-based permit allocation, with a different threshold per `(weight, threshold)`
+    /// tier
+    ///
+    /// See [`WeightedThresholdConfig`] and [`Bucket::try_permit_weighted`].
+    pub fn weighted_threshold(self, tiers: Vec<(u64, u64)>) -> Self {
+        Self {
+            permit_config: Some(PermitConfig::WeightedThreshold(WeightedThresholdConfig {
+                tiers,
+            })),
+            ..self
+        }
+    }
+
+    /// Grant permits in turn to competing callers
+    ///
+    /// Callers identify themselves with [`Bucket::try_permit_for`], and only get a permit if no
+    /// other caller has requested fewer tokens so far. Callers that haven't requested a permit
+    /// for a second are ignored until they come back. [`Bucket::try_permit`] is treated as
+    /// caller `0`.
+    pub fn fair(self) -> Self {
+        Self {
+            permit_config: Some(PermitConfig::Fair),
+            ..self
+        }
+    }
+
+    /// Refuse new permits when the number of available tokens goes below `pct` percent of the
+    /// maximum number of tokens
+    ///
+    /// The threshold is computed when building the bucket, and must be lower than the maximum
+    /// number of tokens.
+    pub fn threshold_percent(self, pct: u8) -> Self {
+        Self {
+            permit_config: Some(PermitConfig::ThresholdPercent(pct)),
+            ..self
+        }
+    }
+
+    /// Use a custom [`Permitter`] for permit allocation
+    pub fn with_permitter<P>(self, permitter: P) -> Self
+    where
+        P: Permitter + 'static,
+    {
+        Self {
+            permit_config: Some(PermitConfig::Custom(Arc::new(permitter))),
+            ..self
+        }
+    }
+
+    /// Expire [`Permit`](crate::permit::Permit)s that haven't been used within `ttl`
+    ///
+    /// Expired permits are refused with [`Error::ExpiredPermit`](crate::Error::ExpiredPermit),
+    /// so that stale permits can't be used after other callers drained the bucket.
+    pub fn permit_ttl(self, ttl: Duration) -> Self {
+        Self {
+            permit_ttl: Some(ttl),
+            ..self
+        }
+    }
+
+    /// Set the name of the resource represented by the tokens, and how much of that resource a
+    /// single token represents
+    ///
+    /// This is only used for display purposes, e.g. by [`Bucket::available_human`].
+    pub fn denomination(self, name: &str, unit: u64) -> Self {
+        Self {
+            denomination: Some(Denomination::new(name, unit)),
+            ..self
+        }
+    }
+
+    /// Set the token costs of named operations, used by [`Bucket::try_acquire_for_op`]
+    ///
+    /// This replaces any costs registered before with [`Builder::register_op_cost`].
+    pub fn with_cost_map(self, costs: CostMap) -> Self {
+        Self {
+            cost_map: Some(costs),
+            ..self
+        }
+    }
+
+    /// Set the cost of the operation `op` to `tokens`, used by [`Bucket::try_acquire_for_op`]
+    pub fn register_op_cost(self, op: &str, tokens: u64) -> Self {
+        Self {
+            cost_map: Some(self.cost_map.unwrap_or_default().with_cost(op, tokens)),
+            ..self
+        }
+    }
+
+    /// Check the configuration, returning all the errors at once
+    ///
+    /// [`Builder::build`] returns the first of these errors. This can also be called on builders
+    /// that aren't ready to build yet, for example to report a configuration file with both a
+    /// missing refill and an invalid threshold, with [`BuildError::MissingRefill`] and
+    /// [`BuildError::MissingMax`] for the missing parts.
+    pub fn validate(&self) -> Result<(), Vec<BuildError>> {
+        let mut errors = Vec::new();
+        let refill = self.refill();
+        match &refill {
+            Some(refill) => errors.extend(refill.errors()),
+            None => {
+                match self.refill_rate {
+                    Some((quantity, interval)) if quantity == 0 || interval.is_zero() => {
+                        errors.push(BuildError::InvalidRate)
+                    }
+                    Some(_) => (),
+                    None => errors.push(BuildError::MissingRefill),
+                }
+                if self.max.is_none() {
+                    errors.push(BuildError::MissingMax);
+                }
+            }
+        }
+        match (self.refill_rate, &self.refill_config, self.max) {
+            // Unlike `RateConfig`, `Builder::refill_rate` doesn't allow a zero quantity
+            (Some((0, _)), _, _)
+                if refill.is_some() && !errors.contains(&BuildError::InvalidRate) =>
+            {
+                errors.push(BuildError::InvalidRate);
+            }
+            (None, Some(refill), Some(max)) if max != refill.max() => {
+                errors.push(BuildError::ConflictingMax {
+                    max,
+                    refill_max: refill.max(),
+                });
+            }
+            _ => (),
+        }
+
+        let max = match (&self.shared_max, &refill) {
+            (Some(max), _) => Some(max.load(Ordering::Acquire)),
+            (None, refill) => refill.as_ref().map(RefillConfig::max),
+        };
+        let initial = match (self.initial, &refill) {
+            (Some(Initial::Tokens(tokens)), _) => Some(tokens),
+            (None, Some(RefillConfig::Burst(config))) => Some(config.burst),
+            _ => None,
+        };
+        // Larger counts would be taken for acquisitions about to be reverted
+        for tokens in [max, self.credit_limit].into_iter().flatten() {
+            if tokens > TokenCount::MAX.into() {
+                errors.push(BuildError::TooManyTokens { tokens });
+            }
+        }
+        match (initial, max) {
+            (Some(tokens), _) if tokens > TokenCount::MAX.into() => {
+                errors.push(BuildError::TooManyTokens { tokens })
+            }
+            (Some(initial), Some(max)) if initial > max => {
+                errors.push(BuildError::InitialExceedsMax { initial, max })
+            }
+            _ => (),
+        }
+        let exceeds_max = |threshold: u64| match max {
+            Some(max) if threshold >= max => {
+                Some(BuildError::ThresholdExceedsMax { threshold, max })
+            }
+            _ => None,
+        };
+        match &self.permit_config {
+            Some(PermitConfig::Threshold(config)) => {
+                errors.extend(exceeds_max(config.threshold.into()));
+            }
+            Some(PermitConfig::ThresholdPercent(pct)) => {
+                let exceeds_max =
+                    max.is_some_and(|max| max as u128 * *pct as u128 / 100 >= max as u128);
+                if *pct > 100 || exceeds_max {
+                    errors.push(BuildError::InvalidThreshold);
+                }
+            }
+            Some(PermitConfig::Priority(config)) => {
+                errors.extend(
+                    config
+                        .tiers
+                        .iter()
+                        .find_map(|&(_, threshold)| exceeds_max(threshold)),
+                );
+            }
+            Some(PermitConfig::WeightedThreshold(config)) => {
+                errors.extend(
+                    config
+                        .tiers
+                        .iter()
+                        .find_map(|&(_, threshold)| exceeds_max(threshold)),
+                );
+            }
+            Some(PermitConfig::Fair | PermitConfig::Custom(_)) | None => (),
+        }
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+
+    /// Refill configuration, if a refill and a maximum number of tokens were set
+    fn refill(&self) -> Option<RefillConfig> {
+        match self.refill_rate {
+            Some((quantity, interval)) => {
+                // Unless set, the maximum comes from the refill replaced by `Builder::refill_rate`
+                let max = self
+                    .max
+                    .or_else(|| self.refill_config.as_ref().map(RefillConfig::max))?;
+                Some(RefillConfig::Rate(RateConfig {
+                    quantity: quantity.into(),
+                    interval,
+                    max: max.into(),
+                }))
+            }
+            None => self.refill_config.clone(),
+        }
+    }
+}
+
+impl Builder<RefillReady, MaxSet> {
     /// Build the [`Bucket`]
+    ///
+    /// This returns the first error found by [`Builder::validate`], if any.
     pub fn build(self) -> Result<Bucket, BuildError> {
-        let refill = self.refill_config.ok_or(BuildError::MissingRefill)?;
-        let inner = Arc::new(refill.into_inner_bucket(self.initial.unwrap_or(0)));
-        let permitter: Arc<dyn Permitter> = match self.permit_config {
+        if let Err(errors) = self.validate() {
+            return Err(errors
+                .into_iter()
+                .next()
+                .expect("validation failures have at least one error"));
+        }
+        Ok(self.build_validated())
+    }
+
+    /// Build the [`Bucket`] from a configuration that was already validated by
+    /// [`Builder::build`]
+    ///
+    /// This is used to create more buckets from the configuration of an existing one. They are
+    /// not validated again, as a [`Builder::shared_max`] lowered since then can't make the
+    /// configuration invalid once buckets were built with it.
+    pub(crate) fn build_validated(self) -> Bucket {
+        let config = Arc::new(self.clone());
+        // The `RefillReady` and `MaxSet` states guarantee that either a refill was set, or
+        // `refill_rate` and `max` were both set
+        let refill = self
+            .refill()
+            .expect("refill and max are set in the `RefillReady` and `MaxSet` states");
+        let initial = self.initial.map(|initial| match initial {
+            Initial::Tokens(tokens) => tokens,
+            Initial::Full => match &self.shared_max {
+                Some(max) => max.load(Ordering::Acquire),
+                None => refill.max(),
+            },
+            Initial::Empty => 0,
+        });
+        let mut inner = refill
+            .into_inner_bucket(initial, self.elapsed.unwrap_or_default())
+            .with_credit_limit(self.credit_limit.unwrap_or(0))
+            .with_max_retries(self.max_cas_retries.unwrap_or(DEFAULT_MAX_RETRIES))
+            .with_contention_strategy(self.contention_strategy.unwrap_or_default());
+        if let Some(clock) = self.clock {
+            inner = inner.with_clock(clock);
+        }
+        if let Some(metrics) = self.metrics {
+            inner = inner.with_metrics(metrics);
+        }
+        if let Some(max) = self.shared_max {
+            inner = inner.with_shared_max(max);
+        }
+        #[cfg(feature = "tracing")]
+        if let Some(name) = self.trace_name {
+            inner = inner.with_trace_name(name);
+        }
+        let inner = Arc::new(inner);
+        // Thresholds were checked against the maximum number of tokens by `validate`
+        let mut permitter: Arc<dyn Permitter> = match self.permit_config {
             Some(PermitConfig::Threshold(threshold_config)) => {
                 Arc::new(threshold_config.into_permitter(inner.clone()))
             }
+            Some(PermitConfig::ThresholdPercent(pct)) => {
+                let threshold = inner.max() as u128 * pct as u128 / 100;
+                let threshold_config = ThresholdConfig {
+                    threshold: TokenCount(threshold as u64),
+                };
+                Arc::new(threshold_config.into_permitter(inner.clone()))
+            }
+            Some(PermitConfig::Priority(priority_config)) => {
+                Arc::new(priority_config.into_permitter(inner.clone()))
+            }
+            Some(PermitConfig::WeightedThreshold(weighted_config)) => {
+                Arc::new(weighted_config.into_permitter(inner.clone()))
+            }
+            Some(PermitConfig::Fair) => Arc::new(FairPermitter::new(inner.clone())),
+            Some(PermitConfig::Custom(permitter)) => {
+                Arc::new(CustomPermitter::new(permitter, inner.clone()))
+            }
             None => Arc::new(AlwaysPermitter::new(inner.clone())),
         };
+        if let Some(ttl) = self.permit_ttl {
+            permitter = Arc::new(TtlPermitter::new(permitter, ttl));
+        }
+
+        Bucket {
+            permitter,
+            inner,
+            denomination: Arc::new(self.denomination.unwrap_or_default()),
+            costs: Arc::new(self.cost_map.unwrap_or_default()),
+            name: None,
+            config,
+        }
+    }
+
+    /// Build a [`ReadWriteBucket`], where reads cost `read_cost` tokens and writes cost
+    /// `write_cost` tokens
+    pub fn build_read_write(
+        self,
+        read_cost: u64,
+        write_cost: u64,
+    ) -> Result<ReadWriteBucket, BuildError> {
+        Ok(ReadWriteBucket::new(self.build()?, read_cost, write_cost))
+    }
+
+    /// Build a [`ChildBucket`], where acquisitions also take tokens from `parent`
+    pub fn build_child(self, parent: &Bucket) -> Result<ChildBucket, BuildError> {
+        Ok(ChildBucket::new(self.build()?, parent.clone()))
+    }
+}
+
+impl<R, M> fmt::Debug for Builder<R, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("Builder");
+        f.field("initial", &self.initial)
+            .field("credit_limit", &self.credit_limit)
+            .field("max_cas_retries", &self.max_cas_retries)
+            .field("contention_strategy", &self.contention_strategy)
+            .field("refill", &self.refill_config)
+            .field("refill_rate", &self.refill_rate)
+            .field("max", &self.max)
+            .field("shared_max", &self.shared_max)
+            .field("permit", &self.permit_config)
+            .field("denomination", &self.denomination)
+            .field("cost_map", &self.cost_map)
+            .field("custom_clock", &self.clock.is_some())
+            .field("elapsed", &self.elapsed)
+            .field("permit_ttl", &self.permit_ttl)
+            .field("metrics", &self.metrics.is_some());
+        #[cfg(feature = "tracing")]
+        f.field("trace_name", &self.trace_name);
+        f.finish()
+    }
+}
+
+impl TryFrom<Builder> for Bucket {
+    type Error = BuildError;
 
-        Ok(Bucket { permitter, inner })
+    fn try_from(builder: Builder) -> Result<Self, Self::Error> {
+        builder.build()
     }
 }
@@ -0,0 +1,105 @@
+//! Time sources for buckets
+//!
+//! By default, a [`Bucket`](crate::Bucket) measures time with a [`SystemClock`]. Tests can
+//! simulate the passage of time without sleeping by building the bucket with a [`ManualClock`]
+//! through [`Builder::with_clock`](crate::Builder::with_clock).
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use threshold_bucket::{clock::ManualClock, refill::RateConfig, Bucket};
+//!
+//! let clock = ManualClock::new();
+//! let bucket = Bucket::builder()
+//!     .rate(RateConfig {
+//!         quantity: 1.into(),
+//!         interval: Duration::from_secs(1),
+//!         max: 10.into(),
+//!     })
+//!     .with_clock(clock.clone())
+//!     .build()
+//!     .unwrap();
+//!
+//! clock.advance(Duration::from_secs(5));
+//! ```
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Source of time for a bucket
+pub trait Clock {
+    /// Time elapsed since the clock started
+    fn now(&self) -> Duration;
+
+    /// Wait for `duration` to pass on this clock
+    ///
+    /// This is used for the backoff of `Bucket::try_acquire_with_retry`, with the `blocking`
+    /// feature. By default, this puts the current thread to sleep.
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// Clock based on the system's monotonic clock
+#[derive(Clone, Copy, Debug)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    /// Create a new clock starting now
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// Clock that only moves forward when advanced manually
+///
+/// Clones share the same time, so a clone can be given to the bucket while the original is used
+/// to advance it.
+#[derive(Clone, Debug, Default)]
+pub struct ManualClock {
+    nanos: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    /// Create a new clock starting at zero
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move the clock forward by `duration`
+    pub fn advance(&self, duration: Duration) {
+        self.nanos
+            .fetch_add(duration.as_nanos() as u64, Ordering::AcqRel);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::Acquire))
+    }
+
+    /// Advance the clock by `duration` instead of sleeping
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
@@ -0,0 +1,62 @@
+//! Serializable bucket configuration
+
+use serde::{Deserialize, Serialize};
+
+use crate::{permit::ThresholdConfig, refill::RateConfig, Bucket, Builder};
+
+/// Serializable configuration for a [`Bucket`]
+///
+/// ```rust
+/// use threshold_bucket::BucketConfig;
+///
+/// let config: BucketConfig = serde_json::from_str(r#"{
+///     "rate": { "quantity": 10, "interval": 1000, "max": 100 },
+///     "threshold": { "threshold": 20 }
+/// }"#).unwrap();
+/// let bucket = config.into_builder().build().unwrap();
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BucketConfig {
+    /// Constant refill rate
+    pub rate: RateConfig,
+    /// Threshold under which the bucket will refuse to grant new permits
+    #[serde(default)]
+    pub threshold: Option<ThresholdConfig>,
+    /// Initial number of tokens
+    #[serde(default)]
+    pub initial: Option<u64>,
+}
+
+impl BucketConfig {
+    /// Create a [`Builder`] from this configuration
+    pub fn into_builder(self) -> Builder {
+        let mut builder = Bucket::builder().rate(self.rate);
+        if let Some(threshold) = self.threshold {
+            builder = builder.threshold(threshold);
+        }
+        if let Some(initial) = self.initial {
+            builder = builder.initial(initial);
+        }
+        builder
+    }
+}
+
+/// (De)serialize a [`Duration`](std::time::Duration) as a number of milliseconds
+pub(crate) mod millis {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub(crate) fn serialize<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_u64(duration.as_millis() as u64)
+    }
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
@@ -0,0 +1,20 @@
+//! Strategies to update the number of tokens under contention
+
+/// How acquisitions behave when other threads keep updating the number of available tokens
+///
+/// See [`Builder::contention_strategy`](crate::Builder::contention_strategy).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ContentionStrategy {
+    /// Retry the compare-and-swap until it succeeds, or the maximum number of attempts is reached
+    #[default]
+    SpinOnly,
+    /// After `threshold` failed compare-and-swap attempts, serialize the remaining attempts
+    /// behind a lock
+    ///
+    /// This keeps the lock-free fast path when contention is low, while preventing threads from
+    /// spinning without making progress under extreme load.
+    MutexFallback {
+        /// Number of failed attempts before taking the lock
+        threshold: u8,
+    },
+}
@@ -0,0 +1,77 @@
+//! Token costs of named operations
+
+use std::collections::HashMap;
+
+/// Number of tokens consumed by each operation on a [`Bucket`](crate::Bucket)
+///
+/// Operations are identified by tags such as `"read"` or `"bulk_write"`, and unregistered
+/// operations cost the default cost, which is 1 token unless set with
+/// [`CostMap::with_default_cost`].
+///
+/// ```rust
+/// use threshold_bucket::CostMap;
+///
+/// let costs = CostMap::new()
+///     .with_cost("read", 1)
+///     .with_cost("bulk_write", 10)
+///     .with_default_cost(2);
+///
+/// assert_eq!(costs.lookup("bulk_write"), Some(10));
+/// assert_eq!(costs.lookup("delete"), None);
+/// assert_eq!(costs.cost("delete"), 2);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CostMap {
+    costs: HashMap<String, u64>,
+    default_cost: u64,
+}
+
+impl CostMap {
+    /// Create a new cost map without any operations, and a default cost of 1 token
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the cost of `op` to `tokens`
+    pub fn with_cost(mut self, op: &str, tokens: u64) -> Self {
+        self.insert(op, tokens);
+        self
+    }
+
+    /// Set the cost of unregistered operations
+    pub fn with_default_cost(self, tokens: u64) -> Self {
+        Self {
+            default_cost: tokens,
+            ..self
+        }
+    }
+
+    /// Set the cost of `op` to `tokens`, returning its previous cost if it was registered
+    pub fn insert(&mut self, op: &str, tokens: u64) -> Option<u64> {
+        self.costs.insert(op.to_string(), tokens)
+    }
+
+    /// Cost of `op`, if it is registered
+    pub fn lookup(&self, op: &str) -> Option<u64> {
+        self.costs.get(op).copied()
+    }
+
+    /// Cost of `op`, or the default cost if it isn't registered
+    pub fn cost(&self, op: &str) -> u64 {
+        self.lookup(op).unwrap_or(self.default_cost)
+    }
+
+    /// Cost of unregistered operations
+    pub fn default_cost(&self) -> u64 {
+        self.default_cost
+    }
+}
+
+impl Default for CostMap {
+    fn default() -> Self {
+        Self {
+            costs: HashMap::new(),
+            default_cost: 1,
+        }
+    }
+}
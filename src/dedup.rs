@@ -0,0 +1,154 @@
+//! # Deduplicated acquisitions
+//!
+//! [`Dedup`] coalesces concurrent acquisitions with the same key into a single acquisition, for
+//! example when many concurrent requests for the same resource shouldn't each consume tokens.
+
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::{Mutex, MutexGuard},
+};
+
+use tokio::sync::broadcast;
+
+use crate::{permit::Permit, Bucket, Error};
+
+/// Acquisitions from a [`Bucket`], shared by concurrent callers with the same key
+///
+/// The first caller for a key acquires the tokens, and callers with the same key arriving while
+/// it is still waiting receive the same result instead of acquiring tokens themselves. Once the
+/// acquisition resolves, the next caller for the key acquires tokens again.
+///
+/// ```rust
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::time::Duration;
+/// use threshold_bucket::{dedup::Dedup, refill::RateConfig, Bucket};
+///
+/// let bucket = Bucket::builder()
+///     .rate(RateConfig {
+///         quantity: 1.into(),
+///         interval: Duration::from_secs(1),
+///         max: 10.into(),
+///     })
+///     .initial(10)
+///     .build()?;
+/// let dedup = Dedup::new(bucket.clone());
+///
+/// assert_eq!(dedup.try_acquire("/index.html", || bucket.try_permit(), 1).await?, 1);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Dedup<K> {
+    bucket: Bucket,
+    /// Senders of the acquisitions in flight, by key
+    in_flight: Mutex<HashMap<K, broadcast::Sender<Result<u64, Error>>>>,
+}
+
+impl<K> Dedup<K>
+where
+    K: Hash + Eq + Clone,
+{
+    /// Create a new [`Dedup`] acquiring tokens from `bucket`
+    pub fn new(bucket: Bucket) -> Self {
+        Self {
+            bucket,
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Acquire `num` tokens for `key`, sleeping until they are available, unless an acquisition
+    /// for `key` is already in flight.
+    ///
+    /// The first caller gets a [`Permit`] with `permit_fn` and acquires the tokens as
+    /// [`Bucket::wait_acquire`]. Other callers for the same key wait for it and return the same
+    /// result, without calling `permit_fn`. If the first caller is cancelled, one of the waiting
+    /// callers acquires the tokens instead.
+    pub async fn try_acquire<F>(&self, key: K, permit_fn: F, num: u64) -> Result<u64, Error>
+    where
+        F: FnOnce() -> Result<Permit, Error>,
+    {
+        while let Some(mut receiver) = self.subscribe(&key) {
+            match receiver.recv().await {
+                Ok(result) => return result,
+                // The caller acquiring the tokens was cancelled, and another caller may have
+                // taken over already
+                Err(_) => continue,
+            }
+        }
+
+        let in_flight = InFlight {
+            dedup: self,
+            key: Some(key),
+        };
+        let result = match permit_fn() {
+            Ok(permit) => self.bucket.wait_acquire(permit, num).await,
+            Err(err) => Err(err),
+        };
+        in_flight.finish(result.clone());
+        result
+    }
+
+    /// Number of keys with an acquisition in flight
+    pub fn in_flight(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Subscribe to the acquisition in flight for `key`
+    ///
+    /// If there isn't one, this registers one for the caller and returns `None`.
+    fn subscribe(&self, key: &K) -> Option<broadcast::Receiver<Result<u64, Error>>> {
+        let mut in_flight = self.lock();
+        match in_flight.get(key) {
+            Some(sender) => Some(sender.subscribe()),
+            None => {
+                in_flight.insert(key.clone(), broadcast::channel(1).0);
+                None
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<K, broadcast::Sender<Result<u64, Error>>>> {
+        // Entries are inserted and removed in one piece, a panic can't leave the map inconsistent
+        self.in_flight
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Acquisition in flight, removed from the [`Dedup`] when finished or cancelled
+struct InFlight<'a, K>
+where
+    K: Hash + Eq + Clone,
+{
+    dedup: &'a Dedup<K>,
+    key: Option<K>,
+}
+
+impl<K> InFlight<'_, K>
+where
+    K: Hash + Eq + Clone,
+{
+    /// Send `result` to the waiting callers
+    fn finish(mut self, result: Result<u64, Error>) {
+        if let Some(sender) = self.remove() {
+            // No callers are waiting if this fails, which is fine
+            let _ = sender.send(result);
+        }
+    }
+
+    fn remove(&mut self) -> Option<broadcast::Sender<Result<u64, Error>>> {
+        let key = self.key.take()?;
+        self.dedup.lock().remove(&key)
+    }
+}
+
+impl<K> Drop for InFlight<'_, K>
+where
+    K: Hash + Eq + Clone,
+{
+    fn drop(&mut self) {
+        // Dropping the sender wakes up the waiting callers, so that one of them takes over
+        self.remove();
+    }
+}
@@ -0,0 +1,30 @@
+//! Human-readable token denomination
+
+/// Name and size of the resource represented by the tokens of a [`Bucket`](crate::Bucket)
+#[derive(Clone, Debug)]
+pub(crate) struct Denomination {
+    /// Name of the resource, e.g. `"API calls"`
+    name: String,
+    /// Quantity of the resource represented by a single token
+    unit: u64,
+}
+
+impl Denomination {
+    pub(crate) fn new(name: &str, unit: u64) -> Self {
+        Self {
+            name: name.to_string(),
+            unit,
+        }
+    }
+
+    /// Format a number of tokens in the denomination
+    pub(crate) fn format(&self, tokens: u64) -> String {
+        format!("{} {}", tokens.saturating_mul(self.unit), self.name)
+    }
+}
+
+impl Default for Denomination {
+    fn default() -> Self {
+        Self::new("tokens", 1)
+    }
+}
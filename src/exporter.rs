@@ -0,0 +1,70 @@
+//! Prometheus metrics for a [`Bucket`](crate::Bucket)
+
+use prometheus::{Gauge, IntCounter, IntGauge, Registry};
+
+use crate::Error;
+
+/// Gauges and counters of a bucket, see
+/// [`Bucket::register_metrics`](crate::Bucket::register_metrics)
+pub(crate) struct BucketMetrics {
+    available: IntGauge,
+    capacity: IntGauge,
+    utilization: Gauge,
+    acquired: IntCounter,
+    denied: IntCounter,
+}
+
+impl BucketMetrics {
+    /// Create the metrics, with names starting with `prefix`, and register them in `registry`
+    pub(crate) fn register(registry: &Registry, prefix: &str) -> prometheus::Result<Self> {
+        let metrics = Self {
+            available: IntGauge::new(
+                format!("{prefix}_available_tokens"),
+                "Number of tokens available in the bucket",
+            )?,
+            capacity: IntGauge::new(
+                format!("{prefix}_capacity"),
+                "Maximum number of tokens in the bucket",
+            )?,
+            utilization: Gauge::new(
+                format!("{prefix}_utilization"),
+                "Fraction of the maximum number of tokens currently available",
+            )?,
+            acquired: IntCounter::new(
+                format!("{prefix}_acquired_total"),
+                "Number of tokens acquired",
+            )?,
+            denied: IntCounter::new(
+                format!("{prefix}_denied_total"),
+                "Number of acquisitions refused for lack of tokens",
+            )?,
+        };
+        registry.register(Box::new(metrics.available.clone()))?;
+        registry.register(Box::new(metrics.capacity.clone()))?;
+        registry.register(Box::new(metrics.utilization.clone()))?;
+        registry.register(Box::new(metrics.acquired.clone()))?;
+        registry.register(Box::new(metrics.denied.clone()))?;
+        Ok(metrics)
+    }
+
+    /// Count the outcome of an acquisition
+    pub(crate) fn record_acquire(&self, result: &Result<u64, Error>) {
+        match result {
+            Ok(num) => self.acquired.inc_by(*num),
+            Err(Error::NotEnoughTokens { .. }) => self.denied.inc(),
+            Err(_) => (),
+        }
+    }
+
+    /// Update the gauges with the current fill level of the bucket
+    pub(crate) fn set_level(&self, available: u64, capacity: u64) {
+        self.available
+            .set(i64::try_from(available).unwrap_or(i64::MAX));
+        self.capacity
+            .set(i64::try_from(capacity).unwrap_or(i64::MAX));
+        self.utilization.set(match capacity {
+            0 => 0.0,
+            capacity => (available as f64 / capacity as f64).clamp(0.0, 1.0),
+        });
+    }
+}
@@ -0,0 +1,45 @@
+//! Tokens returned to the bucket unless committed
+
+use std::sync::Weak;
+
+use crate::inner::Inner;
+
+/// Tokens acquired from a [`Bucket`](crate::Bucket), given back when dropped unless committed
+///
+/// This is useful when the operation consuming the tokens can fail midway: if the
+/// [`TokenGuard`] is dropped without calling [`TokenGuard::commit`], the tokens are added back to
+/// the bucket, without exceeding its maximum number of tokens.
+///
+/// If the bucket itself was already dropped, dropping the guard does nothing.
+pub struct TokenGuard {
+    bucket: Weak<Inner>,
+    tokens: u64,
+}
+
+impl TokenGuard {
+    pub(crate) fn new(bucket: Weak<Inner>, tokens: u64) -> Self {
+        Self { bucket, tokens }
+    }
+
+    /// Number of tokens held by this guard
+    pub fn tokens(&self) -> u64 {
+        self.tokens
+    }
+
+    /// Keep the tokens, consuming the guard without giving them back to the bucket
+    pub fn commit(mut self) {
+        self.tokens = 0;
+    }
+}
+
+impl Drop for TokenGuard {
+    fn drop(&mut self) {
+        if self.tokens == 0 {
+            return;
+        }
+        if let Some(bucket) = self.bucket.upgrade() {
+            // Nothing can be done with a failure to give back the tokens while dropping
+            let _ = bucket.release(self.tokens);
+        }
+    }
+}
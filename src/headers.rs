@@ -0,0 +1,73 @@
+//! Rate limit HTTP headers, as strings independent of any HTTP framework
+
+use std::time::Duration;
+
+use crate::{Bucket, Error};
+
+/// Values for the `X-RateLimit-*` headers of an HTTP response
+///
+/// See [`Bucket::to_http_headers`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BucketHeaders {
+    /// Maximum number of tokens in the bucket, for `X-RateLimit-Limit`
+    pub limit: u64,
+    /// Number of tokens available, for `X-RateLimit-Remaining`
+    pub remaining: u64,
+    /// Number of seconds until the bucket is full again, for `X-RateLimit-Reset`
+    pub reset: u64,
+}
+
+impl BucketHeaders {
+    /// Header names and values, ready to be added to a response
+    pub fn to_header_values(&self) -> [(&'static str, String); 3] {
+        [
+            ("X-RateLimit-Limit", self.limit.to_string()),
+            ("X-RateLimit-Remaining", self.remaining.to_string()),
+            ("X-RateLimit-Reset", self.reset.to_string()),
+        ]
+    }
+}
+
+impl Bucket {
+    /// Values for the `X-RateLimit-*` headers of an HTTP response, based on the current state of
+    /// the [`Bucket`].
+    ///
+    /// The bucket is refilled first, and the remaining tokens and the reset both describe the
+    /// bucket after that refill. The reset is `0` when the bucket is full, or if the refill can't
+    /// tell when it will be.
+    pub fn to_http_headers(&self) -> BucketHeaders {
+        let limit = self.capacity();
+        let (remaining, wait_for) = self.inner.available_and_wait_for(limit);
+        BucketHeaders {
+            limit,
+            remaining: remaining.into(),
+            reset: wait_for.map(retry_after_secs).unwrap_or(0),
+        }
+    }
+}
+
+impl Error {
+    /// Value for the `Retry-After` header of a `429 Too Many Requests` response, in seconds
+    ///
+    /// This is only set for [`Error::NotEnoughTokens`] with a known duration, rounded up to the
+    /// next second so that clients don't retry too early.
+    pub fn to_retry_after_header(&self) -> Option<String> {
+        match self {
+            Error::NotEnoughTokens {
+                wait_for: Some(wait_for),
+                ..
+            } => Some(retry_after_secs(*wait_for).to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// Number of whole seconds to wait, rounded up so clients don't retry too early
+pub(crate) fn retry_after_secs(wait_for: Duration) -> u64 {
+    let secs = wait_for.as_secs();
+    if wait_for.subsec_nanos() > 0 {
+        secs + 1
+    } else {
+        secs
+    }
+}
@@ -0,0 +1,93 @@
+//! Child buckets drawing tokens from a shared parent bucket
+
+use crate::{permit::Permit, Bucket, Error};
+
+/// # [`Bucket`] drawing tokens from a parent [`Bucket`]
+///
+/// Acquisitions take tokens from both the child and the parent, so that the parent enforces a
+/// global limit shared by all its children, like a hierarchical token bucket.
+///
+/// Tokens are first taken from the parent, then from the child. If the child doesn't have enough
+/// tokens, the tokens taken from the parent are given back. This is best-effort: these are two
+/// separate atomic operations, so other acquisitions on the parent can briefly see it with fewer
+/// tokens than it will eventually have.
+///
+/// Permits are granted by the child's permitter. The parent's permitter is not used.
+///
+/// ```rust
+/// # use std::time::Duration;
+/// use threshold_bucket::{refill::RateConfig, Bucket};
+///
+/// let global = Bucket::builder()
+///     .rate(RateConfig { quantity: 10_000.into(), interval: Duration::from_secs(1), max: 10_000.into() })
+///     .initial_full()
+///     .build()?;
+/// let user = Bucket::builder()
+///     .rate(RateConfig { quantity: 100.into(), interval: Duration::from_secs(1), max: 100.into() })
+///     .initial_full()
+///     .build_child(&global)?;
+///
+/// let permit = user.try_permit().unwrap();
+/// user.try_acquire(permit, 10)?;
+/// assert_eq!(user.available(), 90);
+/// assert_eq!(global.available(), 9_990);
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct ChildBucket {
+    bucket: Bucket,
+    parent: Bucket,
+}
+
+impl ChildBucket {
+    /// Create a new [`ChildBucket`] from an existing `bucket` and its `parent`.
+    pub fn new(bucket: Bucket, parent: Bucket) -> Self {
+        Self { bucket, parent }
+    }
+
+    /// Child [`Bucket`].
+    pub fn bucket(&self) -> &Bucket {
+        &self.bucket
+    }
+
+    /// Parent [`Bucket`].
+    pub fn parent(&self) -> &Bucket {
+        &self.parent
+    }
+
+    /// Number of tokens available in the child [`Bucket`].
+    pub fn available(&self) -> u64 {
+        self.bucket.available()
+    }
+
+    /// Try to acquire a [`Permit`] from the child [`Bucket`].
+    #[deprecated(note = "use `ChildBucket::try_permit` instead")]
+    pub fn get_permit(&self) -> Option<Permit> {
+        self.bucket.try_permit().ok()
+    }
+
+    /// Try to acquire a [`Permit`] from the child [`Bucket`], returning
+    /// [`Error::NotEnoughTokens`] if it cannot be granted.
+    pub fn try_permit(&self) -> Result<Permit, Error> {
+        self.bucket.try_permit()
+    }
+
+    /// Try to acquire `num` number of tokens from both the child and the parent.
+    ///
+    /// This fails if either of them doesn't have enough tokens, in which case no tokens are
+    /// acquired.
+    pub fn try_acquire(&self, permit: Permit, num: u64) -> Result<u64, Error> {
+        self.bucket.check_permit(&permit)?;
+
+        self.parent.inner.try_acquire(num)?;
+        let min_available = permit.min_available();
+        permit.notify(num);
+        self.bucket
+            .inner
+            .try_acquire_above(num, min_available)
+            .inspect_err(|_| {
+                // Give back the tokens taken from the parent
+                let _ = self.parent.inner.release(num);
+            })
+    }
+}
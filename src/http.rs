@@ -0,0 +1,116 @@
+//! # Rate limit headers
+//!
+//! [`rate_limit_headers`] computes the values of the `X-RateLimit-*` headers from the state of a
+//! [`Bucket`], independently of any HTTP framework. [`HeaderMapExt`] inserts them into the
+//! headers of a response. The values are the same as [`Bucket::to_http_headers`], so
+//! `X-RateLimit-Reset` is the number of seconds until the bucket is full again.
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use threshold_bucket::{http::rate_limit_headers, refill::RateConfig, Bucket};
+//!
+//! let bucket = Bucket::builder()
+//!     .rate(RateConfig {
+//!         quantity: 1.into(),
+//!         interval: Duration::from_secs(1),
+//!         max: 10.into(),
+//!     })
+//!     .initial(10)
+//!     .build()?;
+//! bucket.quick_acquire(3)?;
+//!
+//! let mut headers = Vec::new();
+//! rate_limit_headers(&bucket).insert_into(&mut headers);
+//! assert_eq!(headers[0], ("X-RateLimit-Limit", "10".to_string()));
+//! assert_eq!(headers[1], ("X-RateLimit-Remaining", "7".to_string()));
+//! assert_eq!(headers[2], ("X-RateLimit-Reset", "3".to_string()));
+//! # Ok::<_, Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::{Bucket, BucketHeaders};
+
+/// Values for the `X-RateLimit-*` headers of an HTTP response
+///
+/// See [`rate_limit_headers`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimitHeaders {
+    /// Maximum number of tokens in the bucket, for `X-RateLimit-Limit`
+    pub x_ratelimit_limit: u64,
+    /// Number of tokens available, for `X-RateLimit-Remaining`
+    pub x_ratelimit_remaining: u64,
+    /// Number of seconds until the bucket is full again, for `X-RateLimit-Reset`
+    pub x_ratelimit_reset: u64,
+}
+
+impl RateLimitHeaders {
+    /// Header names and values, ready to be added to a response
+    ///
+    /// These are the same as [`BucketHeaders::to_header_values`].
+    pub fn to_header_pairs(&self) -> [(&'static str, String); 3] {
+        BucketHeaders {
+            limit: self.x_ratelimit_limit,
+            remaining: self.x_ratelimit_remaining,
+            reset: self.x_ratelimit_reset,
+        }
+        .to_header_values()
+    }
+
+    /// Insert the headers into `headers`, replacing any previous values
+    pub fn insert_into<H>(&self, headers: &mut H)
+    where
+        H: HeaderMapExt + ?Sized,
+    {
+        for (name, value) in self.to_header_pairs() {
+            headers.insert_header(name, value);
+        }
+    }
+}
+
+/// Values for the `X-RateLimit-*` headers of an HTTP response, based on the current state of
+/// `bucket`
+///
+/// This is [`Bucket::to_http_headers`]: the bucket is refilled first, and the reset is the number
+/// of seconds until the bucket is full again, or `0` if the refill can't tell when it will be.
+pub fn rate_limit_headers(bucket: &Bucket) -> RateLimitHeaders {
+    bucket.to_http_headers().into()
+}
+
+impl From<BucketHeaders> for RateLimitHeaders {
+    fn from(headers: BucketHeaders) -> Self {
+        Self {
+            x_ratelimit_limit: headers.limit,
+            x_ratelimit_remaining: headers.remaining,
+            x_ratelimit_reset: headers.reset,
+        }
+    }
+}
+
+/// Collection of HTTP headers that [`RateLimitHeaders`] can be inserted into
+///
+/// This is implemented for lists of header names and values, and for `http::HeaderMap`, so that
+/// other frameworks only need to implement a single method.
+pub trait HeaderMapExt {
+    /// Insert a header, replacing any previous value with the same name
+    ///
+    /// Header names are case-insensitive.
+    fn insert_header(&mut self, name: &'static str, value: String);
+}
+
+impl HeaderMapExt for Vec<(&'static str, String)> {
+    fn insert_header(&mut self, name: &'static str, value: String) {
+        self.retain(|(other, _)| !other.eq_ignore_ascii_case(name));
+        self.push((name, value));
+    }
+}
+
+impl HeaderMapExt for ::http::HeaderMap {
+    fn insert_header(&mut self, name: &'static str, value: String) {
+        // Header names are static, and values are numbers, so this can't fail in practice
+        if let (Ok(name), Ok(value)) = (
+            ::http::HeaderName::from_bytes(name.as_bytes()),
+            ::http::HeaderValue::from_str(&value),
+        ) {
+            self.insert(name, value);
+        }
+    }
+}
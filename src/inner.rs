@@ -1,47 +1,421 @@
 use std::{
-    sync::atomic::{AtomicU64, Ordering},
-    time::{Duration, Instant},
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard, RwLock,
+    },
+    time::Duration,
 };
 
-use crate::{refill::Refill, Error};
+#[cfg(feature = "prometheus")]
+use crate::exporter::BucketMetrics;
+use crate::{
+    clock::{Clock, SystemClock},
+    contention::ContentionStrategy,
+    metrics::{MetricsObserver, NoopObserver},
+    observer::RefillObserver,
+    refill::{shared_max::SharedMaxRefill, Refill},
+    stats::{BucketStats, Stats},
+    Error, TokenCount,
+};
+#[cfg(feature = "prometheus")]
+use std::sync::OnceLock;
+
+/// Token counts above this value are transient underflows from the fast path in
+/// [`Inner::try_acquire`], and are never treated as real token counts.
+pub(crate) const UNDERFLOW_GUARD: u64 = TokenCount::MAX.0;
+
+/// Default maximum number of compare-and-swap attempts
+pub(crate) const DEFAULT_MAX_RETRIES: u32 = 0x10000;
 
 pub(crate) struct Inner {
-    refill: Box<dyn Refill + Send + Sync>,
+    refill: Arc<dyn Refill + Send + Sync>,
 
+    /// Number of tokens when the bucket was created
+    initial: u64,
     /// Currently available number of tokens
     available: AtomicU64,
-    start: Instant,
+    /// Number of tokens borrowed from the credit line, repaid by future refills
+    debt: AtomicU64,
+    /// Maximum number of tokens that can be borrowed
+    credit_limit: u64,
+    /// Maximum number of compare-and-swap attempts before returning [`Error::HighContention`]
+    max_retries: u32,
+    /// Behaviour when compare-and-swap attempts keep failing
+    contention_strategy: ContentionStrategy,
+    /// Lock serializing acquisitions with [`ContentionStrategy::MutexFallback`]
+    contention_mutex: Mutex<()>,
+    /// Source of time for refills
+    clock: Arc<dyn Clock + Send + Sync>,
+    /// Time already elapsed when the bucket was created, e.g. when restored from a snapshot
+    offset: Duration,
+    /// Lifetime counters
+    stats: Stats,
+    /// Observer notified of acquisitions and refills
+    metrics: Arc<dyn MetricsObserver + Send + Sync>,
+    /// Observers notified of refills, added after the bucket is built
+    refill_observers: RwLock<Vec<Arc<dyn RefillObserver + Send + Sync>>>,
+    /// Prometheus metrics, once registered
+    #[cfg(feature = "prometheus")]
+    prometheus: OnceLock<Arc<BucketMetrics>>,
+    /// Name of the bucket in tracing events
+    #[cfg(feature = "tracing")]
+    trace_name: Option<Arc<str>>,
 }
 
 impl Inner {
     /// Create a new inner bucket
-    pub(crate) fn new<R>(refill: R, initial: u64) -> Self
-    where
-        R: Refill + Send + Sync + 'static,
-    {
+    pub(crate) fn new(refill: Arc<dyn Refill + Send + Sync>, initial: u64) -> Self {
         Self {
-            refill: Box::new(refill),
+            refill,
+            initial,
             available: AtomicU64::new(initial),
-            start: Instant::now(),
+            debt: AtomicU64::new(0),
+            credit_limit: 0,
+            max_retries: DEFAULT_MAX_RETRIES,
+            contention_strategy: ContentionStrategy::default(),
+            contention_mutex: Mutex::new(()),
+            clock: Arc::new(SystemClock::new()),
+            offset: Duration::ZERO,
+            stats: Stats::default(),
+            metrics: Arc::new(NoopObserver),
+            refill_observers: RwLock::new(Vec::new()),
+            #[cfg(feature = "prometheus")]
+            prometheus: OnceLock::new(),
+            #[cfg(feature = "tracing")]
+            trace_name: None,
         }
     }
 
-    pub fn available(&self) -> u64 {
-        self.available.load(Ordering::Acquire)
+    /// Allow borrowing up to `credit_limit` tokens when there aren't enough tokens available
+    pub(crate) fn with_credit_limit(self, credit_limit: u64) -> Self {
+        Self {
+            credit_limit,
+            ..self
+        }
+    }
+
+    /// Use `clock` as the source of time for refills
+    pub(crate) fn with_clock(self, clock: Arc<dyn Clock + Send + Sync>) -> Self {
+        Self { clock, ..self }
+    }
+
+    /// Wait for `duration` to pass on the clock of the bucket
+    #[cfg(feature = "blocking")]
+    pub(crate) fn sleep(&self, duration: Duration) {
+        self.clock.sleep(duration);
+    }
+
+    /// Start the bucket as if `offset` had already elapsed
+    ///
+    /// The refill must be created with the same offset, so that it doesn't add tokens for that
+    /// time.
+    pub(crate) fn with_offset(self, offset: Duration) -> Self {
+        Self { offset, ..self }
+    }
+
+    /// Set the maximum number of compare-and-swap attempts before returning
+    /// [`Error::HighContention`]
+    pub(crate) fn with_max_retries(self, max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            ..self
+        }
+    }
+
+    /// Set the behaviour when compare-and-swap attempts keep failing
+    pub(crate) fn with_contention_strategy(self, contention_strategy: ContentionStrategy) -> Self {
+        Self {
+            contention_strategy,
+            ..self
+        }
+    }
+
+    /// Notify `metrics` of acquisitions and refills
+    pub(crate) fn with_metrics(self, metrics: Arc<dyn MetricsObserver + Send + Sync>) -> Self {
+        Self { metrics, ..self }
+    }
+
+    /// Read the maximum number of tokens from `max` instead of the refill configuration
+    pub(crate) fn with_shared_max(self, max: Arc<AtomicU64>) -> Self {
+        Self {
+            refill: Arc::new(SharedMaxRefill::new(self.refill, max)),
+            ..self
+        }
+    }
+
+    /// Include `name` in the tracing events of the bucket
+    #[cfg(feature = "tracing")]
+    pub(crate) fn with_trace_name(self, name: Arc<str>) -> Self {
+        Self {
+            trace_name: Some(name),
+            ..self
+        }
+    }
+
+    /// Observer notified of the activity of the bucket
+    pub(crate) fn metrics(&self) -> &dyn MetricsObserver {
+        self.metrics.as_ref()
+    }
+
+    /// Update `metrics` on acquisitions and refills from now on
+    ///
+    /// Returns [`prometheus::Error::AlreadyReg`] if metrics were already registered for this
+    /// bucket.
+    #[cfg(feature = "prometheus")]
+    pub(crate) fn set_prometheus(&self, metrics: BucketMetrics) -> prometheus::Result<()> {
+        metrics.set_level(self.available().into(), self.max());
+        self.prometheus
+            .set(Arc::new(metrics))
+            .map_err(|_| prometheus::Error::AlreadyReg)
+    }
+
+    /// Time elapsed since the start of the bucket
+    pub fn elapsed(&self) -> Duration {
+        self.offset + self.clock.now()
+    }
+
+    pub fn available(&self) -> TokenCount {
+        match self.available.load(Ordering::Acquire) {
+            // An optimistic decrement is being reverted, there weren't enough tokens for it
+            available if available > UNDERFLOW_GUARD => TokenCount::ZERO,
+            available => TokenCount(available),
+        }
+    }
+
+    /// Refill tokens if necessary, then return the number of available tokens
+    pub fn available_after_refill(&self) -> u64 {
+        self.refill(self.elapsed());
+        self.available().into()
+    }
+
+    /// Refill tokens if necessary, then return the number of available tokens and the elapsed
+    /// time used for the refill
+    ///
+    /// Both come from the same point in time, so that a bucket restored from them doesn't miss a
+    /// refill that was due but not applied yet.
+    pub fn snapshot(&self) -> (TokenCount, Duration) {
+        let elapsed = self.elapsed();
+        self.refill(elapsed);
+        (self.available(), elapsed)
+    }
+
+    /// Number of tokens currently borrowed from the credit line
+    pub fn debt(&self) -> u64 {
+        self.debt.load(Ordering::Acquire)
+    }
+
+    /// Duration until `requested` tokens will be available
+    ///
+    /// Tokens that are due are refilled first, so that this doesn't report a refill that already
+    /// happened.
+    pub fn wait_for(&self, requested: u64) -> Option<Duration> {
+        self.available_and_wait_for(requested).1
+    }
+
+    /// Refill tokens if necessary, then return the number of available tokens and the duration
+    /// until `requested` tokens are available
+    ///
+    /// Both come from the same refill, so that they describe the same state of the bucket.
+    pub fn available_and_wait_for(&self, requested: u64) -> (TokenCount, Option<Duration>) {
+        let elapsed = self.elapsed();
+        self.refill(elapsed);
+        let available = self.available();
+        (
+            available,
+            self.refill.wait_for(elapsed, available, requested.into()),
+        )
+    }
+
+    /// Maximum number of tokens in the bucket
+    pub fn max(&self) -> u64 {
+        self.refill.max()
+    }
+
+    /// Maximum number of tokens for a single [`Inner::try_acquire`], including the credit line
+    pub fn max_tokens_per_request(&self) -> u64 {
+        self.max().saturating_add(self.credit_limit)
+    }
+
+    /// Lifetime statistics of the bucket
+    pub fn stats(&self) -> BucketStats {
+        self.stats.snapshot()
     }
 
     pub fn try_acquire(&self, num: u64) -> Result<u64, Error> {
-        self.refill(self.start.elapsed());
+        self.try_acquire_above(num, 0)
+    }
+
+    /// Acquire `num` tokens, only if at least `min_available` tokens are available
+    ///
+    /// This is checked atomically with the acquisition, so that a permit granted above a
+    /// threshold can't be used once other threads took the bucket below that threshold.
+    pub fn try_acquire_above(&self, num: u64, min_available: u64) -> Result<u64, Error> {
+        // The number of available tokens is recorded by `acquire`, once tokens are refilled
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "bucket.try_acquire",
+            bucket = self.trace_name.as_deref(),
+            requested = num,
+            available = tracing::field::Empty,
+        )
+        .entered();
+
+        let result = self
+            .acquire(num, self.credit_limit, min_available)
+            .map(|_| num);
+        self.record_acquire(num, &result);
+        result
+    }
+
+    /// Acquire tokens for each `(num, min_available)` pair, as with [`Inner::try_acquire_above`]
+    /// in sequence
+    ///
+    /// When there are enough tokens for all acquisitions, they are acquired together with a single
+    /// compare-and-swap.
+    pub fn try_acquire_many(&self, requests: &[(u64, u64)]) -> Vec<Result<u64, Error>> {
+        self.refill(self.elapsed());
+
+        let max = self.max();
+        let available = self.available.load(Ordering::Acquire);
+        // Number of tokens left after each acquisition, if they all succeed
+        let remaining = requests
+            .iter()
+            .try_fold(available, |remaining, &(num, min_available)| {
+                (num <= max && remaining >= min_available)
+                    .then(|| remaining.checked_sub(num))
+                    .flatten()
+            });
+        if let Some(remaining) = remaining {
+            if available <= UNDERFLOW_GUARD
+                && self
+                    .available
+                    .compare_exchange(available, remaining, Ordering::AcqRel, Ordering::Acquire)
+                    .is_ok()
+            {
+                return requests
+                    .iter()
+                    .map(|&(num, _)| {
+                        let result = Ok(num);
+                        self.record_acquire(num, &result);
+                        result
+                    })
+                    .collect();
+            }
+        }
+
+        requests
+            .iter()
+            .map(|&(num, min_available)| self.try_acquire_above(num, min_available))
+            .collect()
+    }
+
+    /// Acquire `num` tokens, borrowing the missing tokens as long as the total debt stays within
+    /// `max_overdraft`
+    ///
+    /// Fails without borrowing if fewer than `min_available` tokens are available. Returns the
+    /// number of tokens borrowed.
+    pub fn try_overdraft(
+        &self,
+        num: u64,
+        max_overdraft: u64,
+        min_available: u64,
+    ) -> Result<u64, Error> {
+        let result = self.acquire(num, max_overdraft, min_available);
+        self.record_acquire(num, &result.clone().map(|_| num));
+        result
+    }
+
+    /// Acquire `num` tokens, borrowing the missing tokens as long as the total debt stays within
+    /// `credit_limit`
+    ///
+    /// Fails with [`Error::NotEnoughTokens`] if fewer than `min_available` tokens are available,
+    /// without borrowing. Returns the number of tokens borrowed.
+    fn acquire(&self, num: u64, credit_limit: u64, min_available: u64) -> Result<u64, Error> {
+        if num > self.max().saturating_add(credit_limit) {
+            return Err(Error::ExceedMaxTokens);
+        }
+
+        self.refill(self.elapsed());
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("available", u64::from(self.available()));
+
+        // Optimistic decrement
+        //
+        // In the uncontended case, a single `fetch_sub` is enough to acquire the tokens. If there
+        // weren't enough tokens, the counter wrapped around and the tokens are added back before
+        // falling back to the compare-and-swap loop. This is skipped when there already don't
+        // seem to be enough tokens, as wrapping around makes other threads spin until the tokens
+        // are added back, and can exhaust their attempts on an empty bucket.
+        //
+        // The counter must wrap around whenever the decrement fails, so that `fill`, `drain` and
+        // refills wait for the tokens to be added back instead of overwriting a count they would
+        // be added back to. This only holds when `min_available` doesn't exceed `num`: otherwise,
+        // a failed decrement could leave a valid count behind, so these acquisitions always go
+        // through the compare-and-swap loop.
+        let available = self.available.load(Ordering::Acquire);
+        if min_available <= num && available >= num && available <= UNDERFLOW_GUARD {
+            let previous = self.available.fetch_sub(num, Ordering::AcqRel);
+            if previous >= num && previous <= UNDERFLOW_GUARD {
+                return Ok(0);
+            }
+            self.available.fetch_add(num, Ordering::AcqRel);
+        }
 
         // Compare-and-swap loop
         //
         // If there aren't enough tokens available, this will break early.
         // If there are enough tokens, but the number of available tokens is updated before this
-        // call can, it will loop until it can, or it tried 65536 times.
-        for _ in 0..0x10000 {
+        // call can, it will loop until it can, or it tried `max_retries` times.
+        let mut guard = None;
+        for attempt in 0..self.max_retries {
+            if guard.is_none() {
+                guard = self.serialize(attempt);
+            }
             let available = self.available.load(Ordering::Acquire);
+
+            // Another thread is in the middle of reverting an optimistic decrement
+            if available > UNDERFLOW_GUARD {
+                continue;
+            }
+
+            // Other threads took the bucket below the threshold the permit was granted at
+            if available < min_available {
+                return Err(Error::NotEnoughTokens {
+                    available,
+                    wait_for: self.refill.wait_for(
+                        self.elapsed(),
+                        available.into(),
+                        min_available.into(),
+                    ),
+                });
+            }
+
             if available < num {
-                return Err(Error::NotEnoughTokens(self.refill.wait_for(available, num)));
+                // Borrow the missing tokens from the credit line, if possible
+                let shortfall = num - available;
+                if !self.borrow(shortfall, credit_limit) {
+                    return Err(Error::NotEnoughTokens {
+                        available,
+                        wait_for: self.refill.wait_for(
+                            self.elapsed(),
+                            available.into(),
+                            num.into(),
+                        ),
+                    });
+                }
+
+                if self
+                    .available
+                    .compare_exchange(available, 0, Ordering::AcqRel, Ordering::Acquire)
+                    .is_ok()
+                {
+                    return Ok(shortfall);
+                }
+
+                // The number of available tokens changed, give back the borrowed tokens and retry
+                self.debt.fetch_sub(shortfall, Ordering::AcqRel);
+                continue;
             }
 
             let new = available.saturating_sub(num);
@@ -50,18 +424,403 @@ impl Inner {
                 .available
                 .compare_exchange(available, new, Ordering::AcqRel, Ordering::Acquire)
                 .is_ok()
+            {
+                return Ok(0);
+            }
+        }
+
+        // Could not update the number of available tokens after `max_retries` attempts. Contention
+        // is too high, return an error instead of trying ad infinitum.
+        Err(Error::HighContention)
+    }
+
+    /// Acquire as many tokens as available, up to `max_num`
+    ///
+    /// Returns the number of tokens acquired, which can be zero, or [`Error::NotEnoughTokens`] if
+    /// fewer than `min_available` tokens are available.
+    pub fn try_acquire_partial(&self, max_num: u64, min_available: u64) -> Result<u64, Error> {
+        self.try_acquire_range(0, max_num, min_available)
+    }
+
+    /// Acquire as many tokens as available, at least `min_num` and up to `max_num`
+    ///
+    /// Returns the number of tokens acquired, or [`Error::NotEnoughTokens`] if fewer than
+    /// `min_num` or `min_available` tokens are available.
+    pub fn try_acquire_range(
+        &self,
+        min_num: u64,
+        max_num: u64,
+        min_available: u64,
+    ) -> Result<u64, Error> {
+        let result = self.acquire_range(min_num, max_num, min_available);
+        self.record_acquire(max_num, &result);
+        result
+    }
+
+    fn acquire_range(&self, min_num: u64, max_num: u64, min_available: u64) -> Result<u64, Error> {
+        if min_num > self.max() {
+            return Err(Error::ExceedMaxTokens);
+        }
+
+        self.refill(self.elapsed());
+
+        let mut guard = None;
+        for attempt in 0..self.max_retries {
+            if guard.is_none() {
+                guard = self.serialize(attempt);
+            }
+            let available = self.available.load(Ordering::Acquire);
+
+            // Another thread is in the middle of reverting an optimistic decrement
+            if available > UNDERFLOW_GUARD {
+                continue;
+            }
+
+            // Either fewer tokens than required, or other threads took the bucket below the
+            // threshold the permit was granted at
+            let required = min_num.max(min_available);
+            if available < required {
+                return Err(Error::NotEnoughTokens {
+                    available,
+                    wait_for: self.refill.wait_for(
+                        self.elapsed(),
+                        available.into(),
+                        required.into(),
+                    ),
+                });
+            }
+
+            let num = available.min(max_num);
+            if num == 0 {
+                return Ok(0);
+            }
+
+            if self
+                .available
+                .compare_exchange(
+                    available,
+                    available - num,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                )
+                .is_ok()
             {
                 return Ok(num);
             }
         }
 
-        // Could not update the number of available tokens after 65536 attempts. Contention is too,
-        // return an error instead of trying ad infinitum.
         Err(Error::HighContention)
     }
 
+    /// Take the contention lock if `attempt` compare-and-swap attempts already failed
+    ///
+    /// Other threads that reached the threshold wait for the lock instead of spinning, so the
+    /// remaining attempts mostly compete with threads still on the lock-free path.
+    fn serialize(&self, attempt: u32) -> Option<MutexGuard<'_, ()>> {
+        match self.contention_strategy {
+            ContentionStrategy::MutexFallback { threshold } if attempt == u32::from(threshold) => {
+                // The lock doesn't protect any data, poisoning is irrelevant
+                Some(
+                    self.contention_mutex
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner()),
+                )
+            }
+            _ => None,
+        }
+    }
+
+    /// Give back `num` tokens to the bucket, without exceeding the maximum number of tokens
+    ///
+    /// Returns the new number of available tokens.
+    pub fn release(&self, num: u64) -> Result<u64, Error> {
+        let max = self.max();
+        let (_, available) = self
+            .update_available(|available| available.saturating_add(num).min(max.max(available)))?;
+        self.repay_debt();
+        Ok(available)
+    }
+
+    /// Duration until the next refill adds tokens, if known
+    pub fn next_refill_in(&self) -> Option<Duration> {
+        self.refill.next_refill_in(self.elapsed())
+    }
+
+    /// Restart the refill schedule, without changing the number of available tokens
+    pub fn reset_refill(&self) {
+        self.refill.reset(self.elapsed());
+    }
+
+    /// Change the refill rate, restarting the refill schedule
+    ///
+    /// Tokens due with the previous rate are added first.
+    pub fn set_rate(&self, quantity: u64, interval: Duration) -> Result<(), Error> {
+        let elapsed = self.elapsed();
+        let added = self
+            .refill
+            .set_rate(elapsed, &self.available, quantity.into(), interval)?;
+        self.record_refill(elapsed, added);
+        Ok(())
+    }
+
+    /// Average number of tokens added per second, if the refill has a constant rate
+    pub fn effective_rate(&self) -> Option<f64> {
+        self.refill.effective_rate_per_second()
+    }
+
+    /// Remove all available tokens from the bucket
+    ///
+    /// Returns the number of tokens removed.
+    pub fn drain(&self) -> Result<u64, Error> {
+        let (previous, _) = self.update_available(|_| 0)?;
+        Ok(previous)
+    }
+
+    /// Refill tokens if necessary, then remove all available tokens
+    ///
+    /// Returns the number of tokens removed and the elapsed time used for the refill, like
+    /// [`snapshot`](Self::snapshot).
+    pub fn drain_to_snapshot(&self) -> Result<(u64, Duration), Error> {
+        let elapsed = self.elapsed();
+        self.refill(elapsed);
+        let (previous, _) = self.update_available(|_| 0)?;
+        Ok((previous, elapsed))
+    }
+
+    /// Restore the initial number of tokens, clear the debt, and restart the refill schedule
+    pub fn reset(&self) -> Result<(), Error> {
+        self.refill.reset(self.elapsed());
+        self.update_available(|_| self.initial)?;
+        self.debt.store(0, Ordering::Release);
+        Ok(())
+    }
+
+    /// Set the number of available tokens to the maximum
+    ///
+    /// Returns the number of tokens added.
+    pub fn fill(&self) -> Result<u64, Error> {
+        let max = self.max();
+        let (previous, new) = self.update_available(|available| max.max(available))?;
+        Ok(new - previous)
+    }
+
+    /// Atomically update the number of available tokens
+    ///
+    /// Returns the previous and new number of available tokens.
+    fn update_available<F>(&self, f: F) -> Result<(u64, u64), Error>
+    where
+        F: Fn(u64) -> u64,
+    {
+        for _ in 0..self.max_retries {
+            let available = self.available.load(Ordering::Acquire);
+
+            // Another thread is in the middle of reverting an optimistic decrement
+            if available > UNDERFLOW_GUARD {
+                continue;
+            }
+
+            let new = f(available);
+            if self
+                .available
+                .compare_exchange(available, new, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                return Ok((available, new));
+            }
+        }
+
+        Err(Error::HighContention)
+    }
+
+    /// Record the result of an acquisition in the stats and metrics
+    fn record_acquire(&self, requested: u64, result: &Result<u64, Error>) {
+        self.stats.record_acquire(result);
+        match result {
+            Ok(num) => self.metrics.on_acquire(*num),
+            Err(Error::HighContention) => self.metrics.on_contention(),
+            Err(_) => (),
+        }
+        #[cfg(feature = "prometheus")]
+        if let Some(prometheus) = self.prometheus.get() {
+            prometheus.record_acquire(result);
+            prometheus.set_level(self.available().into(), self.max());
+        }
+        #[cfg(feature = "tracing")]
+        self.trace_acquire(requested, result);
+        #[cfg(not(feature = "tracing"))]
+        let _ = requested;
+    }
+
+    /// Emit a tracing event for the result of an acquisition
+    ///
+    /// Denials are logged at the `INFO` level and contention at the `WARN` level, as they are
+    /// more relevant than successful acquisitions.
+    #[cfg(feature = "tracing")]
+    fn trace_acquire(&self, requested: u64, result: &Result<u64, Error>) {
+        let bucket = self.trace_name.as_deref();
+        match result {
+            Ok(acquired) => tracing::debug!(
+                bucket,
+                acquired,
+                remaining = u64::from(self.available()),
+                "acquire"
+            ),
+            Err(Error::NotEnoughTokens { wait_for, .. }) => tracing::info!(
+                bucket,
+                requested,
+                denied = true,
+                wait_for_ms = ?wait_for.map(|wait_for| wait_for.as_millis() as u64),
+                "acquire"
+            ),
+            Err(Error::HighContention) => tracing::warn!(
+                bucket,
+                requested,
+                contention = true,
+                retries = self.max_retries,
+                "acquire"
+            ),
+            Err(err) => tracing::debug!(bucket, requested, error = %err, "acquire"),
+        }
+    }
+
     /// Refill tokens if necessary
     fn refill(&self, elapsed: Duration) {
-        self.refill.refill(elapsed, &self.available)
+        let added = self.refill.refill(elapsed, &self.available);
+        self.record_refill(elapsed, added);
+    }
+
+    /// Record `added` tokens from a refill at `elapsed`, then repay debt with them
+    fn record_refill(&self, elapsed: Duration, added: u64) {
+        if added > 0 {
+            self.stats.record_refill();
+            self.metrics.on_refill(added);
+            let observers = self
+                .refill_observers
+                .read()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if !observers.is_empty() {
+                let new_available = self.available().into();
+                let next_refill_in = self.refill.next_refill_in(elapsed);
+                for observer in observers.iter() {
+                    observer.on_refill(added, new_available, next_refill_in);
+                }
+            }
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                bucket = self.trace_name.as_deref(),
+                tokens_added = added,
+                new_available = u64::from(self.available()),
+                "refill"
+            );
+        }
+        self.repay_debt();
+        #[cfg(feature = "prometheus")]
+        if let Some(prometheus) = self.prometheus.get() {
+            prometheus.set_level(self.available().into(), self.max());
+        }
+    }
+
+    /// Notify `observer` of every refill adding tokens
+    pub fn observe_refill(&self, observer: Arc<dyn RefillObserver + Send + Sync>) {
+        // Observers are only ever pushed, a panic can't leave the list inconsistent
+        self.refill_observers
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(observer);
+    }
+
+    /// Borrow `num` tokens from the credit line
+    ///
+    /// Returns `false` if this would exceed `credit_limit`.
+    fn borrow(&self, num: u64, credit_limit: u64) -> bool {
+        self.debt
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |debt| {
+                debt.checked_add(num).filter(|&debt| debt <= credit_limit)
+            })
+            .is_ok()
+    }
+
+    /// Use available tokens to repay the debt
+    ///
+    /// While there is debt, refilled tokens go towards repaying it before they become available.
+    fn repay_debt(&self) {
+        for _ in 0..self.max_retries {
+            let available = u64::from(self.available());
+            if available == 0 {
+                return;
+            }
+
+            let Ok(debt) = self
+                .debt
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |debt| {
+                    (debt > 0).then(|| debt - debt.min(available))
+                })
+            else {
+                // No debt to repay
+                return;
+            };
+
+            let amount = debt.min(available);
+            if self
+                .available
+                .compare_exchange(
+                    available,
+                    available - amount,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                )
+                .is_ok()
+            {
+                return;
+            }
+
+            // The number of available tokens changed, restore the debt and retry
+            self.debt.fetch_add(amount, Ordering::AcqRel);
+        }
+    }
+}
+
+/// Shared pool of tokens of a [`Bucket`](crate::Bucket), without its permitter
+///
+/// This is returned by [`Bucket::inner`](crate::Bucket::inner), for advanced use cases such as
+/// distributed coordinators that need to take tokens from the pool with their own logic.
+///
+/// Acquisitions bypass the permitter of the bucket, so they skip threshold enforcement and any
+/// other permit logic, such as priorities or fairness. Refills, stats and metrics still apply.
+#[derive(Clone)]
+pub struct BucketInner {
+    inner: Arc<Inner>,
+}
+
+impl BucketInner {
+    pub(crate) fn new(inner: Arc<Inner>) -> Self {
+        Self { inner }
+    }
+
+    /// Number of tokens available, without refilling first
+    pub fn available(&self) -> u64 {
+        self.inner.available().into()
+    }
+
+    /// Maximum number of tokens in the bucket
+    pub fn capacity(&self) -> u64 {
+        self.inner.max()
+    }
+
+    /// Try to acquire `num` tokens, without a permit
+    ///
+    /// Returns the number of tokens acquired. See [`Bucket::try_acquire`](crate::Bucket::try_acquire)
+    /// for the errors.
+    pub fn try_acquire(&self, num: u64) -> Result<u64, Error> {
+        self.inner.try_acquire(num)
+    }
+}
+
+impl fmt::Debug for BucketInner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BucketInner")
+            .field("available", &self.inner.available())
+            .field("capacity", &self.inner.max())
+            .finish_non_exhaustive()
     }
 }
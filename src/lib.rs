@@ -1,48 +1,424 @@
 #![warn(missing_docs)]
 #![doc = include_str!("../README.md")]
 
-use std::{sync::Arc, time::Duration};
+#[cfg(all(feature = "tokio", feature = "async-std"))]
+compile_error!("the `tokio` and `async-std` features are mutually exclusive");
 
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+use std::pin::Pin;
+#[cfg(feature = "tokio")]
+use std::task::{Context, Poll};
+use std::{
+    fmt, io,
+    ops::{Bound, RangeBounds},
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+mod background;
 mod builder;
+pub mod clock;
+#[cfg(feature = "serde")]
+mod config;
+mod contention;
+mod cost;
+#[cfg(feature = "tokio")]
+pub mod dedup;
+mod denomination;
+#[cfg(feature = "prometheus")]
+mod exporter;
+mod guard;
+#[cfg(feature = "http")]
+mod headers;
+mod hierarchy;
+#[cfg(feature = "http")]
+pub mod http;
 mod inner;
+pub mod limiter;
+pub mod map;
+pub mod metrics;
+#[cfg(feature = "tower")]
+pub mod middleware;
+mod multi;
+pub mod observer;
+mod overdraft;
+mod parse;
 pub mod permit;
+mod read_write;
 pub mod refill;
+#[cfg(feature = "blocking")]
+mod retry;
+mod snapshot;
+mod stats;
+#[cfg(all(feature = "tokio", feature = "futures"))]
+mod stream;
+mod tokens;
 
-pub use builder::Builder;
-use permit::{Permit, Permitter};
+pub use builder::{Builder, MaxSet, NoMax, NoRefill, RefillReady};
+#[cfg(feature = "serde")]
+pub use config::BucketConfig;
+pub use contention::ContentionStrategy;
+pub use cost::CostMap;
+use denomination::Denomination;
+pub use guard::TokenGuard;
+#[cfg(feature = "http")]
+pub use headers::BucketHeaders;
+pub use hierarchy::ChildBucket;
+pub use inner::BucketInner;
+pub use multi::try_acquire_all_or_nothing;
+pub use overdraft::OverdraftToken;
+use permit::{BatchPermit, Permit, Permitter};
+pub use read_write::ReadWriteBucket;
+#[cfg(feature = "blocking")]
+pub use retry::RetryConfig;
+pub use snapshot::BucketSnapshot;
+pub use stats::BucketStats;
+#[cfg(all(feature = "tokio", feature = "futures"))]
+pub use stream::AcquireStream;
+pub use tokens::TokenCount;
 
 /// # Leaky bucket with permitter
 #[derive(Clone)]
 pub struct Bucket {
     permitter: Arc<dyn Permitter>,
     inner: Arc<inner::Inner>,
+    denomination: Arc<Denomination>,
+    /// Token costs of named operations, for [`Bucket::try_acquire_for_op`]
+    costs: Arc<CostMap>,
+    /// Configuration the bucket was built with
+    config: Arc<Builder>,
+    /// Name of this handle to the bucket, set with [`Bucket::with_name`]
+    name: Option<Arc<str>>,
+}
+
+impl fmt::Debug for Bucket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bucket")
+            .field("name", &self.name)
+            .field("available", &self.inner.available())
+            .field("capacity", &self.capacity())
+            .field("debt", &self.inner.debt())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Human-readable fill level, such as `Bucket(api: 42/200 tokens, threshold: 100)`
+///
+/// The name is omitted if the bucket doesn't have one, and the threshold if the permitter doesn't
+/// have one.
+impl fmt::Display for Bucket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Bucket(")?;
+        if let Some(name) = &self.name {
+            write!(f, "{name}: ")?;
+        }
+        write!(f, "{}/{} tokens", self.available(), self.capacity())?;
+        if let Some(threshold) = self.threshold() {
+            write!(f, ", threshold: {threshold}")?;
+        }
+        f.write_str(")")
+    }
 }
 
 impl Bucket {
     /// Create a new [`Builder`].
-    pub fn builder() -> Builder {
-        Builder::default()
+    pub fn builder() -> Builder<NoRefill, NoMax> {
+        Builder::new()
     }
 
     /// Number of tokens available in the [`Bucket`].
     pub fn available(&self) -> u64 {
-        self.inner.available()
+        self.inner.available().into()
+    }
+
+    /// Maximum number of tokens in the [`Bucket`].
+    ///
+    /// This is the `max` of the refill configuration, such as
+    /// [`RateConfig::max`](refill::RateConfig::max), named after the capacity of the bucket to
+    /// avoid confusion with [`Ord::max`].
+    pub fn capacity(&self) -> u64 {
+        self.inner.max()
+    }
+
+    /// Maximum number of tokens in the [`Bucket`].
+    ///
+    /// Synonym for [`Bucket::capacity`], matching the name of the refill configuration field.
+    pub fn max(&self) -> u64 {
+        self.capacity()
+    }
+
+    /// Maximum number of tokens a single [`Bucket::try_acquire`] can request.
+    ///
+    /// This is the maximum number of tokens, plus the [`Builder::credit_limit`] if set. Larger
+    /// requests always fail with [`Error::ExceedMaxTokens`], and should be split up instead.
+    pub fn max_tokens_per_request(&self) -> u64 {
+        self.inner.max_tokens_per_request()
+    }
+
+    /// Number of tokens missing for the [`Bucket`] to be full.
+    ///
+    /// This is `capacity() - available()`, or `0` if more tokens are available than the capacity.
+    pub fn used(&self) -> u64 {
+        self.capacity().saturating_sub(self.available())
+    }
+
+    /// Minimum number of available tokens to grant a [`Permit`], if the permitter has one.
+    ///
+    /// For [`Builder::priority`], this is the threshold for callers without a priority.
+    pub fn threshold(&self) -> Option<u64> {
+        self.permitter.threshold()
+    }
+
+    /// Fraction of the maximum number of tokens currently available, between `0.0` and `1.0`.
+    pub fn utilization(&self) -> f64 {
+        match self.capacity() {
+            0 => 0.0,
+            max => (self.available() as f64 / max as f64).clamp(0.0, 1.0),
+        }
+    }
+
+    /// Whether the [`Bucket`] has no tokens available.
+    pub fn is_empty(&self) -> bool {
+        self.available() == 0
+    }
+
+    /// Whether the [`Bucket`] has its maximum number of tokens available.
+    pub fn is_full(&self) -> bool {
+        self.available() >= self.max()
+    }
+
+    /// Number of tokens borrowed from the credit line of the [`Bucket`].
+    ///
+    /// See [`Builder::credit_limit`].
+    pub fn debt(&self) -> u64 {
+        self.inner.debt()
+    }
+
+    /// Number of tokens available in the [`Bucket`], after applying any pending refill.
+    ///
+    /// Unlike [`Bucket::available`], this accounts for tokens that should have been added since
+    /// the last acquisition.
+    pub fn available_after_refill(&self) -> u64 {
+        self.inner.available_after_refill()
+    }
+
+    /// Number of tokens available in the [`Bucket`], in the configured denomination.
+    ///
+    /// For example, this returns `"47 API calls"` for a bucket with 47 tokens available and a
+    /// denomination of `("API calls", 1)`.
+    pub fn available_human(&self) -> String {
+        self.denomination.format(self.available())
+    }
+
+    /// Maximum number of tokens in the [`Bucket`], in the configured denomination.
+    pub fn capacity_human(&self) -> String {
+        self.denomination.format(self.capacity())
+    }
+
+    /// Shared pool of tokens of the [`Bucket`], to acquire tokens without permits.
+    ///
+    /// Acquisitions through the returned [`BucketInner`] bypass the permitter, and so skip
+    /// threshold enforcement. They still take tokens from this bucket and all its clones.
+    pub fn inner(&self) -> Arc<BucketInner> {
+        Arc::new(BucketInner::new(self.inner.clone()))
     }
 
     /// Try to acquire a [`Permit`].
+    #[deprecated(note = "use `Bucket::try_permit` instead")]
     pub fn get_permit(&self) -> Option<Permit> {
-        self.permitter.get_permit()
+        self.try_permit().ok()
+    }
+
+    /// Try to acquire a [`Permit`], returning [`Error::NotEnoughTokens`] if it cannot be granted.
+    ///
+    /// When the permitter refuses permits below a threshold, the error includes the duration
+    /// until the bucket should be back above it.
+    pub fn try_permit(&self) -> Result<Permit, Error> {
+        self.observe_permit(self.permitter.get_permit())
+            .ok_or_else(|| Error::NotEnoughTokens {
+                available: self.inner.available_after_refill(),
+                wait_for: self.permitter.wait_for(),
+            })
+    }
+
+    /// Try to acquire a [`Permit`], returning why it was denied if it cannot be granted.
+    ///
+    /// This returns [`CheckedPermitError::BelowThreshold`] when the permitter has a threshold and
+    /// fewer tokens are available, so that callers can tell threshold rejections apart from other
+    /// denials, such as by a fair or custom permitter.
+    pub fn try_permit_checked(&self) -> Result<Permit, CheckedPermitError> {
+        let (permit, denied_at) = match self.permitter.get_permit_checked() {
+            Ok(permit) => (Some(permit), None),
+            Err(denied_at) => (None, denied_at),
+        };
+        if let Some(permit) = self.observe_permit(permit) {
+            return Ok(permit);
+        }
+
+        // Report the number of tokens the permitter refused the permit at, if it tells
+        let available = denied_at.unwrap_or_else(|| self.inner.available_after_refill());
+        let wait_for = self.permitter.wait_for();
+        match self.permitter.threshold() {
+            Some(threshold) if available < threshold => Err(CheckedPermitError::BelowThreshold {
+                available,
+                threshold,
+                wait_for,
+            }),
+            _ => Err(CheckedPermitError::Denied {
+                available,
+                wait_for,
+            }),
+        }
+    }
+
+    /// Notify the metrics observer of whether a [`Permit`] was granted.
+    fn observe_permit(&self, permit: Option<Permit>) -> Option<Permit> {
+        let metrics = self.inner.metrics();
+        match permit {
+            Some(_) => metrics.on_permit_granted(),
+            None => metrics.on_permit_denied(self.permitter.wait_for()),
+        }
+        permit
+    }
+
+    /// Try to acquire a [`Permit`] for a caller with the given `priority`, returning
+    /// [`Error::NotEnoughTokens`] if it cannot be granted.
+    ///
+    /// Priorities are only taken into account by
+    /// [`Builder::priority`](crate::Builder::priority) and custom permitters. Other permitters
+    /// treat all callers the same.
+    pub fn try_permit_with_priority(&self, priority: u8) -> Result<Permit, Error> {
+        self.observe_permit(self.permitter.get_permit_with_priority(priority))
+            .ok_or_else(|| Error::NotEnoughTokens {
+                available: self.inner.available_after_refill(),
+                wait_for: self.permitter.wait_for(),
+            })
+    }
+
+    /// Try to acquire a [`Permit`] for the caller identified by `caller_id`, returning
+    /// [`Error::NotEnoughTokens`] if it cannot be granted.
+    ///
+    /// Callers are only taken into account by [`Builder::fair`] and custom permitters. Use
+    /// [`permit::caller_id`] to derive an identifier from a name.
+    pub fn try_permit_for(&self, caller_id: u64) -> Result<Permit, Error> {
+        self.observe_permit(self.permitter.get_permit_for(caller_id))
+            .ok_or_else(|| Error::NotEnoughTokens {
+                available: self.inner.available_after_refill(),
+                wait_for: self.permitter.wait_for(),
+            })
+    }
+
+    /// Try to acquire a [`Permit`] for a request with the given `weight`, returning
+    /// [`Error::NotEnoughTokens`] if it cannot be granted.
+    ///
+    /// Weights are only taken into account by [`Builder::weighted_threshold`] and custom
+    /// permitters. Other permitters treat all requests the same.
+    pub fn try_permit_weighted(&self, weight: u64) -> Result<Permit, Error> {
+        self.observe_permit(self.permitter.get_permit_weighted(weight))
+            .ok_or_else(|| Error::NotEnoughTokens {
+                available: self.inner.available_after_refill(),
+                wait_for: self.permitter.wait_for(),
+            })
+    }
+
+    /// Check that `permit` hasn't expired and was granted by this [`Bucket`]'s permitter.
+    pub(crate) fn check_permit(&self, permit: &Permit) -> Result<(), Error> {
+        if !permit.is_valid() {
+            return Err(Error::ExpiredPermit);
+        }
+        if !self.permitter.belongs(permit) {
+            return Err(Error::InvalidPermit);
+        }
+        Ok(())
+    }
+
+    /// Change the threshold under which the [`Bucket`] refuses to grant new permits.
+    ///
+    /// Permits granted before the change keep the threshold they were granted at. This returns
+    /// [`BuildError::ThresholdExceedsMax`] if `new_threshold` isn't lower than the maximum number
+    /// of tokens, and [`BuildError::UnsupportedPermitter`] if the bucket doesn't use
+    /// threshold-based permits, such as [`Builder::priority`] or without a threshold. See
+    /// [`Bucket::clone_with_new_threshold`] to use a different threshold for some callers only.
+    pub fn set_threshold(&self, new_threshold: u64) -> Result<(), BuildError> {
+        let max = self.inner.max();
+        if new_threshold >= max {
+            return Err(BuildError::ThresholdExceedsMax {
+                threshold: new_threshold,
+                max,
+            });
+        }
+        self.permitter.set_threshold(new_threshold)
+    }
+
+    /// Create a [`Bucket`] sharing the same tokens, but granting permits with a different
+    /// threshold.
+    ///
+    /// Both buckets compete over the same tokens and refill schedule, which is useful to lower the
+    /// threshold of a tenant temporarily without losing the accumulated tokens. The new bucket
+    /// always uses threshold-based permits, whatever permitter the original bucket uses.
+    ///
+    /// This returns [`BuildError::ThresholdExceedsMax`] if `new_threshold` isn't lower than the
+    /// maximum number of tokens.
+    pub fn clone_with_new_threshold(&self, new_threshold: u64) -> Result<Bucket, BuildError> {
+        let max = self.inner.max();
+        if new_threshold >= max {
+            return Err(BuildError::ThresholdExceedsMax {
+                threshold: new_threshold,
+                max,
+            });
+        }
+        let config = permit::ThresholdConfig {
+            threshold: new_threshold.into(),
+        };
+        Ok(Bucket {
+            permitter: Arc::new(config.clone().into_permitter(self.inner.clone())),
+            inner: self.inner.clone(),
+            denomination: self.denomination.clone(),
+            costs: self.costs.clone(),
+            name: self.name.clone(),
+            config: Arc::new(self.builder_from_current().threshold(config)),
+        })
+    }
+
+    /// Create a [`Builder`] with the configuration this [`Bucket`] was built with.
+    ///
+    /// Custom refills and permitters are shared with this bucket, as with [`BucketMap`](map::BucketMap).
+    pub fn builder_from_current(&self) -> Builder {
+        Builder::clone(&self.config)
+    }
+
+    /// Create a new [`Bucket`] with the same configuration, but its own tokens.
+    ///
+    /// Unlike [`Clone::clone`], acquiring tokens from the new bucket doesn't affect this one. The
+    /// new bucket starts with the initial number of tokens of this bucket, not the number of
+    /// tokens currently available.
+    pub fn clone_isolated(&self) -> Bucket {
+        self.builder_from_current().build_validated()
+    }
+
+    /// Try to acquire a [`BatchPermit`], covering `count` token acquisitions.
+    pub fn get_batch_permit(&self, count: usize) -> Option<BatchPermit> {
+        self.try_permit()
+            .ok()
+            .map(|permit| BatchPermit::new(permit, count))
+    }
+
+    /// Get a [`Future`](std::future::Future) resolving to a [`Permit`] once one can be granted.
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    pub fn get_async_permit(&self) -> permit::AsyncPermit {
+        permit::AsyncPermit::new(Arc::downgrade(&self.permitter), Arc::downgrade(&self.inner))
     }
 
     /// Try to acquire one token.
     ///
     /// Shorthand for `try_acquire(permit, 1)`.
     pub fn try_acquire_one(&self, permit: Permit) -> Result<(), Error> {
-        if !self.permitter.belongs(&permit) {
-            return Err(Error::InvalidPermit);
-        }
+        self.check_permit(&permit)?;
+        let min_available = permit.min_available();
         permit.notify(1);
-        self.inner.try_acquire(1).map(|_| ())
+        self.inner.try_acquire_above(1, min_available).map(|_| ())
     }
 
     /// Try to acquire `num` number of tokens.
@@ -50,28 +426,669 @@ impl Bucket {
     /// This will return an [`Error`] if the permit is invalid, this tries to acquire more token
     /// than available, or it fails to swap the available number of tokens.
     pub fn try_acquire(&self, permit: Permit, num: u64) -> Result<u64, Error> {
-        if !self.permitter.belongs(&permit) {
-            return Err(Error::InvalidPermit);
-        }
+        self.check_permit(&permit)?;
+        let min_available = permit.min_available();
         permit.notify(num);
-        self.inner.try_acquire(num)
+        self.inner.try_acquire_above(num, min_available)
+    }
+
+    /// Try to acquire the tokens for the operation `op`, with its cost in the [`CostMap`] set with
+    /// [`Builder::with_cost_map`] or [`Builder::register_op_cost`].
+    ///
+    /// Operations that aren't registered cost the default cost of the map. This returns the
+    /// number of tokens acquired, as [`Bucket::try_acquire`].
+    pub fn try_acquire_for_op(&self, permit: Permit, op: &str) -> Result<u64, Error> {
+        self.try_acquire(permit, self.costs.cost(op))
+    }
+
+    /// Try to acquire tokens for each `(permit, num)` pair, in order.
+    ///
+    /// This has the same results as calling [`Bucket::try_acquire`] for each pair in sequence,
+    /// and each result is independent: an invalid permit or missing tokens only fail their own
+    /// acquisition. When there are enough tokens for all valid permits, the tokens are acquired
+    /// together, without interleaving with other threads.
+    pub fn try_acquire_many<I>(&self, requests: I) -> Vec<Result<u64, Error>>
+    where
+        I: IntoIterator<Item = (Permit, u64)>,
+    {
+        let checked: Vec<_> = requests
+            .into_iter()
+            .map(|(permit, num)| {
+                self.check_permit(&permit)?;
+                let min_available = permit.min_available();
+                permit.notify(num);
+                Ok((num, min_available))
+            })
+            .collect();
+
+        let valid: Vec<_> = checked
+            .iter()
+            .filter_map(|result| result.clone().ok())
+            .collect();
+        let mut acquired = self.inner.try_acquire_many(&valid).into_iter();
+        checked
+            .into_iter()
+            .map(|result| {
+                result.and_then(|_| acquired.next().expect("one acquisition per valid permit"))
+            })
+            .collect()
+    }
+
+    /// Get a [`Permit`] and use it to acquire a single token.
+    ///
+    /// This returns [`Error::NotEnoughTokens`] if the permitter doesn't grant a permit.
+    pub fn quick_acquire_one(&self) -> Result<(), Error> {
+        self.try_acquire_one(self.try_permit()?)
+    }
+
+    /// Get a [`Permit`] and use it to acquire `num` number of tokens.
+    ///
+    /// This returns [`Error::NotEnoughTokens`] if the permitter doesn't grant a permit.
+    pub fn quick_acquire(&self, num: u64) -> Result<u64, Error> {
+        self.try_acquire(self.try_permit()?, num)
+    }
+
+    /// Acquire `num` number of tokens, going into debt if there aren't enough tokens available.
+    ///
+    /// This succeeds as long as the total debt of the bucket stays within `max_overdraft`,
+    /// whatever [`Builder::credit_limit`] is set to. The debt is repaid by future refills before
+    /// tokens become available again. As with [`Bucket::try_acquire`], this doesn't borrow any
+    /// tokens if other threads took the bucket below the threshold the permit was granted at.
+    pub fn overdraft(
+        &self,
+        permit: Permit,
+        num: u64,
+        max_overdraft: u64,
+    ) -> Result<OverdraftToken, Error> {
+        self.check_permit(&permit)?;
+        let min_available = permit.min_available();
+        permit.notify(num);
+        self.inner
+            .try_overdraft(num, max_overdraft, min_available)
+            .map(OverdraftToken::new)
+    }
+
+    /// Try to acquire as many tokens as available, up to `max_num`.
+    ///
+    /// This returns the number of tokens acquired, or `Ok(0)` if no tokens are available. It will
+    /// return an [`Error`] if the permit is invalid, other threads took the bucket below the
+    /// threshold the permit was granted at, or it fails to swap the available number of tokens.
+    pub fn try_acquire_partial(&self, permit: Permit, max_num: u64) -> Result<u64, Error> {
+        self.check_permit(&permit)?;
+        let min_available = permit.min_available();
+        permit.notify(max_num);
+        self.inner.try_acquire_partial(max_num, min_available)
+    }
+
+    /// Try to acquire as many tokens as available within `range`.
+    ///
+    /// The start of the range is the minimum number of tokens required, and its end the maximum
+    /// number of tokens desired. This returns [`Error::NotEnoughTokens`] if fewer tokens than the
+    /// minimum are available, and otherwise acquires as many tokens as available, up to the
+    /// maximum. An empty range, such as `5..5`, doesn't acquire any tokens.
+    ///
+    /// ```rust
+    /// # use std::time::Duration;
+    /// # use threshold_bucket::{refill::RateConfig, Bucket, Error};
+    /// let bucket = Bucket::builder()
+    ///     .rate(RateConfig {
+    ///         quantity: 1.into(),
+    ///         interval: Duration::from_secs(60),
+    ///         max: 100.into(),
+    ///     })
+    ///     .initial(30)
+    ///     .build()?;
+    ///
+    /// // Acquire between 10 and 50 tokens
+    /// assert_eq!(bucket.try_acquire_range(bucket.try_permit()?, 10..=50)?, 30);
+    /// // Acquire up to 50 tokens, as `Bucket::try_acquire_partial`
+    /// assert_eq!(bucket.try_acquire_range(bucket.try_permit()?, ..=50)?, 0);
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn try_acquire_range<R: RangeBounds<u64>>(
+        &self,
+        permit: Permit,
+        range: R,
+    ) -> Result<u64, Error> {
+        let min_num = match range.start_bound() {
+            Bound::Included(&min) => min,
+            Bound::Excluded(&min) => min.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let max_num = match range.end_bound() {
+            Bound::Included(&max) => Some(max),
+            Bound::Excluded(&max) => max.checked_sub(1),
+            Bound::Unbounded => Some(u64::MAX),
+        };
+
+        self.check_permit(&permit)?;
+        let min_available = permit.min_available();
+        let max_num = match max_num {
+            Some(max_num) if max_num >= min_num => max_num,
+            // Empty range
+            _ => {
+                permit.notify(0);
+                return Ok(0);
+            }
+        };
+        permit.notify(max_num);
+        self.inner
+            .try_acquire_range(min_num, max_num, min_available)
+    }
+
+    /// Try to acquire `num` number of tokens, returning them to the bucket unless committed.
+    ///
+    /// See [`TokenGuard`].
+    pub fn acquire_guard(&self, permit: Permit, num: u64) -> Result<TokenGuard, Error> {
+        let tokens = self.try_acquire(permit, num)?;
+        Ok(TokenGuard::new(Arc::downgrade(&self.inner), tokens))
+    }
+
+    /// Refill the [`Bucket`] on schedule in a background task, instead of only when tokens are
+    /// acquired.
+    ///
+    /// This keeps [`Bucket::available`] up to date. The task stops on its own once all clones of
+    /// the bucket are dropped. Custom refills that don't implement
+    /// [`Refill::next_refill_in`](refill::Refill::next_refill_in) are refilled every second.
+    #[cfg(feature = "tokio")]
+    pub fn start_background_refill(
+        &self,
+        handle: &tokio::runtime::Handle,
+    ) -> tokio::task::JoinHandle<()> {
+        background::spawn(handle, &self.inner)
+    }
+
+    /// Refill the [`Bucket`] on schedule in a background task, instead of only when tokens are
+    /// acquired.
+    ///
+    /// This keeps [`Bucket::available`] up to date. The task is spawned with
+    /// [`async_std::task::spawn`], and stops on its own once all clones of the bucket are dropped.
+    #[cfg(all(feature = "async-std", not(feature = "tokio")))]
+    pub fn start_background_refill(&self) -> async_std::task::JoinHandle<()> {
+        background::spawn(&self.inner)
+    }
+
+    /// Acquire `num` number of tokens, sleeping until they are available.
+    ///
+    /// When there aren't enough tokens, this sleeps for the duration hinted by
+    /// [`Error::NotEnoughTokens`], waits for a new [`Permit`], then tries again. Other errors,
+    /// such as [`Error::InvalidPermit`] or [`Error::ExceedMaxTokens`], are returned immediately.
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    pub async fn wait_acquire(&self, permit: Permit, num: u64) -> Result<u64, Error> {
+        let mut permit = permit;
+        loop {
+            match self.try_acquire(permit, num) {
+                Err(Error::NotEnoughTokens {
+                    wait_for: Some(wait_for),
+                    ..
+                }) => {
+                    background::sleep(wait_for).await;
+                    permit = self.get_async_permit().await?;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Acquire `num` number of tokens, sleeping until they are available, as an owned future.
+    ///
+    /// This behaves as [`Bucket::wait_acquire`], but the future holds a clone of the [`Bucket`]
+    /// instead of borrowing it, and is boxed, so that it can be stored with other futures, e.g. in
+    /// a `Vec` passed to `futures::future::join_all`, or spawned as a task.
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    pub fn try_acquire_or_wait_async(
+        &self,
+        permit: Permit,
+        num: u64,
+    ) -> Pin<Box<dyn std::future::Future<Output = Result<u64, Error>> + Send>> {
+        let bucket = self.clone();
+        Box::pin(async move { bucket.wait_acquire(permit, num).await })
+    }
+
+    /// Acquire `num` number of tokens, blocking the current thread until they are available.
+    ///
+    /// When there aren't enough tokens, this sleeps for the duration hinted by
+    /// [`Error::NotEnoughTokens`], gets a new [`Permit`], then tries again. If `timeout` is set
+    /// and elapses first, the last [`Error::NotEnoughTokens`] is returned. Other errors are
+    /// returned immediately.
+    #[cfg(feature = "blocking")]
+    pub fn acquire_blocking(
+        &self,
+        permit: Permit,
+        num: u64,
+        timeout: Option<Duration>,
+    ) -> Result<u64, Error> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut result = self.try_acquire(permit, num);
+        loop {
+            match result {
+                Err(Error::NotEnoughTokens {
+                    wait_for: Some(wait_for),
+                    ..
+                }) => {
+                    let wait_for = match deadline {
+                        Some(deadline) => {
+                            let now = Instant::now();
+                            if now >= deadline {
+                                return result;
+                            }
+                            wait_for.min(deadline - now)
+                        }
+                        None => wait_for,
+                    };
+                    std::thread::sleep(wait_for);
+                    result = self
+                        .try_permit()
+                        .and_then(|permit| self.try_acquire(permit, num));
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Acquire `num` number of tokens, retrying with exponential backoff as set by `config`.
+    ///
+    /// When there aren't enough tokens, or under high contention, this waits for the next backoff
+    /// of `config` with [`Clock::sleep`](clock::Clock::sleep) on the clock of the bucket, gets a
+    /// new [`Permit`], then tries again. Once all retries failed, the last error is returned.
+    /// Other errors are returned immediately.
+    ///
+    /// Unlike [`Bucket::acquire_blocking`], this doesn't wait for the duration hinted by
+    /// [`Error::NotEnoughTokens`], so that callers control how long they wait at most.
+    #[cfg(feature = "blocking")]
+    pub fn try_acquire_with_retry(
+        &self,
+        permit: Permit,
+        num: u64,
+        config: RetryConfig,
+    ) -> Result<u64, Error> {
+        let mut result = self.try_acquire(permit, num);
+        for retry in 0..config.max_retries {
+            match result {
+                Err(Error::NotEnoughTokens { .. } | Error::HighContention) => {
+                    self.inner.sleep(config.backoff(retry));
+                    result = self
+                        .try_permit()
+                        .and_then(|permit| self.try_acquire(permit, num));
+                }
+                result => return result,
+            }
+        }
+        result
+    }
+
+    /// Acquire `num` number of tokens in chunks of up to `chunk_size` tokens, as a
+    /// [`Stream`](futures_core::Stream) yielding the number of tokens acquired for each chunk.
+    ///
+    /// Each chunk uses a new [`Permit`] from `permit_fn`, such as `|| bucket.try_permit()`. When
+    /// there aren't enough tokens, the stream sleeps for the duration hinted by
+    /// [`Error::NotEnoughTokens`] before trying again. The last chunk can be smaller than
+    /// `chunk_size`. Other errors are yielded once, and end the stream.
+    #[cfg(all(feature = "tokio", feature = "futures"))]
+    pub fn acquire_n_as_stream<F>(
+        &self,
+        permit_fn: F,
+        num: u64,
+        chunk_size: u64,
+    ) -> AcquireStream<F>
+    where
+        F: Fn() -> Result<Permit, Error>,
+    {
+        AcquireStream::new(self.clone(), permit_fn, num, chunk_size)
+    }
+
+    /// Try to acquire `num` number of tokens from a [`Future::poll`](std::future::Future::poll)
+    /// implementation.
+    ///
+    /// This returns [`Poll::Pending`] if there aren't enough tokens yet, and wakes the task once
+    /// they should be available, or immediately under contention. The permit is used either way,
+    /// so a new one must be acquired before polling again. Other errors, such as
+    /// [`Error::InvalidPermit`] or [`Error::ExceedMaxTokens`], are returned as
+    /// [`Poll::Ready`], as well as [`Error::NotEnoughTokens`] when the tokens will never be
+    /// available.
+    ///
+    /// The wake-up timer uses [`tokio::time`], so this must be called from within a Tokio
+    /// runtime.
+    #[cfg(feature = "tokio")]
+    pub fn poll_acquire(
+        &self,
+        cx: &mut Context<'_>,
+        permit: Permit,
+        num: u64,
+    ) -> Poll<Result<u64, Error>> {
+        match self.try_acquire(permit, num) {
+            Err(Error::NotEnoughTokens {
+                wait_for: Some(wait_for),
+                ..
+            }) if wait_for > Duration::ZERO => {
+                background::wake_after(cx.waker().clone(), wait_for);
+                Poll::Pending
+            }
+            Err(Error::NotEnoughTokens {
+                wait_for: Some(_), ..
+            })
+            | Err(Error::HighContention) => {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+            result => Poll::Ready(result),
+        }
+    }
+
+    /// Try to acquire `num` number of tokens, calling `fallback` with the [`Error`] on failure.
+    ///
+    /// This is equivalent to `try_acquire(permit, num).or_else(fallback)`.
+    pub fn try_acquire_with_fallback<F>(
+        &self,
+        permit: Permit,
+        num: u64,
+        fallback: F,
+    ) -> Result<u64, Error>
+    where
+        F: FnOnce(Error) -> Result<u64, Error>,
+    {
+        self.try_acquire(permit, num).or_else(fallback)
+    }
+
+    /// Duration until at least `num` tokens will be available in the [`Bucket`], without
+    /// acquiring any tokens.
+    ///
+    /// This refills the bucket first, so the prediction is based on the latest number of
+    /// available tokens. Returns [`Duration::ZERO`] if the tokens are already available, or
+    /// [`None`] if `num` is greater than the maximum number of tokens, or if the tokens will
+    /// never be available.
+    pub fn wait_for(&self, num: u64) -> Option<Duration> {
+        if num > self.inner.max() {
+            return None;
+        }
+
+        self.inner.wait_for(num)
+    }
+
+    /// Time at which at least `num` tokens will be available in the [`Bucket`].
+    ///
+    /// This is the [`Instant`] equivalent of [`Bucket::wait_for`].
+    ///
+    /// ```rust
+    /// # use std::time::{Duration, Instant};
+    /// # use threshold_bucket::{Bucket, refill::RateConfig};
+    /// # let bucket = Bucket::builder()
+    /// #     .rate(RateConfig { quantity: 1.into(), interval: Duration::from_secs(1), max: 10.into() })
+    /// #     .build()
+    /// #     .unwrap();
+    /// let ready_at = bucket.available_at(5).unwrap_or_else(Instant::now);
+    /// # assert!(ready_at > Instant::now());
+    /// ```
+    pub fn available_at(&self, num: u64) -> Option<Instant> {
+        let now = Instant::now();
+        self.wait_for(num).map(|wait_for| now + wait_for)
+    }
+
+    /// Lifetime statistics of the [`Bucket`], such as the number of tokens acquired.
+    pub fn stats(&self) -> BucketStats {
+        BucketStats {
+            name: self.name.as_deref().map(String::from),
+            ..self.inner.stats()
+        }
+    }
+
+    /// Return this [`Bucket`] with the name `name`, to tell buckets apart in logs.
+    ///
+    /// The name is included in the [`Debug`](fmt::Debug) and [`Display`](fmt::Display) output and
+    /// in [`Bucket::stats`]. It only applies to this handle and the clones made from it after this
+    /// call, as other clones share the same tokens under their own name. Tracing events are
+    /// emitted by the shared state, and use the name set with `Builder::with_trace_name` instead.
+    pub fn with_name(self, name: impl Into<Arc<str>>) -> Bucket {
+        Bucket {
+            name: Some(name.into()),
+            ..self
+        }
+    }
+
+    /// Name of this [`Bucket`], if set with [`Bucket::with_name`].
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Register Prometheus metrics for the [`Bucket`] in `registry`, with names starting with
+    /// `prefix`.
+    ///
+    /// This registers the `{prefix}_available_tokens`, `{prefix}_capacity` and
+    /// `{prefix}_utilization` gauges, updated on every acquisition and refill, and the
+    /// `{prefix}_acquired_total` and `{prefix}_denied_total` counters. Metrics can only be
+    /// registered once per bucket.
+    #[cfg(feature = "prometheus")]
+    pub fn register_metrics(
+        &self,
+        registry: &prometheus::Registry,
+        prefix: &str,
+    ) -> prometheus::Result<()> {
+        self.inner
+            .set_prometheus(exporter::BucketMetrics::register(registry, prefix)?)
+    }
+
+    /// Notify `observer` every time a refill adds tokens to the [`Bucket`].
+    ///
+    /// Observers are shared by all clones of the bucket, and can't be removed. See the
+    /// [`observer`] module.
+    pub fn observe_refill(&self, observer: Arc<dyn observer::RefillObserver + Send + Sync>) {
+        self.inner.observe_refill(observer);
+    }
+
+    /// Capture the current state of the [`Bucket`], to restore it later with
+    /// [`Builder::restore_from_snapshot`].
+    ///
+    /// Tokens that are due are refilled first, so the snapshot doesn't miss a pending refill.
+    pub fn snapshot(&self) -> BucketSnapshot {
+        let (available, elapsed) = self.inner.snapshot();
+        BucketSnapshot {
+            available: available.into(),
+            elapsed_millis: elapsed.as_millis() as u64,
+        }
+    }
+
+    /// Write the current state of the [`Bucket`] to the file at `path`, to restore it later with
+    /// [`Builder::restore_checkpoint`].
+    ///
+    /// This is a lightweight alternative to persisting a [`BucketSnapshot`] with `serde`, as a
+    /// fixed 24 bytes file: the available tokens, the elapsed milliseconds, and the maximum
+    /// number of tokens, each as a little-endian `u64`.
+    pub fn checkpoint(&self, path: &Path) -> io::Result<()> {
+        let checkpoint = self.snapshot().to_checkpoint(self.inner.max());
+        std::fs::write(path, checkpoint)
+    }
+
+    /// Add `num` tokens to the [`Bucket`], without exceeding the maximum number of tokens.
+    ///
+    /// This is an administrative action that bypasses the permitter, e.g. to grant a temporary
+    /// allowance. Returns the new number of available tokens.
+    pub fn add_tokens(&self, num: u64) -> Result<u64, Error> {
+        self.inner.release(num)
+    }
+
+    /// Remove all available tokens from the [`Bucket`].
+    ///
+    /// Returns the number of tokens removed.
+    pub fn drain(&self) -> Result<u64, Error> {
+        self.inner.drain()
+    }
+
+    /// Remove all available tokens from the [`Bucket`], returning them as a [`BucketSnapshot`].
+    ///
+    /// Reading and removing the tokens happen in a single atomic update, so tokens can't be
+    /// acquired in between, and concurrent calls never return the same tokens twice. This is
+    /// useful to transfer a quota to another bucket or store.
+    pub fn drain_to_snapshot(&self) -> Result<BucketSnapshot, Error> {
+        let (available, elapsed) = self.inner.drain_to_snapshot()?;
+        Ok(BucketSnapshot {
+            available,
+            elapsed_millis: elapsed.as_millis() as u64,
+        })
+    }
+
+    /// Reset the [`Bucket`] to its initial number of tokens.
+    ///
+    /// This also clears any debt from the credit line, and restarts the refill schedule, so the
+    /// next refill happens a full interval from now.
+    pub fn reset(&self) -> Result<(), Error> {
+        self.inner.reset()
+    }
+
+    /// Restart the refill schedule, without changing the number of available tokens.
+    ///
+    /// For example, this drops an
+    /// [`ExponentialConfig`](refill::ExponentialConfig) refill back to its initial quantity,
+    /// e.g. when a downstream service fails again.
+    pub fn reset_refill(&self) {
+        self.inner.reset_refill();
+    }
+
+    /// Change the refill rate to `quantity` tokens every `interval`.
+    ///
+    /// Tokens already due with the previous rate are added first. The new rate then applies from a
+    /// full `interval` from now, without adding tokens for the interval in progress. This returns
+    /// [`Error::InvalidRate`] if `quantity` or `interval` is zero, and
+    /// [`Error::UnsupportedOperation`] if the refill doesn't have a rate, such as
+    /// [`Builder::sliding_window`] or [`Builder::quota`].
+    pub fn set_rate(&self, quantity: u64, interval: Duration) -> Result<(), Error> {
+        if quantity == 0 || interval.is_zero() {
+            return Err(Error::InvalidRate);
+        }
+        self.inner.set_rate(quantity, interval)
+    }
+
+    /// Number of tokens added per second, for display purposes.
+    ///
+    /// For example, this returns `0.1` for a [`RateConfig`](refill::RateConfig) adding 1 token
+    /// every 10 seconds, including after [`Bucket::set_rate`]. This returns [`None`] if the refill
+    /// doesn't have a constant rate, such as [`Builder::sliding_window`] or [`Builder::quota`].
+    pub fn effective_rate(&self) -> Option<f64> {
+        self.inner.effective_rate()
+    }
+
+    /// Duration until the next scheduled refill, without acquiring any tokens.
+    ///
+    /// This returns [`Duration::ZERO`] if a refill is already due, and [`None`] for custom
+    /// refills that don't implement [`Refill::next_refill_in`](refill::Refill::next_refill_in).
+    pub fn next_refill_in(&self) -> Option<Duration> {
+        self.inner.next_refill_in()
+    }
+
+    /// Fill the [`Bucket`] up to its maximum number of tokens.
+    ///
+    /// Returns the number of tokens added.
+    pub fn fill(&self) -> Result<u64, Error> {
+        self.inner.fill()
     }
 }
 
 /// Bucket build errors
-#[derive(Debug, Clone, thiserror::Error)]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
 pub enum BuildError {
-    /// Missing refill for the bucket [`Builder`]
+    /// No refill was set, with [`Builder::rate`] or an equivalent method
+    ///
+    /// [`Builder::build`] requires a refill at compile time, so this is only returned by
+    /// [`Builder::validate`], on builders that aren't ready to build yet.
     #[error("missing refill")]
     MissingRefill,
 
-    /// Missing max number of tokens for the bucket [`Builder`]
+    /// No maximum number of tokens was set for [`Builder::refill_rate`], with [`Builder::max`]
+    ///
+    /// As with [`BuildError::MissingRefill`], this is only returned by [`Builder::validate`].
     #[error("missing max")]
     MissingMax,
+
+    /// The refill rate has a zero interval, a zero quantity with [`Builder::refill_rate`], or an
+    /// exponential multiplier below 1
+    ///
+    /// A [`RateConfig`](refill::RateConfig) with a zero quantity is allowed, for a bucket that is
+    /// never refilled.
+    #[error("invalid refill rate")]
+    InvalidRate,
+
+    /// The threshold percentage is above 100, or the threshold isn't lower than the max
+    #[error("invalid threshold")]
+    InvalidThreshold,
+
+    /// The threshold isn't lower than the max, so permits could never be granted once a token is
+    /// acquired
+    #[error("threshold ({threshold}) must be lower than the max ({max})")]
+    ThresholdExceedsMax {
+        /// Configured threshold
+        threshold: u64,
+        /// Maximum number of tokens in the bucket
+        max: u64,
+    },
+
+    /// The permitter of the bucket doesn't have a threshold that can be changed
+    #[error("unsupported permitter")]
+    UnsupportedPermitter,
+
+    /// The refill quantity is greater than the max, so refills would always overshoot
+    #[error("refill quantity ({quantity}) must not exceed the max ({max})")]
+    QuantityExceedsMax {
+        /// Tokens added per refill interval
+        quantity: u64,
+        /// Maximum number of tokens in the bucket
+        max: u64,
+    },
+
+    /// The maximum, initial number of tokens, or credit limit is above [`TokenCount::MAX`]
+    #[error(
+        "{tokens} tokens exceed the largest supported count ({})",
+        TokenCount::MAX
+    )]
+    TooManyTokens {
+        /// Configured number of tokens
+        tokens: u64,
+    },
+
+    /// The initial number of tokens is greater than the max
+    #[error("initial tokens ({initial}) must not exceed the max ({max})")]
+    InitialExceedsMax {
+        /// Configured initial number of tokens
+        initial: u64,
+        /// Maximum number of tokens in the bucket
+        max: u64,
+    },
+
+    /// [`Builder::max`] was set with a refill that includes a different maximum number of tokens,
+    /// such as [`Builder::rate`]
+    #[error("max ({max}) conflicts with the max of the refill ({refill_max})")]
+    ConflictingMax {
+        /// Maximum number of tokens set with [`Builder::max`]
+        max: u64,
+        /// Maximum number of tokens of the refill
+        refill_max: u64,
+    },
+}
+
+/// Bucket specification parse errors
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParseError {
+    /// The specification doesn't match any supported format
+    #[error("invalid format: {0:?}")]
+    InvalidFormat(String),
+
+    /// Invalid quantity of tokens added per interval
+    #[error("invalid quantity: {0:?}")]
+    InvalidQuantity(String),
+
+    /// Invalid refill interval
+    #[error("invalid interval: {0:?}")]
+    InvalidInterval(String),
+
+    /// Invalid maximum number of tokens
+    #[error("invalid max: {0:?}")]
+    InvalidMax(String),
+
+    /// The parsed specification could not be built into a [`Bucket`]
+    #[error(transparent)]
+    Build(#[from] BuildError),
 }
 
 /// Bucket errors
-#[derive(Debug, Clone, thiserror::Error)]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
 pub enum Error {
     /// The requested number of tokens exceeds the maximum possible number of tokens
     #[error("requested number exceeds the maximum number of tokens")]
@@ -85,7 +1102,221 @@ pub enum Error {
     #[error("invalid permit")]
     InvalidPermit,
 
+    /// The permit passed has exceeded its time to live
+    #[error("expired permit")]
+    ExpiredPermit,
+
     /// Not enough tokens available
-    #[error("not enough tokens available")]
-    NotEnoughTokens(Option<Duration>),
+    #[error("not enough tokens available ({available} available)")]
+    NotEnoughTokens {
+        /// Number of tokens available when the request was denied
+        available: u64,
+        /// Duration until enough tokens should be available, if known
+        wait_for: Option<Duration>,
+    },
+
+    /// The bucket was dropped
+    #[error("bucket dropped")]
+    BucketDropped,
+
+    /// The refill rate is invalid, such as a quantity or interval of zero
+    #[error("invalid refill rate")]
+    InvalidRate,
+
+    /// The operation is not supported by the refill algorithm of the bucket
+    #[error("operation not supported by the refill")]
+    UnsupportedOperation,
+}
+
+/// Reasons for a [`Bucket::try_permit_checked`] denial
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CheckedPermitError {
+    /// Fewer tokens than the threshold of the permitter are available
+    #[error("below threshold ({available} available, threshold: {threshold})")]
+    BelowThreshold {
+        /// Number of tokens available when the permit was denied
+        available: u64,
+        /// Minimum number of available tokens to grant a permit
+        threshold: u64,
+        /// Duration until the bucket should be back above the threshold, if known
+        wait_for: Option<Duration>,
+    },
+
+    /// The permitter denied the permit for another reason, such as a custom or fair permitter
+    #[error("permit denied ({available} available)")]
+    Denied {
+        /// Number of tokens available when the permit was denied
+        available: u64,
+        /// Duration until the permitter could grant a permit, if known
+        wait_for: Option<Duration>,
+    },
+}
+
+/// Denials convert to [`Error::NotEnoughTokens`], as returned by [`Bucket::try_permit`]
+impl From<CheckedPermitError> for Error {
+    fn from(err: CheckedPermitError) -> Self {
+        match err {
+            CheckedPermitError::BelowThreshold {
+                available,
+                wait_for,
+                ..
+            }
+            | CheckedPermitError::Denied {
+                available,
+                wait_for,
+            } => Error::NotEnoughTokens {
+                available,
+                wait_for,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        clock::ManualClock,
+        permit::ThresholdConfig,
+        refill::{RateConfig, SlidingWindowConfig},
+        Bucket, BuildError, Builder, Error,
+    };
+
+    fn builder(initial: u64) -> Builder {
+        Bucket::builder()
+            .sliding_window(SlidingWindowConfig {
+                quantity: 10,
+                window: Duration::from_secs(10),
+                max: 10,
+            })
+            .initial(initial)
+            .with_clock(ManualClock::new())
+    }
+
+    fn bucket(initial: u64) -> Bucket {
+        builder(initial).build().unwrap()
+    }
+
+    #[test]
+    fn acquisition_errors() {
+        let (bucket, other) = (bucket(2), bucket(2));
+
+        assert_eq!(
+            bucket.try_acquire(bucket.try_permit().unwrap(), 11),
+            Err(Error::ExceedMaxTokens)
+        );
+        assert_eq!(
+            bucket.try_acquire(other.try_permit().unwrap(), 1),
+            Err(Error::InvalidPermit)
+        );
+        assert_eq!(
+            bucket.try_acquire(bucket.try_permit().unwrap(), 5),
+            Err(Error::NotEnoughTokens {
+                available: 2,
+                wait_for: Some(Duration::from_secs(3)),
+            })
+        );
+        assert_eq!(
+            bucket.set_rate(0, Duration::from_secs(1)),
+            Err(Error::InvalidRate)
+        );
+        assert_eq!(
+            bucket.set_rate(1, Duration::from_secs(1)),
+            Err(Error::UnsupportedOperation)
+        );
+    }
+
+    #[test]
+    fn permit_errors() {
+        let bucket = builder(2).permit_ttl(Duration::ZERO).build().unwrap();
+        assert_eq!(
+            bucket.try_acquire(bucket.try_permit().unwrap(), 1),
+            Err(Error::ExpiredPermit)
+        );
+
+        let bucket = builder(2).max_cas_retries(0).build().unwrap();
+        assert_eq!(
+            bucket.try_acquire(bucket.try_permit().unwrap(), 5),
+            Err(Error::HighContention)
+        );
+
+        let mut batch = bucket.get_batch_permit(2).unwrap();
+        drop(bucket);
+        assert_eq!(batch.try_acquire(1), Err(Error::BucketDropped));
+        assert_ne!(Error::ExpiredPermit, Error::InvalidPermit);
+    }
+
+    #[test]
+    fn build_errors() {
+        assert_eq!(
+            builder(2)
+                .threshold(ThresholdConfig {
+                    threshold: 10.into()
+                })
+                .build()
+                .unwrap_err(),
+            BuildError::ThresholdExceedsMax {
+                threshold: 10,
+                max: 10,
+            }
+        );
+        assert_eq!(
+            builder(2).threshold_percent(101).build().unwrap_err(),
+            BuildError::InvalidThreshold
+        );
+        assert_eq!(
+            bucket(2).set_threshold(5),
+            Err(BuildError::UnsupportedPermitter)
+        );
+        assert_eq!(
+            Bucket::builder().validate(),
+            Err(vec![BuildError::MissingRefill, BuildError::MissingMax])
+        );
+
+        let rate = |quantity: u64, interval| RateConfig {
+            quantity: quantity.into(),
+            interval,
+            max: 10.into(),
+        };
+        assert_eq!(
+            Bucket::builder()
+                .rate(rate(1, Duration::ZERO))
+                .build()
+                .unwrap_err(),
+            BuildError::InvalidRate
+        );
+        assert_eq!(
+            Bucket::builder()
+                .rate(rate(11, Duration::from_secs(1)))
+                .build()
+                .unwrap_err(),
+            BuildError::QuantityExceedsMax {
+                quantity: 11,
+                max: 10,
+            }
+        );
+        assert_eq!(
+            Bucket::builder()
+                .rate(rate(1, Duration::from_secs(1)))
+                .max(20)
+                .build()
+                .unwrap_err(),
+            BuildError::ConflictingMax {
+                max: 20,
+                refill_max: 10,
+            }
+        );
+        assert_eq!(
+            builder(11).build().unwrap_err(),
+            BuildError::InitialExceedsMax {
+                initial: 11,
+                max: 10,
+            }
+        );
+        assert_eq!(
+            builder(2).credit_limit(u64::MAX).build().unwrap_err(),
+            BuildError::TooManyTokens { tokens: u64::MAX }
+        );
+    }
 }
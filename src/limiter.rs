@@ -0,0 +1,170 @@
+//! # Per-key rate limiting
+//!
+//! [`RateLimiter`] wraps a [`BucketMap`] behind a simpler interface, for the common case of
+//! allowing a number of requests per key, without handling permits or buckets directly.
+//!
+//! ```rust
+//! use threshold_bucket::{limiter::RateLimiter, refill::RateConfig};
+//!
+//! let limiter = RateLimiter::builder(RateConfig::per_second(1, 2))
+//!     .initial(2)
+//!     .max_keys(10_000)
+//!     .build()?;
+//!
+//! assert!(limiter.check(&"alice").is_ok());
+//! assert!(limiter.check_n(&"alice", 2).is_err());
+//! assert!(limiter.check(&"bob").is_ok());
+//! # Ok::<_, Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{fmt::Display, hash::Hash, marker::PhantomData, time::Duration};
+
+use crate::{
+    map::BucketMap, permit::ThresholdConfig, refill::RateConfig, Bucket, BuildError, Error,
+};
+
+/// Rate limiter with one [`Bucket`] per key
+///
+/// Each check acquires tokens from the bucket for the key, creating it on first use.
+pub struct RateLimiter<K> {
+    buckets: BucketMap<K>,
+}
+
+impl<K> RateLimiter<K>
+where
+    K: Hash + Eq + Clone + Display + Send + Sync,
+{
+    /// Create a new [`RateLimiterBuilder`], refilling the bucket of each key with `rate`.
+    pub fn builder(rate: RateConfig) -> RateLimiterBuilder<K> {
+        RateLimiterBuilder {
+            rate,
+            threshold: None,
+            initial: None,
+            max_keys: None,
+            _key: PhantomData,
+        }
+    }
+
+    /// Allow a single request for `key`, acquiring one token.
+    pub fn check(&self, key: &K) -> Result<(), RateLimitError> {
+        self.check_n(key, 1)
+    }
+
+    /// Allow a request costing `n` tokens for `key`.
+    ///
+    /// This returns [`RateLimitError::Exceeded`] if there aren't enough tokens for the key, or if
+    /// `n` is more than the bucket can ever hold.
+    pub fn check_n(&self, key: &K, n: u64) -> Result<(), RateLimitError> {
+        match self.buckets.try_acquire(key, n) {
+            Ok(_) => Ok(()),
+            Err(Error::NotEnoughTokens { wait_for, .. }) => Err(RateLimitError::Exceeded {
+                key_display: key.to_string(),
+                retry_after: wait_for,
+            }),
+            Err(Error::ExceedMaxTokens) => Err(RateLimitError::Exceeded {
+                key_display: key.to_string(),
+                retry_after: None,
+            }),
+            Err(err) => Err(RateLimitError::Bucket(err)),
+        }
+    }
+
+    /// Number of keys currently tracked.
+    pub fn len(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// Returns `true` if no key is tracked.
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+}
+
+impl<K> Clone for RateLimiter<K> {
+    fn clone(&self) -> Self {
+        Self {
+            buckets: self.buckets.clone(),
+        }
+    }
+}
+
+/// Builder for a [`RateLimiter`]
+pub struct RateLimiterBuilder<K> {
+    rate: RateConfig,
+    threshold: Option<ThresholdConfig>,
+    initial: Option<u64>,
+    max_keys: Option<usize>,
+    _key: PhantomData<K>,
+}
+
+impl<K> RateLimiterBuilder<K>
+where
+    K: Hash + Eq + Clone + Display + Send + Sync,
+{
+    /// Refuse requests when the bucket of a key goes below `threshold` tokens
+    pub fn threshold(self, threshold: u64) -> Self {
+        Self {
+            threshold: Some(ThresholdConfig {
+                threshold: threshold.into(),
+            }),
+            ..self
+        }
+    }
+
+    /// Set the initial number of tokens for new keys
+    pub fn initial(self, initial: u64) -> Self {
+        Self {
+            initial: Some(initial),
+            ..self
+        }
+    }
+
+    /// Set the maximum number of keys tracked at once
+    ///
+    /// When a new key comes in while the limiter is full, the least recently used key is
+    /// forgotten, and starts over with a new bucket if it comes back.
+    pub fn max_keys(self, max_keys: usize) -> Self {
+        Self {
+            max_keys: Some(max_keys),
+            ..self
+        }
+    }
+
+    /// Build the [`RateLimiter`]
+    ///
+    /// This returns an error if the bucket configuration is invalid.
+    pub fn build(self) -> Result<RateLimiter<K>, BuildError> {
+        let mut config = Bucket::builder().rate(self.rate);
+        if let Some(threshold) = self.threshold {
+            config = config.threshold(threshold);
+        }
+        if let Some(initial) = self.initial {
+            config = config.initial(initial);
+        }
+
+        let mut buckets = BucketMap::builder(config);
+        if let Some(max_keys) = self.max_keys {
+            buckets = buckets.max_entries(max_keys);
+        }
+        Ok(RateLimiter {
+            buckets: buckets.build()?,
+        })
+    }
+}
+
+/// Rate limiter errors
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RateLimitError {
+    /// The key exceeded its rate limit
+    #[error("rate limit exceeded for {key_display}")]
+    Exceeded {
+        /// Key that exceeded its rate limit, formatted with [`Display`]
+        key_display: String,
+        /// Duration until the request could be allowed, if known
+        retry_after: Option<Duration>,
+    },
+
+    /// The bucket of the key failed for another reason, such as high contention
+    #[error(transparent)]
+    Bucket(#[from] Error),
+}
@@ -0,0 +1,223 @@
+//! # Per-key buckets
+//!
+//! [`BucketMap`] lazily creates a [`Bucket`] for each key, using a shared [`Builder`]
+//! configuration. This is useful for per-user or per-IP rate limiting.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard, RwLock,
+    },
+};
+
+use crate::{Bucket, BuildError, Builder, Error};
+
+/// Map of [`Bucket`]s, one per key
+///
+/// Buckets are created with the same configuration when a key is first seen. If a maximum number
+/// of entries is set, the least recently used bucket is evicted when inserting a new key in a
+/// full map. Finding that bucket doesn't require going through all entries.
+///
+/// Note that a custom [`Refill`](crate::refill::Refill) or
+/// [`Permitter`](crate::permit::Permitter) set on the [`Builder`] is shared by all buckets.
+pub struct BucketMap<K> {
+    shared: Arc<Shared<K>>,
+}
+
+struct Shared<K> {
+    builder: Builder,
+    max_entries: Option<usize>,
+    entries: RwLock<HashMap<K, Entry>>,
+    /// Keys by last use, only kept up to date when the number of entries is limited
+    ///
+    /// This is always locked after `entries`.
+    recency: Mutex<Recency<K>>,
+}
+
+struct Entry {
+    bucket: Bucket,
+    /// Tick of the last use of the entry, only updated while holding the lock on `recency`
+    last_used: AtomicU64,
+}
+
+/// Order in which the entries were last used
+struct Recency<K> {
+    /// Logical clock, incremented on each use
+    tick: u64,
+    /// Keys by the tick of their last use, so that the first key is the least recently used
+    keys: BTreeMap<u64, K>,
+}
+
+impl<K> BucketMap<K>
+where
+    K: Hash + Eq + Clone + Send + Sync,
+{
+    /// Create a new [`BucketMapBuilder`], using `config` to create the buckets.
+    pub fn builder(config: Builder) -> BucketMapBuilder<K> {
+        BucketMapBuilder {
+            config,
+            max_entries: None,
+            _key: std::marker::PhantomData,
+        }
+    }
+
+    /// Try to acquire `num` number of tokens from the bucket for `key`.
+    ///
+    /// This creates the bucket if it doesn't exist yet.
+    pub fn try_acquire(&self, key: &K, num: u64) -> Result<u64, Error> {
+        let bucket = self.get_or_insert(key);
+        let permit = bucket.try_permit()?;
+        bucket.try_acquire(permit, num)
+    }
+
+    /// Number of tokens available in the bucket for `key`, if it exists.
+    pub fn available(&self, key: &K) -> Option<u64> {
+        self.get(key).map(|bucket| bucket.available())
+    }
+
+    /// Bucket for `key`, if it exists.
+    pub fn get(&self, key: &K) -> Option<Bucket> {
+        let entries = self
+            .shared
+            .entries
+            .read()
+            .unwrap_or_else(|e| e.into_inner());
+        entries.get(key).map(|entry| self.touch(key, entry))
+    }
+
+    /// Bucket for `key`, creating it if it doesn't exist yet.
+    pub fn get_or_insert(&self, key: &K) -> Bucket {
+        if let Some(bucket) = self.get(key) {
+            return bucket;
+        }
+
+        let mut entries = self
+            .shared
+            .entries
+            .write()
+            .unwrap_or_else(|e| e.into_inner());
+
+        // Another thread could have inserted the key while waiting for the write lock
+        if let Some(entry) = entries.get(key) {
+            return self.touch(key, entry);
+        }
+
+        let mut last_used = 0;
+        if let Some(max_entries) = self.shared.max_entries {
+            let mut recency = self.recency();
+            while entries.len() >= max_entries.max(1) {
+                let Some((_, lru)) = recency.keys.pop_first() else {
+                    break;
+                };
+                entries.remove(&lru);
+            }
+            last_used = recency.use_key(key.clone(), None);
+        }
+
+        let bucket = self.shared.builder.clone().build_validated();
+        let entry = Entry {
+            bucket: bucket.clone(),
+            last_used: AtomicU64::new(last_used),
+        };
+        entries.insert(key.clone(), entry);
+
+        bucket
+    }
+
+    /// Number of buckets in the map.
+    pub fn len(&self) -> usize {
+        self.shared
+            .entries
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .len()
+    }
+
+    /// Returns `true` if the map doesn't contain any bucket.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Mark the entry as used, and return its bucket
+    fn touch(&self, key: &K, entry: &Entry) -> Bucket {
+        if self.shared.max_entries.is_some() {
+            let mut recency = self.recency();
+            let previous = entry.last_used.load(Ordering::Relaxed);
+            let tick = recency.use_key(key.clone(), Some(previous));
+            entry.last_used.store(tick, Ordering::Relaxed);
+        }
+        entry.bucket.clone()
+    }
+
+    fn recency(&self) -> MutexGuard<'_, Recency<K>> {
+        self.shared
+            .recency
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<K> Recency<K> {
+    /// Record a use of `key`, last used at tick `previous` if any, and return the tick of this use
+    fn use_key(&mut self, key: K, previous: Option<u64>) -> u64 {
+        if let Some(previous) = previous {
+            self.keys.remove(&previous);
+        }
+        let tick = self.tick;
+        self.tick += 1;
+        self.keys.insert(tick, key);
+        tick
+    }
+}
+
+impl<K> Clone for BucketMap<K> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+/// Builder for a [`BucketMap`]
+pub struct BucketMapBuilder<K> {
+    config: Builder,
+    max_entries: Option<usize>,
+    _key: std::marker::PhantomData<K>,
+}
+
+impl<K> BucketMapBuilder<K>
+where
+    K: Hash + Eq + Clone + Send + Sync,
+{
+    /// Set the maximum number of buckets in the map
+    ///
+    /// When inserting a new key in a full map, the least recently used bucket is evicted.
+    pub fn max_entries(self, max_entries: usize) -> Self {
+        Self {
+            max_entries: Some(max_entries),
+            ..self
+        }
+    }
+
+    /// Build the [`BucketMap`]
+    ///
+    /// This returns an error if the bucket configuration is invalid.
+    pub fn build(self) -> Result<BucketMap<K>, BuildError> {
+        // Validate the configuration once, so creating buckets later cannot fail
+        self.config.clone().build()?;
+
+        Ok(BucketMap {
+            shared: Arc::new(Shared {
+                builder: self.config,
+                max_entries: self.max_entries,
+                entries: RwLock::new(HashMap::new()),
+                recency: Mutex::new(Recency {
+                    tick: 0,
+                    keys: BTreeMap::new(),
+                }),
+            }),
+        })
+    }
+}
@@ -0,0 +1,148 @@
+//! Hooks for custom metrics backends
+//!
+//! A [`MetricsObserver`] set with [`Builder::with_metrics`](crate::Builder::with_metrics) is
+//! called on every permit decision, acquisition and refill of a [`Bucket`](crate::Bucket).
+//!
+//! ```rust
+//! use std::{sync::Arc, time::Duration};
+//! use threshold_bucket::{
+//!     metrics::{MetricEvent, RecordingObserver},
+//!     refill::RateConfig,
+//!     Bucket,
+//! };
+//!
+//! let observer = Arc::new(RecordingObserver::new());
+//! let bucket = Bucket::builder()
+//!     .rate(RateConfig {
+//!         quantity: 1.into(),
+//!         interval: Duration::from_secs(1),
+//!         max: 10.into(),
+//!     })
+//!     .initial(10)
+//!     .with_metrics(observer.clone())
+//!     .build()?;
+//!
+//! bucket.quick_acquire(3)?;
+//! assert_eq!(
+//!     observer.events(),
+//!     vec![MetricEvent::PermitGranted, MetricEvent::Acquire { tokens: 3 }]
+//! );
+//! # Ok::<_, Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{sync::Mutex, time::Duration};
+
+/// Observer notified of the activity of a [`Bucket`](crate::Bucket)
+///
+/// All methods do nothing by default, so implementations only need to override the events they
+/// are interested in. Observers are called inline, and should return quickly.
+pub trait MetricsObserver {
+    /// A permit was granted
+    fn on_permit_granted(&self) {}
+
+    /// A permit was refused, with the duration until one could be granted, if known
+    fn on_permit_denied(&self, wait_for: Option<Duration>) {
+        let _ = wait_for;
+    }
+
+    /// `tokens` tokens were acquired
+    fn on_acquire(&self, tokens: u64) {
+        let _ = tokens;
+    }
+
+    /// A refill added `tokens_added` tokens
+    fn on_refill(&self, tokens_added: u64) {
+        let _ = tokens_added;
+    }
+
+    /// An acquisition failed with [`Error::HighContention`](crate::Error::HighContention)
+    fn on_contention(&self) {}
+}
+
+/// Observer ignoring all events
+///
+/// This is the default when no observer is set on the [`Builder`](crate::Builder).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoopObserver;
+
+impl MetricsObserver for NoopObserver {}
+
+/// Event recorded by a [`RecordingObserver`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MetricEvent {
+    /// See [`MetricsObserver::on_permit_granted`]
+    PermitGranted,
+    /// See [`MetricsObserver::on_permit_denied`]
+    PermitDenied {
+        /// Duration until a permit could be granted, if known
+        wait_for: Option<Duration>,
+    },
+    /// See [`MetricsObserver::on_acquire`]
+    Acquire {
+        /// Number of tokens acquired
+        tokens: u64,
+    },
+    /// See [`MetricsObserver::on_refill`]
+    Refill {
+        /// Number of tokens added
+        tokens_added: u64,
+    },
+    /// See [`MetricsObserver::on_contention`]
+    Contention,
+}
+
+/// Observer storing all events in memory, mostly useful for tests
+#[derive(Debug, Default)]
+pub struct RecordingObserver {
+    events: Mutex<Vec<MetricEvent>>,
+}
+
+impl RecordingObserver {
+    /// Create a new observer without any events
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Events recorded so far, in order
+    pub fn events(&self) -> Vec<MetricEvent> {
+        self.lock().clone()
+    }
+
+    /// Remove all recorded events
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn record(&self, event: MetricEvent) {
+        self.lock().push(event);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<MetricEvent>> {
+        // A panic while holding the lock can't leave the events in an inconsistent state
+        self.events
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl MetricsObserver for RecordingObserver {
+    fn on_permit_granted(&self) {
+        self.record(MetricEvent::PermitGranted);
+    }
+
+    fn on_permit_denied(&self, wait_for: Option<Duration>) {
+        self.record(MetricEvent::PermitDenied { wait_for });
+    }
+
+    fn on_acquire(&self, tokens: u64) {
+        self.record(MetricEvent::Acquire { tokens });
+    }
+
+    fn on_refill(&self, tokens_added: u64) {
+        self.record(MetricEvent::Refill { tokens_added });
+    }
+
+    fn on_contention(&self) {
+        self.record(MetricEvent::Contention);
+    }
+}
@@ -0,0 +1,4 @@
+//! Middleware for rate limiting services with a [`Bucket`](crate::Bucket)
+
+#[cfg(feature = "tower")]
+pub mod tower;
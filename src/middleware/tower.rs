@@ -0,0 +1,161 @@
+//! [`tower`](https://docs.rs/tower) middleware rejecting HTTP requests when the bucket is empty
+//!
+//! Each request acquires one token from the bucket. When there aren't enough tokens, the request
+//! is not sent to the inner service, and a `429 Too Many Requests` response is returned instead,
+//! with a `Retry-After` header if the bucket knows when tokens will be available.
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use threshold_bucket::{middleware::tower::RateLimitLayer, refill::RateConfig, Bucket};
+//!
+//! let bucket = Bucket::builder()
+//!     .rate(RateConfig {
+//!         quantity: 10.into(),
+//!         interval: Duration::from_secs(1),
+//!         max: 100.into(),
+//!     })
+//!     .initial_full()
+//!     .build()
+//!     .unwrap();
+//! let layer = RateLimitLayer::new(bucket);
+//! ```
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use http::{header::RETRY_AFTER, HeaderValue, Request, Response, StatusCode};
+use pin_project_lite::pin_project;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::{headers::retry_after_secs, Bucket, Error};
+
+/// [`Layer`] applying [`RateLimitService`] to services
+#[derive(Clone, Debug)]
+pub struct RateLimitLayer {
+    bucket: Bucket,
+}
+
+impl RateLimitLayer {
+    /// Create a new layer, rate limiting requests with `bucket`
+    pub fn new(bucket: Bucket) -> Self {
+        Self { bucket }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            bucket: self.bucket.clone(),
+        }
+    }
+}
+
+/// [`Service`] rejecting requests with `429 Too Many Requests` when the bucket is empty
+#[derive(Clone, Debug)]
+pub struct RateLimitService<S> {
+    inner: S,
+    bucket: Bucket,
+}
+
+impl<S> RateLimitService<S> {
+    /// Create a new service, rate limiting requests to `inner` with `bucket`
+    pub fn new(inner: S, bucket: Bucket) -> Self {
+        Self { inner, bucket }
+    }
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for RateLimitService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    ResBody: Default,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future, ResBody>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        match self.bucket.quick_acquire_one() {
+            Ok(_) => ResponseFuture {
+                state: State::Inner {
+                    future: self.inner.call(req),
+                },
+            },
+            Err(err) => ResponseFuture {
+                state: State::RateLimited {
+                    response: Some(too_many_requests(&err)),
+                },
+            },
+        }
+    }
+}
+
+pin_project! {
+    /// Response future for [`RateLimitService`]
+    pub struct ResponseFuture<F, B> {
+        #[pin]
+        state: State<F, B>,
+    }
+}
+
+pin_project! {
+    #[project = StateProj]
+    enum State<F, B> {
+        // Waiting for the inner service
+        Inner {
+            #[pin]
+            future: F,
+        },
+        // The request was rejected, the response is taken when polled
+        RateLimited {
+            response: Option<Response<B>>,
+        },
+    }
+}
+
+impl<F, B, E> Future for ResponseFuture<F, B>
+where
+    F: Future<Output = Result<Response<B>, E>>,
+{
+    type Output = Result<Response<B>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project().state.project() {
+            StateProj::Inner { future } => future.poll(cx),
+            StateProj::RateLimited { response } => Poll::Ready(Ok(response
+                .take()
+                .expect("ResponseFuture polled after completion"))),
+        }
+    }
+}
+
+/// Build a `429 Too Many Requests` response for `err`
+fn too_many_requests<B>(err: &Error) -> Response<B>
+where
+    B: Default,
+{
+    let mut response = Response::new(B::default());
+    *response.status_mut() = StatusCode::TOO_MANY_REQUESTS;
+
+    if let Error::NotEnoughTokens {
+        wait_for: Some(wait_for),
+        ..
+    } = err
+    {
+        response
+            .headers_mut()
+            .insert(RETRY_AFTER, HeaderValue::from(retry_after_secs(*wait_for)));
+    }
+
+    response
+}
@@ -0,0 +1,51 @@
+//! Acquisitions across multiple buckets
+
+use crate::{permit::Permit, Bucket, Error};
+
+/// Try to acquire tokens from multiple [`Bucket`]s, or none at all.
+///
+/// Acquisitions are tried in order. If one fails, the tokens acquired by the previous ones are
+/// given back with [`Bucket::add_tokens`], and the index of the failed acquisition is returned
+/// with its [`Error`]. Acquisitions after the failed one are not tried.
+///
+/// The rollback is best-effort: other callers can briefly see the tokens missing from the first
+/// buckets, and tokens given back can be capped by the maximum of their bucket if it was refilled
+/// in the meantime.
+///
+/// ```rust
+/// # use std::time::Duration;
+/// use threshold_bucket::{refill::RateConfig, try_acquire_all_or_nothing, Bucket};
+///
+/// # let bucket = || Bucket::builder()
+/// #     .rate(RateConfig { quantity: 1.into(), interval: Duration::from_secs(1), max: 10.into() })
+/// #     .initial(10)
+/// #     .build()
+/// #     .unwrap();
+/// let (a, b) = (bucket(), bucket());
+///
+/// let acquired = try_acquire_all_or_nothing([
+///     (&a, a.try_permit().unwrap(), 3),
+///     (&b, b.try_permit().unwrap(), 5),
+/// ]);
+/// assert_eq!(acquired.unwrap(), vec![3, 5]);
+/// ```
+pub fn try_acquire_all_or_nothing<'a, I>(acquisitions: I) -> Result<Vec<u64>, (usize, Error)>
+where
+    I: IntoIterator<Item = (&'a Bucket, Permit, u64)>,
+{
+    let mut acquired: Vec<(&Bucket, u64)> = Vec::new();
+
+    for (index, (bucket, permit, num)) in acquisitions.into_iter().enumerate() {
+        match bucket.try_acquire(permit, num) {
+            Ok(tokens) => acquired.push((bucket, tokens)),
+            Err(err) => {
+                for (bucket, tokens) in acquired {
+                    let _ = bucket.add_tokens(tokens);
+                }
+                return Err((index, err));
+            }
+        }
+    }
+
+    Ok(acquired.into_iter().map(|(_, tokens)| tokens).collect())
+}
@@ -0,0 +1,34 @@
+//! Hooks for refill monitoring
+//!
+//! A [`RefillObserver`] added with [`Bucket::observe_refill`](crate::Bucket::observe_refill) is
+//! called every time a refill adds tokens to the [`Bucket`](crate::Bucket). Unlike
+//! [`MetricsObserver`](crate::metrics::MetricsObserver), observers can be added after the bucket
+//! is built, and a bucket can have any number of them.
+
+use std::time::Duration;
+
+/// Observer notified of the refills of a [`Bucket`](crate::Bucket)
+///
+/// Observers are called inline, and should return quickly.
+pub trait RefillObserver {
+    /// A refill added `tokens_added` tokens, bringing the number of available tokens to
+    /// `new_available`
+    ///
+    /// `next_refill_in` is the duration until the next refill, if the refill algorithm knows it.
+    fn on_refill(&self, tokens_added: u64, new_available: u64, next_refill_in: Option<Duration>);
+}
+
+/// Observer printing refills to stderr
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LoggingRefillObserver;
+
+impl RefillObserver for LoggingRefillObserver {
+    fn on_refill(&self, tokens_added: u64, new_available: u64, next_refill_in: Option<Duration>) {
+        match next_refill_in {
+            Some(next_refill_in) => eprintln!(
+                "refill: added {tokens_added} tokens, {new_available} available, next refill in {next_refill_in:?}"
+            ),
+            None => eprintln!("refill: added {tokens_added} tokens, {new_available} available"),
+        }
+    }
+}
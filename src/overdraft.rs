@@ -0,0 +1,23 @@
+//! Acquisitions beyond the available tokens
+
+/// Debt created by [`Bucket::overdraft`](crate::Bucket::overdraft)
+///
+/// The debt is repaid by future refills, before tokens become available again. See
+/// [`Bucket::debt`](crate::Bucket::debt) for the total debt of the bucket.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OverdraftToken {
+    amount: u64,
+}
+
+impl OverdraftToken {
+    pub(crate) fn new(amount: u64) -> Self {
+        Self { amount }
+    }
+
+    /// Number of tokens borrowed by this acquisition
+    ///
+    /// This is `0` if there were enough tokens available.
+    pub fn amount(&self) -> u64 {
+        self.amount
+    }
+}
@@ -0,0 +1,139 @@
+//! Parser for bucket specification strings
+//!
+//! The following formats are supported, where `Q` is the quantity of tokens added per interval,
+//! `I` the interval, and `M` the maximum number of tokens:
+//!
+//! * `"Q/I/M"`, e.g. `"100/10s/200"`
+//! * `"Q req/I max M"`, e.g. `"100 req/10s max 200"`
+//! * `"M tok @Q/I"`, e.g. `"200 tok @100/10s"`
+//!
+//! Intervals are a number followed by a unit (`ms`, `s`, `m`, or `h`, or their longer forms such
+//! as `sec` or `min`). The number can be omitted for a single unit, e.g. `"100/s/200"`.
+//!
+//! [`RateConfig`]s can also be parsed on their own from `"Q/I"`, e.g. `"100/min"`, with a default
+//! maximum of 60 times the quantity, or `"Q/I:max=M"`, e.g. `"100/s:max=5000"`.
+
+use std::{str::FromStr, time::Duration};
+
+use crate::{refill::RateConfig, Bucket, Builder, ParseError};
+
+impl FromStr for Builder {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Builder::new().rate(parse_spec(s)?))
+    }
+}
+
+impl FromStr for Bucket {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(s.parse::<Builder>()?.build()?)
+    }
+}
+
+impl FromStr for RateConfig {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (rate, max) = match s.split_once(':') {
+            Some((rate, max)) => {
+                let max = max
+                    .trim()
+                    .strip_prefix("max")
+                    .and_then(|max| max.trim_start().strip_prefix('='))
+                    .ok_or_else(|| ParseError::InvalidFormat(s.to_string()))?;
+                (rate, Some(max))
+            }
+            None => (s, None),
+        };
+        let (quantity, interval) = split_rate(s, rate)?;
+
+        let quantity = parse_number(quantity)
+            .ok_or_else(|| ParseError::InvalidQuantity(quantity.trim().to_string()))?;
+        let interval = parse_interval(interval)
+            .ok_or_else(|| ParseError::InvalidInterval(interval.trim().to_string()))?;
+        let max = match max {
+            Some(max) => {
+                parse_number(max).ok_or_else(|| ParseError::InvalidMax(max.trim().to_string()))?
+            }
+            None => quantity.saturating_mul(60),
+        };
+
+        Ok(RateConfig {
+            quantity: quantity.into(),
+            interval,
+            max: max.into(),
+        })
+    }
+}
+
+/// Parse a bucket specification into a [`RateConfig`]
+fn parse_spec(s: &str) -> Result<RateConfig, ParseError> {
+    let s = s.trim();
+
+    let (quantity, interval, max) = if let Some((max, rate)) = s.split_once('@') {
+        // "M tok @Q/I"
+        let max = max.trim();
+        let max = max.strip_suffix("tok").unwrap_or(max);
+        let (quantity, interval) = split_rate(s, rate)?;
+        (quantity, interval, max)
+    } else if let Some((rate, max)) = s.split_once(" max ") {
+        // "Q req/I max M"
+        let (quantity, interval) = split_rate(s, rate)?;
+        let quantity = quantity.trim();
+        let quantity = quantity.strip_suffix("req").unwrap_or(quantity);
+        (quantity, interval, max)
+    } else {
+        // "Q/I/M"
+        let mut parts = s.split('/');
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(quantity), Some(interval), Some(max), None) => (quantity, interval, max),
+            _ => return Err(ParseError::InvalidFormat(s.to_string())),
+        }
+    };
+
+    Ok(RateConfig {
+        quantity: parse_number(quantity)
+            .ok_or_else(|| ParseError::InvalidQuantity(quantity.trim().to_string()))?
+            .into(),
+        interval: parse_interval(interval)
+            .ok_or_else(|| ParseError::InvalidInterval(interval.trim().to_string()))?,
+        max: parse_number(max)
+            .ok_or_else(|| ParseError::InvalidMax(max.trim().to_string()))?
+            .into(),
+    })
+}
+
+/// Split a `"Q/I"` rate into its quantity and interval
+fn split_rate<'a>(spec: &str, rate: &'a str) -> Result<(&'a str, &'a str), ParseError> {
+    rate.split_once('/')
+        .ok_or_else(|| ParseError::InvalidFormat(spec.to_string()))
+}
+
+fn parse_number(s: &str) -> Option<u64> {
+    s.trim().parse().ok()
+}
+
+/// Parse an interval, such as `"10s"` or `"ms"`
+fn parse_interval(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number = if number.is_empty() {
+        1
+    } else {
+        number.parse().ok()?
+    };
+
+    let unit = match unit.trim() {
+        "ms" => Duration::from_millis(1),
+        "s" | "sec" | "second" => Duration::from_secs(1),
+        "m" | "min" | "minute" => Duration::from_secs(60),
+        "h" | "hr" | "hour" => Duration::from_secs(3600),
+        _ => return None,
+    };
+
+    unit.checked_mul(number)
+}
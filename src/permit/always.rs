@@ -18,7 +18,10 @@ impl AlwaysPermitter {
 
 impl Permitter for AlwaysPermitter {
     fn get_permit(&self) -> Option<Permit> {
-        Some(Permit::new(AlwaysPermit, Arc::downgrade(&self.inner)))
+        Some(Permit::for_bucket(
+            AlwaysPermit,
+            Arc::downgrade(&self.inner),
+        ))
     }
 
     fn belongs(&self, permit: &Permit) -> bool {
@@ -27,6 +30,10 @@ impl Permitter for AlwaysPermitter {
             .map(|inner| std::ptr::eq(Arc::as_ptr(&self.inner).cast(), inner))
             .unwrap_or(false)
     }
+
+    fn wait_for(&self) -> Option<std::time::Duration> {
+        Some(std::time::Duration::ZERO)
+    }
 }
 
 pub(crate) struct AlwaysPermit;
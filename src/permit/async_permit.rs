@@ -0,0 +1,83 @@
+//! # Asynchronous permits
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Weak,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use super::{Permit, Permitter};
+use crate::{
+    background::{self, Sleep},
+    inner::Inner,
+    Error,
+};
+
+/// Delay before retrying when a permit should be available, but another caller took the tokens
+const RETRY_DELAY: Duration = Duration::from_millis(1);
+
+/// Future resolving to a [`Permit`] once the bucket can grant one
+///
+/// While the bucket cannot grant a [`Permit`], this sleeps until it expects to be able to. This
+/// resolves to [`Error::BucketDropped`] if all clones of the bucket were dropped in the meantime.
+///
+/// This uses the timers of the `tokio` or `async-std` runtime, depending on the enabled feature.
+/// With `tokio`, this must be polled from within a Tokio runtime.
+pub struct AsyncPermit {
+    permitter: Weak<dyn Permitter>,
+    inner: Weak<Inner>,
+    sleep: Option<Sleep>,
+}
+
+impl AsyncPermit {
+    pub(crate) fn new(permitter: Weak<dyn Permitter>, inner: Weak<Inner>) -> Self {
+        Self {
+            permitter,
+            inner,
+            sleep: None,
+        }
+    }
+}
+
+impl Future for AsyncPermit {
+    type Output = Result<Permit, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        loop {
+            if let Some(sleep) = self.sleep.as_mut() {
+                if sleep.as_mut().poll(cx).is_pending() {
+                    return Poll::Pending;
+                }
+                self.sleep = None;
+            }
+
+            let (Some(permitter), Some(inner)) = (self.permitter.upgrade(), self.inner.upgrade())
+            else {
+                return Poll::Ready(Err(Error::BucketDropped));
+            };
+
+            if let Some(permit) = permitter.get_permit() {
+                return Poll::Ready(Ok(permit));
+            }
+
+            match permitter.wait_for() {
+                Some(wait_for) if wait_for > Duration::ZERO => {
+                    self.sleep = Some(background::sleep(wait_for));
+                }
+                // A permit should be available now, but another caller might have used the tokens
+                // concurrently. Sleep briefly instead of spinning until they are refilled.
+                Some(_) => {
+                    self.sleep = Some(background::sleep(RETRY_DELAY));
+                }
+                None => {
+                    return Poll::Ready(Err(Error::NotEnoughTokens {
+                        available: inner.available().into(),
+                        wait_for: None,
+                    }))
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! # Batch permits
+
+use std::{sync::Weak, time::Instant};
+
+use super::{InnerPermit, Permit};
+use crate::{inner::Inner, Error};
+
+/// Permit covering a fixed number of token acquisitions
+///
+/// This avoids acquiring a new [`Permit`] for every acquisition in tight loops. Once all uses are
+/// exhausted, the [`BatchPermit`] is invalid.
+pub struct BatchPermit {
+    inner: Box<dyn InnerPermit>,
+    bucket: Weak<Inner>,
+    remaining: usize,
+    expires_at: Option<Instant>,
+    /// Threshold of the original [`Permit`], checked again on each acquisition
+    min_available: u64,
+}
+
+impl BatchPermit {
+    pub(crate) fn new(permit: Permit, count: usize) -> Self {
+        Self {
+            inner: permit.inner,
+            bucket: permit.bucket,
+            remaining: count,
+            expires_at: permit.expires_at,
+            min_available: permit.min_available,
+        }
+    }
+
+    /// Number of acquisitions left for this [`BatchPermit`]
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Try to acquire `num` number of tokens, using one of the acquisitions for this permit.
+    ///
+    /// This will return [`Error::InvalidPermit`] if all acquisitions were already used, or
+    /// [`Error::ExpiredPermit`] if the original [`Permit`] had a time to live that elapsed.
+    pub fn try_acquire(&mut self, num: u64) -> Result<u64, Error> {
+        if self.remaining == 0 {
+            return Err(Error::InvalidPermit);
+        }
+        if self
+            .expires_at
+            .is_some_and(|expires_at| Instant::now() >= expires_at)
+        {
+            return Err(Error::ExpiredPermit);
+        }
+        let bucket = self.bucket.upgrade().ok_or(Error::BucketDropped)?;
+
+        self.remaining -= 1;
+        self.inner.notify(num);
+        bucket.try_acquire_above(num, self.min_available)
+    }
+}
@@ -0,0 +1,70 @@
+//! # Custom permits
+
+use std::sync::Arc;
+
+use super::{Permit, Permitter};
+use crate::{inner::Inner, BuildError};
+
+/// Wrapper binding [`Permit`]s from a user-provided [`Permitter`] to the bucket
+pub(crate) struct CustomPermitter {
+    permitter: Arc<dyn Permitter>,
+    inner: Arc<Inner>,
+}
+
+impl CustomPermitter {
+    pub(crate) fn new(permitter: Arc<dyn Permitter>, inner: Arc<Inner>) -> Self {
+        Self { permitter, inner }
+    }
+}
+
+impl Permitter for CustomPermitter {
+    fn get_permit(&self) -> Option<Permit> {
+        self.permitter
+            .get_permit()
+            .map(|permit| permit.bind(&self.inner))
+    }
+
+    fn get_permit_checked(&self) -> Result<Permit, Option<u64>> {
+        self.permitter
+            .get_permit_checked()
+            .map(|permit| permit.bind(&self.inner))
+    }
+
+    fn get_permit_with_priority(&self, priority: u8) -> Option<Permit> {
+        self.permitter
+            .get_permit_with_priority(priority)
+            .map(|permit| permit.bind(&self.inner))
+    }
+
+    fn get_permit_for(&self, caller_id: u64) -> Option<Permit> {
+        self.permitter
+            .get_permit_for(caller_id)
+            .map(|permit| permit.bind(&self.inner))
+    }
+
+    fn get_permit_weighted(&self, weight: u64) -> Option<Permit> {
+        self.permitter
+            .get_permit_weighted(weight)
+            .map(|permit| permit.bind(&self.inner))
+    }
+
+    fn belongs(&self, permit: &Permit) -> bool {
+        permit
+            .addr()
+            .map(|inner| std::ptr::eq(Arc::as_ptr(&self.inner).cast(), inner))
+            .unwrap_or(false)
+            && self.permitter.belongs(permit)
+    }
+
+    fn threshold(&self) -> Option<u64> {
+        self.permitter.threshold()
+    }
+
+    fn wait_for(&self) -> Option<std::time::Duration> {
+        self.permitter.wait_for()
+    }
+
+    fn set_threshold(&self, threshold: u64) -> Result<(), BuildError> {
+        self.permitter.set_threshold(threshold)
+    }
+}
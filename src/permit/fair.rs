@@ -0,0 +1,142 @@
+//! # Fair permits
+//!
+//! Grant permits in turn to callers competing for the same bucket, so that a hot caller cannot
+//! monopolize them. Each caller has a virtual finish time, incremented by the number of tokens it
+//! requests, and a caller only gets a permit if no other active caller has an earlier virtual
+//! finish time.
+
+use super::{InnerPermit, Permit, Permitter};
+use crate::inner::Inner;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+/// Callers that haven't requested a permit for this long are ignored until they come back
+const IDLE_TIMEOUT: Duration = Duration::from_secs(1);
+
+pub(crate) struct FairPermitter {
+    callers: Arc<Callers>,
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Callers {
+    entries: Mutex<HashMap<u64, Caller>>,
+}
+
+struct Caller {
+    /// Virtual finish time, the number of tokens requested so far
+    finish: u64,
+    /// Time of the last permit request, since the start of the bucket
+    last_seen: Duration,
+}
+
+impl Callers {
+    fn lock(&self) -> MutexGuard<'_, HashMap<u64, Caller>> {
+        // Finish times are only updated with single assignments, a panic while holding the lock
+        // can't leave them in an inconsistent state
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl FairPermitter {
+    pub(crate) fn new(inner: Arc<Inner>) -> Self {
+        Self {
+            callers: Arc::default(),
+            inner,
+        }
+    }
+}
+
+impl Permitter for FairPermitter {
+    fn get_permit(&self) -> Option<Permit> {
+        self.get_permit_for(0)
+    }
+
+    fn get_permit_for(&self, caller_id: u64) -> Option<Permit> {
+        let now = self.inner.elapsed();
+        let mut callers = self.callers.lock();
+
+        let is_active = |caller: &Caller| now.saturating_sub(caller.last_seen) <= IDLE_TIMEOUT;
+
+        // Forget idle callers, so that one-off callers don't accumulate. They start over at the
+        // current virtual time when they come back.
+        callers.retain(|id, caller| *id == caller_id || is_active(caller));
+
+        // Earliest virtual finish time of the other active callers
+        let earliest = callers
+            .iter()
+            .filter(|(id, caller)| **id != caller_id && is_active(caller))
+            .map(|(_, caller)| caller.finish)
+            .min();
+
+        let caller = callers.entry(caller_id).or_insert(Caller {
+            finish: 0,
+            last_seen: Duration::ZERO,
+        });
+        // New or idle callers start at the current virtual time, instead of catching up on the
+        // tokens they didn't request
+        if !is_active(caller) || caller.finish == 0 {
+            caller.finish = caller.finish.max(earliest.unwrap_or(0));
+        }
+        caller.last_seen = now;
+
+        if earliest.is_some_and(|earliest| caller.finish > earliest) {
+            return None;
+        }
+
+        Some(Permit::for_bucket(
+            FairPermit {
+                caller_id,
+                callers: self.callers.clone(),
+            },
+            Arc::downgrade(&self.inner),
+        ))
+    }
+
+    fn belongs(&self, permit: &Permit) -> bool {
+        permit
+            .addr()
+            .map(|inner| std::ptr::eq(Arc::as_ptr(&self.inner).cast(), inner))
+            .unwrap_or(false)
+    }
+}
+
+pub(crate) struct FairPermit {
+    caller_id: u64,
+    callers: Arc<Callers>,
+}
+
+impl InnerPermit for FairPermit {
+    fn notify(&self, num: u64) {
+        if let Some(caller) = self.callers.lock().get_mut(&self.caller_id) {
+            caller.finish = caller.finish.saturating_add(num);
+        }
+    }
+}
+
+/// Identifier for a caller of [`Bucket::try_permit_for`](crate::Bucket::try_permit_for), derived
+/// from any hashable value
+///
+/// The same value always gives the same identifier within a process, but identifiers should not
+/// be persisted.
+///
+/// ```rust
+/// use threshold_bucket::permit::caller_id;
+///
+/// assert_eq!(caller_id("worker-1"), caller_id("worker-1"));
+/// assert_ne!(caller_id("worker-1"), caller_id("worker-2"));
+/// ```
+pub fn caller_id<T>(caller: &T) -> u64
+where
+    T: Hash + ?Sized,
+{
+    let mut hasher = DefaultHasher::new();
+    caller.hash(&mut hasher);
+    hasher.finish()
+}
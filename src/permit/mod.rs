@@ -2,27 +2,171 @@
 //!
 //! This module contains the different types of [`Permit`]s supported by this crate:
 //!
-//! * [`threshold::ThresholdPermit`]
+//! * Threshold-based permits (see [`ThresholdConfig`])
+//! * Priority-based permits, with thresholds depending on the caller's priority (see
+//!   [`PriorityConfig`])
+//! * Weight-based permits, with thresholds depending on the weight of the request (see
+//!   [`WeightedThresholdConfig`])
+//! * Fair permits, granted in turn to competing callers (see
+//!   [`Builder::fair`](crate::Builder::fair))
+//! * [`BatchPermit`], covering multiple token acquisitions
+//!
+//! Custom permit logic can be used by implementing [`Permitter`], and passing it to
+//! [`Builder::with_permitter`](crate::Builder::with_permitter).
 
-use crate::inner::Inner;
-use std::sync::{Arc, Weak};
+use crate::{inner::Inner, BuildError};
+use std::{
+    fmt,
+    sync::{Arc, Weak},
+    time::{Duration, Instant},
+};
 
 pub(crate) mod always;
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+mod async_permit;
+mod batch;
+pub(crate) mod custom;
+pub(crate) mod fair;
+pub(crate) mod priority;
 pub(crate) mod threshold;
+pub(crate) mod ttl;
+pub(crate) mod weighted_threshold;
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+pub use async_permit::AsyncPermit;
+pub use batch::BatchPermit;
+pub use fair::caller_id;
+pub use priority::PriorityConfig;
 pub use threshold::ThresholdConfig;
+pub use weighted_threshold::WeightedThresholdConfig;
 
 /// Trait that grants a [`Permit`] if conditions are met.
-pub(crate) trait Permitter {
+///
+/// Permitters are shared between all clones of a [`Bucket`](crate::Bucket), and therefore must be
+/// [`Send`] and [`Sync`].
+///
+/// ```rust
+/// # use std::time::Duration;
+/// use std::sync::atomic::{AtomicBool, Ordering};
+/// use threshold_bucket::{
+///     permit::{InnerPermit, Permit, Permitter},
+///     refill::RateConfig,
+///     Bucket,
+/// };
+///
+/// /// Permitter that grants every other permit
+/// #[derive(Default)]
+/// struct Alternating {
+///     deny: AtomicBool,
+/// }
+///
+/// impl Permitter for Alternating {
+///     fn get_permit(&self) -> Option<Permit> {
+///         if self.deny.fetch_xor(true, Ordering::AcqRel) {
+///             None
+///         } else {
+///             Some(Permit::new(AlternatingPermit))
+///         }
+///     }
+/// }
+///
+/// struct AlternatingPermit;
+///
+/// impl InnerPermit for AlternatingPermit {
+///     fn notify(&self, _num: u64) {}
+/// }
+///
+/// let bucket = Bucket::builder()
+///     .rate(RateConfig {
+///         quantity: 10.into(),
+///         interval: Duration::from_secs(1),
+///         max: 100.into(),
+///     })
+///     .initial(100)
+///     .with_permitter(Alternating::default())
+///     .build()?;
+///
+/// let permit = bucket.try_permit().unwrap();
+/// assert!(bucket.try_permit().is_err());
+/// assert_eq!(bucket.try_acquire(permit, 10)?, 10);
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+pub trait Permitter: Send + Sync {
     /// Get a new [`Permit`] for this [`Permitter`]
     ///
     /// This should return [`None`] if it cannot allocate a permit at the moment.
     fn get_permit(&self) -> Option<Permit>;
 
+    /// Get a new [`Permit`] for this [`Permitter`], or the number of available tokens it was
+    /// refused at, if known
+    ///
+    /// [`Bucket::try_permit_checked`](crate::Bucket::try_permit_checked) reports this number of
+    /// tokens, so that it matches the decision of the permitter. By default, this calls
+    /// [`Permitter::get_permit`], and the bucket reports its own number of available tokens.
+    fn get_permit_checked(&self) -> Result<Permit, Option<u64>> {
+        self.get_permit().ok_or(None)
+    }
+
+    /// Get a new [`Permit`] for a caller with the given `priority`
+    ///
+    /// By default, priorities are ignored and this is equivalent to [`Permitter::get_permit`].
+    fn get_permit_with_priority(&self, _priority: u8) -> Option<Permit> {
+        self.get_permit()
+    }
+
+    /// Get a new [`Permit`] for the caller identified by `caller_id`
+    ///
+    /// By default, callers are ignored and this is equivalent to [`Permitter::get_permit`].
+    fn get_permit_for(&self, _caller_id: u64) -> Option<Permit> {
+        self.get_permit()
+    }
+
+    /// Get a new [`Permit`] for a request with the given `weight`
+    ///
+    /// By default, weights are ignored and this is equivalent to [`Permitter::get_permit`].
+    fn get_permit_weighted(&self, _weight: u64) -> Option<Permit> {
+        self.get_permit()
+    }
+
     /// Check if the [`Permit`] belongs to this [`Permitter`]
-    fn belongs(&self, permit: &Permit) -> bool;
+    ///
+    /// For custom permitters, the bucket already checks that the [`Permit`] was granted through
+    /// it before calling this method.
+    fn belongs(&self, _permit: &Permit) -> bool {
+        true
+    }
+
+    /// Minimum number of available tokens to grant a [`Permit`], if this [`Permitter`] has one
+    ///
+    /// This is only used for display purposes. By default, this returns [`None`].
+    fn threshold(&self) -> Option<u64> {
+        None
+    }
+
+    /// Duration until this [`Permitter`] could grant a new [`Permit`]
+    ///
+    /// This should return [`None`] if this cannot be determined.
+    fn wait_for(&self) -> Option<Duration> {
+        None
+    }
+    /// Change the minimum number of available tokens to grant a [`Permit`]
+    ///
+    /// This is called by [`Bucket::set_threshold`](crate::Bucket::set_threshold), after checking
+    /// that `threshold` is lower than the maximum number of tokens. By default, this returns
+    /// [`BuildError::UnsupportedPermitter`], for permitters without a single threshold.
+    fn set_threshold(&self, _threshold: u64) -> Result<(), BuildError> {
+        Err(BuildError::UnsupportedPermitter)
+    }
 }
 
-pub(crate) trait InnerPermit {
+/// Permit-specific logic, notified when a [`Permit`] is used
+///
+/// Permits can be held across `.await` points and used from other threads, and therefore must be
+/// [`Send`] and [`Sync`].
+pub trait InnerPermit: Send + Sync {
+    /// Called with the number of tokens requested when the [`Permit`] is used
+    ///
+    /// This is also called with `0` when the permit is released with [`Permit::cancel`], so that
+    /// permitters reserving tokens for their permits can release them.
     fn notify(&self, num: u64);
 }
 
@@ -30,18 +174,105 @@ pub(crate) trait InnerPermit {
 pub struct Permit {
     inner: Box<dyn InnerPermit>,
     bucket: Weak<Inner>,
+    priority: u8,
+    expires_at: Option<Instant>,
+    /// Minimum number of tokens that must still be available when the permit is used
+    min_available: u64,
+}
+
+impl fmt::Debug for Permit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bucket = match self.bucket.upgrade() {
+            Some(_) => "<live>",
+            None => "<dropped>",
+        };
+        f.debug_struct("Permit")
+            .field("bucket", &format_args!("{bucket}"))
+            .field("priority", &self.priority)
+            .field("expired", &!self.is_valid())
+            .finish()
+    }
 }
 
 impl Permit {
-    pub(crate) fn new<I>(inner: I, bucket: Weak<Inner>) -> Self
+    /// Create a new [`Permit`], for use in custom [`Permitter`]s
+    pub fn new<I>(inner: I) -> Self
+    where
+        I: InnerPermit + 'static,
+    {
+        Self::for_bucket(inner, Weak::new())
+    }
+
+    pub(crate) fn for_bucket<I>(inner: I, bucket: Weak<Inner>) -> Self
     where
         I: InnerPermit + 'static,
     {
         Self {
             inner: Box::new(inner),
             bucket,
+            priority: 0,
+            expires_at: None,
+            min_available: 0,
+        }
+    }
+
+    /// Expire the [`Permit`] after `ttl`
+    ///
+    /// Expired permits are refused with [`Error::ExpiredPermit`](crate::Error::ExpiredPermit).
+    pub fn with_ttl(self, ttl: Duration) -> Self {
+        Self {
+            expires_at: Instant::now().checked_add(ttl),
+            ..self
         }
     }
+
+    /// Whether the [`Permit`] has not expired yet
+    pub fn is_valid(&self) -> bool {
+        self.expires_at
+            .is_none_or(|expires_at| Instant::now() < expires_at)
+    }
+
+    /// Set the priority of the caller this [`Permit`] was granted to
+    pub fn with_priority(self, priority: u8) -> Self {
+        Self { priority, ..self }
+    }
+
+    /// Priority of the caller this [`Permit`] was granted to
+    ///
+    /// This is `0`, the lowest priority, unless the permit was granted with
+    /// [`Permitter::get_permit_with_priority`].
+    pub fn priority(&self) -> u8 {
+        self.priority
+    }
+
+    /// Release the [`Permit`] without acquiring any tokens
+    ///
+    /// This notifies the permit with `0` tokens, whereas dropping it doesn't notify it at all.
+    pub fn cancel(self) {
+        self.notify(0);
+    }
+
+    /// Require at least `min_available` tokens when the [`Permit`] is used, as when it was granted
+    pub(crate) fn with_min_available(self, min_available: u64) -> Self {
+        Self {
+            min_available,
+            ..self
+        }
+    }
+
+    /// Minimum number of tokens that must be available to use the [`Permit`]
+    pub(crate) fn min_available(&self) -> u64 {
+        self.min_available
+    }
+
+    /// Bind the [`Permit`] to a bucket
+    pub(crate) fn bind(self, bucket: &Arc<Inner>) -> Self {
+        Self {
+            bucket: Arc::downgrade(bucket),
+            ..self
+        }
+    }
+
     pub(crate) fn addr(&self) -> Option<*const Inner> {
         Weak::upgrade(&self.bucket).map(|b| Arc::as_ptr(&b))
     }
@@ -52,7 +283,35 @@ impl Permit {
 }
 
 /// Configuration for permits
+#[derive(Clone)]
 pub(crate) enum PermitConfig {
     /// Threshold-based permit allocation
     Threshold(ThresholdConfig),
+    /// Threshold-based permit allocation, as a percentage of the maximum number of tokens
+    ThresholdPercent(u8),
+    /// Priority-based permit allocation
+    Priority(PriorityConfig),
+    /// Weight-based permit allocation
+    WeightedThreshold(WeightedThresholdConfig),
+    /// Round-robin permit allocation across callers
+    Fair,
+    /// Custom permit allocation
+    Custom(Arc<dyn Permitter>),
+}
+
+impl fmt::Debug for PermitConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PermitConfig::Threshold(config) => f.debug_tuple("Threshold").field(config).finish(),
+            PermitConfig::ThresholdPercent(pct) => {
+                f.debug_tuple("ThresholdPercent").field(pct).finish()
+            }
+            PermitConfig::Priority(config) => f.debug_tuple("Priority").field(config).finish(),
+            PermitConfig::WeightedThreshold(config) => {
+                f.debug_tuple("WeightedThreshold").field(config).finish()
+            }
+            PermitConfig::Fair => f.write_str("Fair"),
+            PermitConfig::Custom(_) => f.debug_tuple("Custom").finish_non_exhaustive(),
+        }
+    }
 }
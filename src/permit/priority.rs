@@ -0,0 +1,127 @@
+//! # Priority-based permits
+//!
+//! Grant permits if the total number of available tokens is greater than a threshold that depends
+//! on the caller's priority. Higher priorities usually have lower thresholds, so that they still
+//! get permits when the bucket is running low.
+
+use super::{InnerPermit, Permitter};
+use crate::{inner::Inner, Permit};
+use std::sync::Arc;
+
+pub(crate) struct PriorityPermitter {
+    /// `(priority, threshold)` tiers, sorted by descending priority
+    tiers: Vec<(u8, u64)>,
+    inner: Arc<Inner>,
+}
+
+impl PriorityPermitter {
+    /// Threshold for a caller with the given `priority`
+    ///
+    /// This is the threshold of the highest tier with a priority lower than or equal to
+    /// `priority`, or [`None`] if `priority` is lower than all tiers.
+    fn threshold_for(&self, priority: u8) -> Option<u64> {
+        self.tiers
+            .iter()
+            .find(|(tier, _)| *tier <= priority)
+            .map(|(_, threshold)| *threshold)
+    }
+
+    /// Permit for a caller with the given `priority`, or the number of available tokens it was
+    /// refused at
+    fn permit_with_priority(&self, priority: u8) -> Result<Permit, Option<u64>> {
+        let threshold = self.threshold_for(priority).ok_or(None)?;
+        let available = self.inner.available_after_refill();
+        if available >= threshold {
+            Ok(
+                Permit::for_bucket(PriorityPermit, Arc::downgrade(&self.inner))
+                    .with_priority(priority)
+                    .with_min_available(threshold),
+            )
+        } else {
+            Err(Some(available))
+        }
+    }
+}
+
+impl Permitter for PriorityPermitter {
+    fn get_permit(&self) -> Option<Permit> {
+        self.get_permit_with_priority(0)
+    }
+
+    fn get_permit_checked(&self) -> Result<Permit, Option<u64>> {
+        self.permit_with_priority(0)
+    }
+
+    fn get_permit_with_priority(&self, priority: u8) -> Option<Permit> {
+        self.permit_with_priority(priority).ok()
+    }
+
+    fn belongs(&self, permit: &Permit) -> bool {
+        permit
+            .addr()
+            .map(|inner| std::ptr::eq(Arc::as_ptr(&self.inner).cast(), inner))
+            .unwrap_or(false)
+            && self.threshold_for(permit.priority()).is_some()
+    }
+
+    /// Threshold for callers without a priority
+    fn threshold(&self) -> Option<u64> {
+        self.threshold_for(0)
+    }
+
+    fn wait_for(&self) -> Option<std::time::Duration> {
+        self.threshold_for(0)
+            .and_then(|threshold| self.inner.wait_for(threshold))
+    }
+}
+
+pub(crate) struct PriorityPermit;
+
+impl InnerPermit for PriorityPermit {
+    fn notify(&self, _num: u64) {}
+}
+
+/// Priority configuration
+///
+/// As with [`ThresholdConfig`](super::ThresholdConfig), the threshold of the permit is checked
+/// again when it is used.
+///
+/// ```rust
+/// # use std::time::Duration;
+/// use threshold_bucket::{permit::PriorityConfig, refill::RateConfig, Bucket};
+///
+/// let bucket = Bucket::builder()
+///     .rate(RateConfig {
+///         quantity: 10.into(),
+///         interval: Duration::from_secs(1),
+///         max: 100.into(),
+///     })
+///     .initial(30)
+///     .priority(PriorityConfig {
+///         // Free tier callers need at least 50 tokens, premium callers only 10
+///         tiers: vec![(0, 50), (10, 10)],
+///     })
+///     .build()?;
+///
+/// assert!(bucket.try_permit_with_priority(0).is_err());
+/// assert!(bucket.try_permit_with_priority(10).is_ok());
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PriorityConfig {
+    /// `(priority, threshold)` tiers
+    ///
+    /// A caller gets a permit if the number of available tokens is greater than or equal to the
+    /// threshold of the highest tier with a priority lower than or equal to theirs. Callers with
+    /// a priority lower than all tiers never get a permit.
+    pub tiers: Vec<(u8, u64)>,
+}
+
+impl PriorityConfig {
+    pub(crate) fn into_permitter(self, inner: Arc<Inner>) -> PriorityPermitter {
+        let mut tiers = self.tiers;
+        tiers.sort_unstable_by_key(|&(priority, _)| std::cmp::Reverse(priority));
+        PriorityPermitter { tiers, inner }
+    }
+}
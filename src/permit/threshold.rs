@@ -3,21 +3,34 @@
 //! Grant permits if the total number of available tokens is greater than a specified threshold.
 
 use super::{InnerPermit, Permitter};
-use crate::{inner::Inner, Permit};
-use std::sync::Arc;
+use crate::{inner::Inner, BuildError, Permit, TokenCount};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
 
 pub(crate) struct ThresholdPermitter {
-    config: ThresholdConfig,
+    /// Threshold, which can be changed with [`Bucket::set_threshold`](crate::Bucket::set_threshold)
+    threshold: AtomicU64,
     inner: Arc<Inner>,
 }
 
 impl Permitter for ThresholdPermitter {
     fn get_permit(&self) -> Option<Permit> {
-        let available = self.inner.available();
-        if available >= self.config.threshold {
-            Some(Permit::new(ThresholdPermit, Arc::downgrade(&self.inner)))
+        self.get_permit_checked().ok()
+    }
+
+    fn get_permit_checked(&self) -> Result<Permit, Option<u64>> {
+        // Permits keep the threshold they were granted at, even if it changes before they are used
+        let threshold = self.threshold.load(Ordering::Acquire);
+        let available = self.inner.available_after_refill();
+        if available >= threshold {
+            Ok(
+                Permit::for_bucket(ThresholdPermit, Arc::downgrade(&self.inner))
+                    .with_min_available(threshold),
+            )
         } else {
-            None
+            Err(Some(available))
         }
     }
 
@@ -27,6 +40,19 @@ impl Permitter for ThresholdPermitter {
             .map(|inner| std::ptr::eq(Arc::as_ptr(&self.inner).cast(), inner))
             .unwrap_or(false)
     }
+
+    fn threshold(&self) -> Option<u64> {
+        Some(self.threshold.load(Ordering::Acquire))
+    }
+
+    fn wait_for(&self) -> Option<std::time::Duration> {
+        self.inner.wait_for(self.threshold.load(Ordering::Acquire))
+    }
+
+    fn set_threshold(&self, threshold: u64) -> Result<(), BuildError> {
+        self.threshold.store(threshold, Ordering::Release);
+        Ok(())
+    }
 }
 
 pub(crate) struct ThresholdPermit;
@@ -36,16 +62,24 @@ impl InnerPermit for ThresholdPermit {
 }
 
 /// Threshold configuration
+///
+/// The threshold is checked again when the permit is used with
+/// [`Bucket::try_acquire`](crate::Bucket::try_acquire), atomically with the acquisition, so that
+/// other threads draining the bucket in the meantime can't take it further below the threshold.
+/// A successful acquisition of `num` tokens always starts with at least `threshold` tokens
+/// available, and leaves at least `threshold - num`. Partial, range and overdraft acquisitions
+/// check the threshold the same way before taking or borrowing any tokens.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ThresholdConfig {
     /// Threshold under which the bucket will refuse to grant new permits.
-    pub threshold: u64,
+    pub threshold: TokenCount,
 }
 
 impl ThresholdConfig {
     pub(crate) fn into_permitter(self, inner: Arc<Inner>) -> ThresholdPermitter {
         ThresholdPermitter {
-            config: self,
+            threshold: AtomicU64::new(self.threshold.into()),
             inner,
         }
     }
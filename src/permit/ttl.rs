@@ -0,0 +1,66 @@
+//! # Permits with a time to live
+
+use std::{sync::Arc, time::Duration};
+
+use super::{Permit, Permitter};
+use crate::BuildError;
+
+/// Wrapper expiring the [`Permit`]s of a [`Permitter`] after a fixed duration
+pub(crate) struct TtlPermitter {
+    permitter: Arc<dyn Permitter>,
+    ttl: Duration,
+}
+
+impl TtlPermitter {
+    pub(crate) fn new(permitter: Arc<dyn Permitter>, ttl: Duration) -> Self {
+        Self { permitter, ttl }
+    }
+}
+
+impl Permitter for TtlPermitter {
+    fn get_permit(&self) -> Option<Permit> {
+        self.permitter
+            .get_permit()
+            .map(|permit| permit.with_ttl(self.ttl))
+    }
+
+    fn get_permit_checked(&self) -> Result<Permit, Option<u64>> {
+        self.permitter
+            .get_permit_checked()
+            .map(|permit| permit.with_ttl(self.ttl))
+    }
+
+    fn get_permit_with_priority(&self, priority: u8) -> Option<Permit> {
+        self.permitter
+            .get_permit_with_priority(priority)
+            .map(|permit| permit.with_ttl(self.ttl))
+    }
+
+    fn get_permit_for(&self, caller_id: u64) -> Option<Permit> {
+        self.permitter
+            .get_permit_for(caller_id)
+            .map(|permit| permit.with_ttl(self.ttl))
+    }
+
+    fn get_permit_weighted(&self, weight: u64) -> Option<Permit> {
+        self.permitter
+            .get_permit_weighted(weight)
+            .map(|permit| permit.with_ttl(self.ttl))
+    }
+
+    fn belongs(&self, permit: &Permit) -> bool {
+        permit.is_valid() && self.permitter.belongs(permit)
+    }
+
+    fn threshold(&self) -> Option<u64> {
+        self.permitter.threshold()
+    }
+
+    fn wait_for(&self) -> Option<Duration> {
+        self.permitter.wait_for()
+    }
+
+    fn set_threshold(&self, threshold: u64) -> Result<(), BuildError> {
+        self.permitter.set_threshold(threshold)
+    }
+}
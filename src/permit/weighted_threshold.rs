@@ -0,0 +1,125 @@
+//! # Weight-based permits
+//!
+//! Grant permits if the total number of available tokens is greater than a threshold that depends
+//! on the weight of the request. Heavier requests usually have higher thresholds, so that light
+//! requests still get permits when the bucket is running low.
+
+use super::{InnerPermit, Permitter};
+use crate::{inner::Inner, Permit};
+use std::sync::Arc;
+
+pub(crate) struct WeightedThresholdPermitter {
+    /// `(weight, threshold)` tiers, sorted by ascending weight
+    tiers: Vec<(u64, u64)>,
+    inner: Arc<Inner>,
+}
+
+impl WeightedThresholdPermitter {
+    /// Threshold for a request with the given `weight`
+    ///
+    /// This is the threshold of the heaviest tier with a weight lower than or equal to `weight`,
+    /// or of the lightest tier if `weight` is lighter than all tiers.
+    fn threshold_for(&self, weight: u64) -> Option<u64> {
+        self.tiers
+            .iter()
+            .rev()
+            .find(|(tier, _)| *tier <= weight)
+            .or(self.tiers.first())
+            .map(|(_, threshold)| *threshold)
+    }
+
+    /// Permit for a request with the given `weight`, or the number of available tokens it was
+    /// refused at
+    fn permit_weighted(&self, weight: u64) -> Result<Permit, Option<u64>> {
+        let threshold = self.threshold_for(weight).ok_or(None)?;
+        let available = self.inner.available_after_refill();
+        if available >= threshold {
+            Ok(
+                Permit::for_bucket(WeightedThresholdPermit, Arc::downgrade(&self.inner))
+                    .with_min_available(threshold),
+            )
+        } else {
+            Err(Some(available))
+        }
+    }
+}
+
+impl Permitter for WeightedThresholdPermitter {
+    fn get_permit(&self) -> Option<Permit> {
+        self.get_permit_weighted(0)
+    }
+
+    fn get_permit_checked(&self) -> Result<Permit, Option<u64>> {
+        self.permit_weighted(0)
+    }
+
+    fn get_permit_weighted(&self, weight: u64) -> Option<Permit> {
+        self.permit_weighted(weight).ok()
+    }
+
+    fn belongs(&self, permit: &Permit) -> bool {
+        permit
+            .addr()
+            .map(|inner| std::ptr::eq(Arc::as_ptr(&self.inner).cast(), inner))
+            .unwrap_or(false)
+    }
+
+    /// Threshold for the lightest requests
+    fn threshold(&self) -> Option<u64> {
+        self.threshold_for(0)
+    }
+
+    fn wait_for(&self) -> Option<std::time::Duration> {
+        self.threshold_for(0)
+            .and_then(|threshold| self.inner.wait_for(threshold))
+    }
+}
+
+pub(crate) struct WeightedThresholdPermit;
+
+impl InnerPermit for WeightedThresholdPermit {
+    fn notify(&self, _num: u64) {}
+}
+
+/// Weighted threshold configuration
+///
+/// As with [`ThresholdConfig`](super::ThresholdConfig), the threshold of the permit is checked
+/// again when it is used.
+///
+/// ```rust
+/// # use std::time::Duration;
+/// use threshold_bucket::{refill::RateConfig, Bucket};
+///
+/// let bucket = Bucket::builder()
+///     .rate(RateConfig {
+///         quantity: 10.into(),
+///         interval: Duration::from_secs(1),
+///         max: 100.into(),
+///     })
+///     .initial(50)
+///     // Light requests need at least 10 tokens, medium 30 and heavy 80
+///     .weighted_threshold(vec![(1, 10), (2, 30), (4, 80)])
+///     .build()?;
+///
+/// assert!(bucket.try_permit_weighted(2).is_ok());
+/// assert!(bucket.try_permit_weighted(4).is_err());
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WeightedThresholdConfig {
+    /// `(weight, threshold)` tiers
+    ///
+    /// A request gets a permit if the number of available tokens is greater than or equal to the
+    /// threshold of the heaviest tier with a weight lower than or equal to its own. Requests
+    /// lighter than all tiers use the threshold of the lightest tier.
+    pub tiers: Vec<(u64, u64)>,
+}
+
+impl WeightedThresholdConfig {
+    pub(crate) fn into_permitter(self, inner: Arc<Inner>) -> WeightedThresholdPermitter {
+        let mut tiers = self.tiers;
+        tiers.sort_unstable_by_key(|&(weight, _)| weight);
+        WeightedThresholdPermitter { tiers, inner }
+    }
+}
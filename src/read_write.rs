@@ -0,0 +1,51 @@
+//! Bucket with pre-configured costs for read and write operations
+
+use crate::{permit::Permit, Bucket, Error};
+
+/// # [`Bucket`] with read and write costs
+///
+/// Reads and writes acquire tokens from the same underlying [`Bucket`], but writes usually cost
+/// more tokens than reads.
+#[derive(Clone, Debug)]
+pub struct ReadWriteBucket {
+    bucket: Bucket,
+    read_cost: u64,
+    write_cost: u64,
+}
+
+impl ReadWriteBucket {
+    /// Create a new [`ReadWriteBucket`] on top of an existing [`Bucket`].
+    pub fn new(bucket: Bucket, read_cost: u64, write_cost: u64) -> Self {
+        Self {
+            bucket,
+            read_cost,
+            write_cost,
+        }
+    }
+
+    /// Underlying [`Bucket`].
+    pub fn bucket(&self) -> &Bucket {
+        &self.bucket
+    }
+
+    /// Try to acquire a [`Permit`].
+    #[deprecated(note = "use `ReadWriteBucket::try_permit` instead")]
+    pub fn get_permit(&self) -> Option<Permit> {
+        self.bucket.try_permit().ok()
+    }
+
+    /// Try to acquire a [`Permit`], returning [`Error::NotEnoughTokens`] if it cannot be granted.
+    pub fn try_permit(&self) -> Result<Permit, Error> {
+        self.bucket.try_permit()
+    }
+
+    /// Try to acquire the tokens for a read operation.
+    pub fn try_acquire_read(&self, permit: Permit) -> Result<u64, Error> {
+        self.bucket.try_acquire(permit, self.read_cost)
+    }
+
+    /// Try to acquire the tokens for a write operation.
+    pub fn try_acquire_write(&self, permit: Permit) -> Result<u64, Error> {
+        self.bucket.try_acquire(permit, self.write_cost)
+    }
+}
@@ -0,0 +1,32 @@
+//! Burst followed by a sustained refill rate
+
+use std::time::Duration;
+
+use super::rate::RateConfig;
+
+/// Burst refill configuration
+///
+/// The bucket starts with `burst` tokens, then adds `sustained_quantity` tokens every
+/// `sustained_interval`, like a [`RateConfig`].
+#[derive(Clone, Debug)]
+pub struct BurstConfig {
+    /// Tokens initially available in the bucket
+    pub burst: u64,
+    /// Tokens to add per interval once the bucket is created
+    pub sustained_quantity: u64,
+    /// Interval of time per refill
+    pub sustained_interval: Duration,
+    /// Maximum number of tokens in the bucket
+    pub max: u64,
+}
+
+impl BurstConfig {
+    /// Configuration for the sustained refill rate
+    pub(crate) fn rate(&self) -> RateConfig {
+        RateConfig {
+            quantity: self.sustained_quantity.into(),
+            interval: self.sustained_interval,
+            max: self.max.into(),
+        }
+    }
+}
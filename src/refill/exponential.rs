@@ -0,0 +1,182 @@
+//! Exponentially increasing refill rate
+
+use super::Refill;
+use crate::TokenCount;
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+/// Exponentially increasing refill rate
+///
+/// Every interval adds `current_quantity` tokens, then multiplies it by `multiplier`, up to
+/// `max_quantity`. Resetting the refill, e.g. with
+/// [`Bucket::reset_refill`](crate::Bucket::reset_refill), drops the quantity back to
+/// `initial_quantity`.
+pub(crate) struct ExponentialRefill {
+    initial_quantity: u64,
+    max_quantity: u64,
+    multiplier: f64,
+    interval: Duration,
+    max: u64,
+
+    /// Tokens added by the next refill
+    current_quantity: AtomicU64,
+    /// Time of the next refill, in nanoseconds since the start of the bucket
+    refill_at: AtomicU64,
+}
+
+impl ExponentialRefill {
+    /// Create a new exponential refill, with the next refill after `elapsed`
+    pub(crate) fn new(config: ExponentialConfig, elapsed: Duration) -> Self {
+        let refill = Self {
+            initial_quantity: config.initial_quantity,
+            max_quantity: config.max_quantity,
+            multiplier: config.multiplier,
+            interval: config.interval,
+            max: config.max,
+            current_quantity: AtomicU64::new(config.initial_quantity),
+            refill_at: AtomicU64::new(0),
+        };
+        refill.reset(elapsed);
+        refill
+    }
+
+    /// Quantity for the interval after one adding `quantity` tokens
+    fn grow(&self, quantity: u64) -> u64 {
+        ((quantity as f64 * self.multiplier) as u64).min(self.max_quantity)
+    }
+
+    /// Number of tokens added over `intervals` intervals, and the quantity after them
+    fn accumulate(&self, mut quantity: u64, intervals: u64) -> (u64, u64) {
+        let mut amount = 0u64;
+        for done in 0..intervals {
+            amount = amount.saturating_add(quantity);
+            let next = self.grow(quantity);
+            if next == quantity {
+                // The quantity doesn't change anymore, the remaining intervals add the same amount
+                let remaining = intervals - done - 1;
+                amount = amount.saturating_add(quantity.saturating_mul(remaining));
+                break;
+            }
+            quantity = next;
+        }
+        (amount, quantity)
+    }
+}
+
+impl Refill for ExponentialRefill {
+    fn refill(&self, elapsed: Duration, tokens: &AtomicU64) -> u64 {
+        let intervals = loop {
+            let refill_at = self.refill_at.load(Ordering::Acquire);
+
+            // Next refill is not due yet, return early
+            if elapsed.as_nanos() < refill_at as u128 {
+                return 0;
+            }
+
+            // 1 for the time until `refill_at`, then 1 for every interval since then
+            let intervals =
+                1 + (elapsed.as_nanos() - refill_at as u128) / self.interval.as_nanos().max(1);
+            let next_refill_at = refill_at as u128 + intervals * self.interval.as_nanos();
+
+            // Only the thread moving `refill_at` adds the tokens for these intervals
+            if self
+                .refill_at
+                .compare_exchange(
+                    refill_at,
+                    next_refill_at.min(u64::MAX as u128) as u64,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                )
+                .is_ok()
+            {
+                break intervals.min(u64::MAX as u128) as u64;
+            }
+        };
+
+        let quantity = self.current_quantity.load(Ordering::Acquire);
+        let (amount, next_quantity) = self.accumulate(quantity, intervals);
+        self.current_quantity
+            .store(next_quantity, Ordering::Release);
+
+        super::add_tokens(tokens, amount.into(), self.max)
+    }
+
+    /// Calculate the duration until the requested number of tokens will be available
+    fn wait_for(
+        &self,
+        elapsed: Duration,
+        available: TokenCount,
+        requested: TokenCount,
+    ) -> Option<Duration> {
+        let (available, requested) = (u64::from(available), u64::from(requested));
+        if requested <= available {
+            return Some(Duration::ZERO);
+        }
+        if requested > self.max {
+            return None;
+        }
+
+        let missing = requested - available;
+        let mut quantity = self.current_quantity.load(Ordering::Acquire);
+        let mut amount = 0u64;
+        let mut intervals = 0u64;
+        while amount < missing {
+            let next = self.grow(quantity);
+            if next == quantity {
+                // The quantity doesn't change anymore, count the remaining intervals at once
+                if quantity == 0 {
+                    return None;
+                }
+                intervals += (missing - amount).div_ceil(quantity);
+                break;
+            }
+            amount = amount.saturating_add(quantity);
+            quantity = next;
+            intervals += 1;
+        }
+
+        // The first refill happens at `refill_at`, then every interval after that
+        let refill_at = Duration::from_nanos(self.refill_at.load(Ordering::Acquire));
+        let intervals = u32::try_from(intervals - 1).ok()?;
+        Some(refill_at.saturating_sub(elapsed) + self.interval.checked_mul(intervals)?)
+    }
+
+    fn max(&self) -> u64 {
+        self.max
+    }
+
+    fn next_refill_in(&self, elapsed: Duration) -> Option<Duration> {
+        let refill_at = Duration::from_nanos(self.refill_at.load(Ordering::Acquire));
+        Some(refill_at.saturating_sub(elapsed))
+    }
+
+    fn reset(&self, elapsed: Duration) {
+        let refill_at = elapsed + self.interval;
+        self.refill_at
+            .store(refill_at.as_nanos() as u64, Ordering::Release);
+        self.current_quantity
+            .store(self.initial_quantity, Ordering::Release);
+    }
+}
+
+/// Exponential refill configuration
+///
+/// Every `interval`, the bucket receives the current quantity of tokens, starting at
+/// `initial_quantity`, and the quantity is multiplied by `multiplier`, up to `max_quantity`.
+/// This is useful to ramp up slowly after a downstream service recovers, resetting the quantity
+/// with [`Bucket::reset_refill`](crate::Bucket::reset_refill) when it fails again.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExponentialConfig {
+    /// Tokens added by the first interval
+    pub initial_quantity: u64,
+    /// Maximum number of tokens added per interval
+    pub max_quantity: u64,
+    /// Factor applied to the quantity after every interval
+    pub multiplier: f64,
+    /// Interval of time per refill
+    pub interval: Duration,
+    /// Maximum number of tokens in the bucket
+    pub max: u64,
+}
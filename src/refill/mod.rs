@@ -1,25 +1,276 @@
 //! Refill algoritms
 
-use self::rate::RateRefill;
-use crate::inner::Inner;
-use std::{sync::atomic::AtomicU64, time::Duration};
+use self::{
+    exponential::ExponentialRefill, quota::QuotaRefill, rate::RateRefill,
+    sliding::SlidingWindowRefill,
+};
+use crate::{
+    inner::{Inner, DEFAULT_MAX_RETRIES, UNDERFLOW_GUARD},
+    BuildError, Error, TokenCount,
+};
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
+mod burst;
+mod exponential;
+mod quota;
 pub(crate) mod rate;
+pub(crate) mod shared_max;
+pub(crate) mod sliding;
+pub use burst::BurstConfig;
+pub use exponential::ExponentialConfig;
+pub use quota::QuotaConfig;
 pub use rate::RateConfig;
+pub use sliding::SlidingWindowConfig;
 
-pub(crate) trait Refill {
-    fn refill(&self, elapsed: Duration, tokens: &AtomicU64);
-    fn wait_for(&self, available: u64, requested: u64) -> Option<Duration>;
+/// Add `amount` tokens to `tokens`, without exceeding `max`
+///
+/// Counts above [`UNDERFLOW_GUARD`] are transient underflows from the fast path of acquisitions,
+/// about to be reverted. This waits for them instead of skipping the refill, as the tokens for
+/// the intervals already claimed by the caller would be lost. As with acquisitions, it gives up
+/// after [`DEFAULT_MAX_RETRIES`] attempts, and adds nothing, if the count doesn't come back below
+/// [`UNDERFLOW_GUARD`]. Returns the number of tokens added.
+pub(crate) fn add_tokens(tokens: &AtomicU64, amount: u128, max: u64) -> u64 {
+    for _ in 0..DEFAULT_MAX_RETRIES {
+        let available = tokens.load(Ordering::Acquire);
+        if available > UNDERFLOW_GUARD {
+            std::hint::spin_loop();
+            continue;
+        }
+        if available >= max {
+            return 0;
+        }
+
+        let new = (available as u128 + amount).min(max as u128) as u64;
+        if tokens
+            .compare_exchange_weak(available, new, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+        {
+            return new - available;
+        }
+    }
+
+    0
 }
 
+/// Algorithm adding tokens to a [`Bucket`](crate::Bucket) over time
+///
+/// Custom refill algorithms can be used with [`Builder::with_refill`](crate::Builder::with_refill).
+///
+/// ```rust
+/// use std::{
+///     sync::atomic::{AtomicU64, Ordering},
+///     time::Duration,
+/// };
+/// use threshold_bucket::{refill::Refill, Bucket, TokenCount};
+///
+/// /// Refill that keeps the bucket full at all times
+/// struct AlwaysFull {
+///     max: u64,
+/// }
+///
+/// impl Refill for AlwaysFull {
+///     fn refill(&self, _elapsed: Duration, tokens: &AtomicU64) -> u64 {
+///         let previous = tokens.swap(self.max, Ordering::AcqRel);
+///         self.max.saturating_sub(previous)
+///     }
+///
+///     fn wait_for(
+///         &self,
+///         _elapsed: Duration,
+///         _available: TokenCount,
+///         requested: TokenCount,
+///     ) -> Option<Duration> {
+///         (requested <= self.max).then_some(Duration::ZERO)
+///     }
+///
+///     fn max(&self) -> u64 {
+///         self.max
+///     }
+/// }
+///
+/// let bucket = Bucket::builder().with_refill(AlwaysFull { max: 10 }).build()?;
+///
+/// let permit = bucket.try_permit().unwrap();
+/// assert_eq!(bucket.try_acquire(permit, 10)?, 10);
+/// assert_eq!(bucket.available(), 0);
+///
+/// let permit = bucket.try_permit().unwrap();
+/// assert_eq!(bucket.try_acquire(permit, 10)?, 10);
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+pub trait Refill {
+    /// Add tokens if a refill is due, `elapsed` being the time since the bucket was created
+    ///
+    /// Implementations should not increase `tokens` above [`Refill::max`]. Returns the number of
+    /// tokens added.
+    fn refill(&self, elapsed: Duration, tokens: &AtomicU64) -> u64;
+
+    /// Duration from `elapsed` until `requested` tokens will be available, if there are currently
+    /// `available` tokens
+    ///
+    /// This should return [`None`] if the requested number of tokens will never be available.
+    fn wait_for(
+        &self,
+        elapsed: Duration,
+        available: TokenCount,
+        requested: TokenCount,
+    ) -> Option<Duration>;
+
+    /// Maximum number of tokens in the bucket
+    fn max(&self) -> u64;
+
+    /// Duration from `elapsed` until the next refill adds tokens
+    ///
+    /// This is used to schedule background refills. By default, this returns [`None`] when the
+    /// refill schedule is unknown.
+    fn next_refill_in(&self, _elapsed: Duration) -> Option<Duration> {
+        None
+    }
+
+    /// Restart the refill schedule at `elapsed`, when the bucket is reset
+    ///
+    /// By default, this does nothing.
+    fn reset(&self, _elapsed: Duration) {}
+
+    /// Add `quantity` tokens every `interval` from now on, `elapsed` being the time since the
+    /// bucket was created
+    ///
+    /// Tokens due at `elapsed` with the previous rate should be added to `tokens` first, as with
+    /// [`Refill::refill`]. Returns the number of tokens added. By default, this returns
+    /// [`Error::UnsupportedOperation`], for refills without a rate.
+    fn set_rate(
+        &self,
+        _elapsed: Duration,
+        _tokens: &AtomicU64,
+        _quantity: TokenCount,
+        _interval: Duration,
+    ) -> Result<u64, Error> {
+        Err(Error::UnsupportedOperation)
+    }
+
+    /// Average number of tokens added per second, for display purposes
+    ///
+    /// By default, this returns [`None`], for refills without a constant rate.
+    fn effective_rate_per_second(&self) -> Option<f64> {
+        None
+    }
+}
+
+#[derive(Clone)]
 pub(crate) enum RefillConfig {
     Rate(RateConfig),
+    Burst(BurstConfig),
+    SlidingWindow(SlidingWindowConfig),
+    Exponential(ExponentialConfig),
+    Quota(QuotaConfig),
+    Custom(Arc<dyn Refill + Send + Sync>),
+}
+
+impl fmt::Debug for RefillConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RefillConfig::Rate(config) => f.debug_tuple("Rate").field(config).finish(),
+            RefillConfig::Burst(config) => f.debug_tuple("Burst").field(config).finish(),
+            RefillConfig::SlidingWindow(config) => {
+                f.debug_tuple("SlidingWindow").field(config).finish()
+            }
+            RefillConfig::Exponential(config) => {
+                f.debug_tuple("Exponential").field(config).finish()
+            }
+            RefillConfig::Quota(config) => f.debug_tuple("Quota").field(config).finish(),
+            RefillConfig::Custom(refill) => f
+                .debug_struct("Custom")
+                .field("max", &refill.max())
+                .finish_non_exhaustive(),
+        }
+    }
 }
 
 impl RefillConfig {
-    pub(crate) fn into_inner_bucket(self, initial: u64) -> Inner {
+    /// All the errors in the refill configuration
+    pub(crate) fn errors(&self) -> Vec<BuildError> {
+        let mut errors = Vec::new();
+        let (quantity, max) = match self {
+            RefillConfig::Rate(config) => {
+                if config.interval.is_zero() {
+                    errors.push(BuildError::InvalidRate);
+                }
+                (config.quantity.into(), config.max.into())
+            }
+            RefillConfig::Burst(config) => (config.sustained_quantity, config.max),
+            RefillConfig::Exponential(config) => {
+                if !(config.multiplier >= 1.0 && config.multiplier.is_finite())
+                    || config.interval.is_zero()
+                {
+                    errors.push(BuildError::InvalidRate);
+                }
+                (config.max_quantity, config.max)
+            }
+            RefillConfig::Quota(config) => {
+                if config.reset_interval.is_zero() {
+                    errors.push(BuildError::InvalidRate);
+                }
+                return errors;
+            }
+            RefillConfig::SlidingWindow(_) | RefillConfig::Custom(_) => return errors,
+        };
+        if quantity > max {
+            errors.push(BuildError::QuantityExceedsMax { quantity, max });
+        }
+        errors
+    }
+
+    /// Maximum number of tokens in the bucket
+    pub(crate) fn max(&self) -> u64 {
         match self {
-            RefillConfig::Rate(rate_config) => Inner::new(RateRefill::new(rate_config), initial),
+            RefillConfig::Rate(config) => config.max.into(),
+            RefillConfig::Burst(config) => config.max,
+            RefillConfig::SlidingWindow(config) => config.max,
+            RefillConfig::Exponential(config) => config.max,
+            RefillConfig::Quota(config) => config.max,
+            RefillConfig::Custom(refill) => refill.max(),
         }
     }
+
+    /// Create the inner bucket, with `initial` tokens if set
+    ///
+    /// The bucket starts as if `elapsed` had already passed, without adding tokens for that time.
+    pub(crate) fn into_inner_bucket(self, initial: Option<u64>, elapsed: Duration) -> Inner {
+        let inner = match self {
+            RefillConfig::Rate(rate_config) => Inner::new(
+                Arc::new(RateRefill::new(rate_config, elapsed)),
+                initial.unwrap_or(0),
+            ),
+            // After the burst, burst-depleted tokens accumulate at the sustained rate
+            RefillConfig::Burst(burst_config) => Inner::new(
+                Arc::new(RateRefill::new(burst_config.rate(), elapsed)),
+                initial.unwrap_or(burst_config.burst),
+            ),
+            RefillConfig::SlidingWindow(sliding_config) => Inner::new(
+                Arc::new(SlidingWindowRefill::new(sliding_config, elapsed)),
+                initial.unwrap_or(0),
+            ),
+            RefillConfig::Exponential(exponential_config) => Inner::new(
+                Arc::new(ExponentialRefill::new(exponential_config, elapsed)),
+                initial.unwrap_or(0),
+            ),
+            // Quotas start fresh
+            RefillConfig::Quota(quota_config) => {
+                let max = quota_config.max;
+                Inner::new(
+                    Arc::new(QuotaRefill::new(quota_config, elapsed)),
+                    initial.unwrap_or(max),
+                )
+            }
+            RefillConfig::Custom(refill) => Inner::new(refill, initial.unwrap_or(0)),
+        };
+        inner.with_offset(elapsed)
+    }
 }
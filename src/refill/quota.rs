@@ -0,0 +1,133 @@
+//! Quota reset at a fixed interval
+
+use super::Refill;
+use crate::TokenCount;
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Quota refill
+///
+/// Instead of adding tokens gradually, the bucket goes back to `max` tokens once per
+/// `reset_interval`, like API quotas resetting at the top of each hour.
+pub(crate) struct QuotaRefill {
+    max: u64,
+    reset_interval: Duration,
+    wall_clock_aligned: bool,
+
+    /// Time of the next reset, in nanoseconds since the start of the bucket
+    reset_at: AtomicU64,
+}
+
+impl QuotaRefill {
+    /// Create a new quota refill, with the next reset after `elapsed`
+    pub(crate) fn new(config: QuotaConfig, elapsed: Duration) -> Self {
+        let interval = config.reset_interval.as_nanos().max(1);
+        let first_reset = match config.wall_clock_aligned {
+            // Time until the next multiple of the interval since the Unix epoch
+            true => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_nanos();
+                interval - now % interval
+            }
+            false => interval,
+        };
+
+        Self {
+            max: config.max,
+            reset_interval: config.reset_interval,
+            wall_clock_aligned: config.wall_clock_aligned,
+            reset_at: AtomicU64::new(
+                (elapsed.as_nanos() + first_reset).min(u64::MAX as u128) as u64
+            ),
+        }
+    }
+}
+
+impl Refill for QuotaRefill {
+    fn refill(&self, elapsed: Duration, tokens: &AtomicU64) -> u64 {
+        let interval = self.reset_interval.as_nanos().max(1);
+
+        loop {
+            let reset_at = self.reset_at.load(Ordering::Acquire);
+
+            // Next reset is not due yet, return early
+            if elapsed.as_nanos() < reset_at as u128 {
+                return 0;
+            }
+
+            // Skip the resets that were missed, keeping the same alignment
+            let intervals = 1 + (elapsed.as_nanos() - reset_at as u128) / interval;
+            let next_reset_at = (reset_at as u128).saturating_add(intervals * interval);
+
+            // Only the thread moving `reset_at` resets the tokens
+            if self
+                .reset_at
+                .compare_exchange(
+                    reset_at,
+                    next_reset_at.min(u64::MAX as u128) as u64,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                )
+                .is_ok()
+            {
+                break;
+            }
+        }
+
+        super::add_tokens(tokens, self.max.into(), self.max)
+    }
+
+    /// Calculate the duration until the next reset, if the requested tokens aren't available
+    fn wait_for(
+        &self,
+        elapsed: Duration,
+        available: TokenCount,
+        requested: TokenCount,
+    ) -> Option<Duration> {
+        if requested <= available {
+            return Some(Duration::ZERO);
+        }
+        if requested > self.max {
+            return None;
+        }
+        self.next_refill_in(elapsed)
+    }
+
+    fn max(&self) -> u64 {
+        self.max
+    }
+
+    fn next_refill_in(&self, elapsed: Duration) -> Option<Duration> {
+        let reset_at = Duration::from_nanos(self.reset_at.load(Ordering::Acquire));
+        Some(reset_at.saturating_sub(elapsed))
+    }
+
+    fn reset(&self, elapsed: Duration) {
+        // Aligned resets keep happening at the same boundaries
+        if self.wall_clock_aligned {
+            return;
+        }
+        let reset_at = elapsed + self.reset_interval;
+        self.reset_at
+            .store(reset_at.as_nanos() as u64, Ordering::Release);
+    }
+}
+
+/// Quota refill configuration
+///
+/// The bucket starts with `max` tokens, and goes back to `max` tokens every `reset_interval`,
+/// however many tokens were used in the meantime.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QuotaConfig {
+    /// Number of tokens after each reset
+    pub max: u64,
+    /// Interval of time between resets
+    pub reset_interval: Duration,
+    /// Align resets to multiples of `reset_interval` since the Unix epoch, e.g. the top of each
+    /// hour for an interval of one hour, instead of counting from the creation of the bucket
+    pub wall_clock_aligned: bool,
+}
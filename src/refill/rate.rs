@@ -1,92 +1,267 @@
 //! Constant refill rate
 
 use super::Refill;
+use crate::{Error, TokenCount};
 use std::{
-    sync::atomic::{AtomicU64, Ordering},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        RwLock,
+    },
     time::Duration,
 };
 
 /// Constant refill rate
 pub(crate) struct RateRefill {
-    quantity: u64,
-    interval: Duration,
+    /// Tokens added per interval, which can be changed after the bucket is built
+    rate: RwLock<Rate>,
     max: u64,
 
+    /// Time of the next refill, in nanoseconds since the start of the bucket
     refill_at: AtomicU64,
 }
 
 impl RateRefill {
-    pub(crate) fn new(config: RateConfig) -> Self {
+    /// Create a new rate refill, with the next refill after `elapsed`
+    pub(crate) fn new(config: RateConfig, elapsed: Duration) -> Self {
+        let interval = config.interval.as_nanos() as u64;
+        let refill_at = match interval {
+            0 => 0,
+            _ => (elapsed.as_nanos() as u64 / interval + 1) * interval,
+        };
+
         Self {
-            quantity: config.quantity,
-            interval: config.interval,
-            max: config.max,
-            refill_at: AtomicU64::new(config.interval.as_millis() as u64),
+            rate: RwLock::new(Rate {
+                quantity: config.quantity.into(),
+                interval: config.interval,
+            }),
+            max: config.max.into(),
+            refill_at: AtomicU64::new(refill_at),
         }
     }
-}
 
-impl Refill for RateRefill {
-    fn refill(&self, elapsed: Duration, tokens: &AtomicU64) {
-        let mut intervals;
+    /// Number of tokens added per second with the current rate
+    pub(crate) fn effective_rate(&self) -> f64 {
+        let rate = self.rate();
+        rate.quantity as f64 / rate.interval.as_secs_f64()
+    }
+
+    fn rate(&self) -> Rate {
+        // The rate is always written in one piece, so a panic can't leave it inconsistent
+        *self
+            .rate
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Add the tokens due at `elapsed` with `rate`, as with [`Refill::refill`]
+    fn refill_at_rate(&self, rate: Rate, elapsed: Duration, tokens: &AtomicU64) -> u64 {
+        let interval = rate.interval.as_nanos().max(1);
 
-        loop {
-            let refill_at = Duration::from_millis(self.refill_at.load(Ordering::Relaxed));
+        let intervals = loop {
+            let refill_at = self.refill_at.load(Ordering::Acquire);
 
             // Next refill is not due yet, return early
-            if elapsed < refill_at {
-                return;
+            if elapsed.as_nanos() < refill_at as u128 {
+                return 0;
             }
 
             // Number of intervals
             // 1 for the time until `refill_at`, then 1 for every time intervals since then
-            intervals = (1 + (elapsed - refill_at).as_nanos() / self.interval.as_nanos()) as u64;
+            //
+            // This is computed in `u128`, as the number of intervals can exceed `u32::MAX` after a
+            // long pause, e.g. when the process wakes up from sleep.
+            let intervals = 1 + (elapsed.as_nanos() - refill_at as u128) / interval;
 
             // Update the `refill_at` time
-            let next_refill_at = refill_at + (self.interval * intervals as u32);
+            let next_refill_at = (refill_at as u128).saturating_add(intervals * interval);
             if self
                 .refill_at
                 .compare_exchange(
-                    refill_at.as_millis() as u64,
-                    next_refill_at.as_millis() as u64,
+                    refill_at,
+                    next_refill_at.min(u64::MAX as u128) as u64,
                     Ordering::AcqRel,
                     Ordering::Acquire,
                 )
                 .is_ok()
             {
-                break;
+                break intervals;
             }
+        };
 
-            let amount = intervals * self.quantity;
-            let available = tokens.load(Ordering::Acquire);
+        // Only the thread moving `refill_at` adds the tokens for these intervals, on the same
+        // path as the successful compare-and-swap. The amount is clamped to the maximum before
+        // converting back to `u64`, atomically with the update so that concurrent acquisitions
+        // or returned tokens can't push the bucket over the maximum.
+        //
+        // `refill_at` is advanced first, as it decides which thread adds the tokens. The update
+        // below waits out transient underflows from acquisitions instead of giving up right away,
+        // so the tokens of the claimed intervals are only lost once the bucket is full, or if the
+        // count stays above the underflow guard.
+        let amount = intervals.saturating_mul(rate.quantity as u128);
+        super::add_tokens(tokens, amount, self.max)
+    }
+}
 
-            if available + amount >= self.max {
-                tokens.fetch_add(self.max - available, Ordering::Release);
-            } else {
-                tokens.fetch_add(amount, Ordering::Release);
-            }
-        }
+/// Quantity and interval of a [`RateRefill`], updated together
+#[derive(Clone, Copy)]
+struct Rate {
+    quantity: u64,
+    interval: Duration,
+}
+
+impl Refill for RateRefill {
+    fn refill(&self, elapsed: Duration, tokens: &AtomicU64) -> u64 {
+        self.refill_at_rate(self.rate(), elapsed, tokens)
     }
 
     /// Calculate the duration until the requested number of tokens will be avilable
-    fn wait_for(&self, available: u64, requested: u64) -> Option<Duration> {
-        Some(if requested < available {
+    fn wait_for(
+        &self,
+        elapsed: Duration,
+        available: TokenCount,
+        requested: TokenCount,
+    ) -> Option<Duration> {
+        let (available, requested) = (u64::from(available), u64::from(requested));
+        let rate = self.rate();
+        Some(if requested <= available {
             Duration::ZERO
         } else {
-            let intervals = (requested - available) / self.quantity;
-            Duration::from_millis(self.refill_at.load(Ordering::Acquire))
-                + (self.interval * intervals as u32)
+            if rate.quantity == 0 {
+                return None;
+            }
+
+            // The next refill adds tokens, so only the extra intervals after it are counted
+            //
+            // As with `refill`, this is computed in `u128` nanoseconds, as the number of intervals
+            // can exceed `u32::MAX`, and saturates instead of overflowing.
+            let intervals = (requested - available).saturating_sub(1) / rate.quantity;
+            let refill_at = self.refill_at.load(Ordering::Acquire) as u128;
+            let nanos = refill_at
+                .saturating_sub(elapsed.as_nanos())
+                .saturating_add(rate.interval.as_nanos().saturating_mul(intervals as u128));
+            let secs = nanos / 1_000_000_000;
+            match u64::try_from(secs) {
+                Ok(secs) => Duration::new(secs, (nanos % 1_000_000_000) as u32),
+                Err(_) => Duration::MAX,
+            }
         })
     }
+
+    fn max(&self) -> u64 {
+        self.max
+    }
+
+    fn next_refill_in(&self, elapsed: Duration) -> Option<Duration> {
+        let refill_at = Duration::from_nanos(self.refill_at.load(Ordering::Acquire));
+        Some(refill_at.saturating_sub(elapsed))
+    }
+
+    fn reset(&self, elapsed: Duration) {
+        let refill_at = elapsed + self.rate().interval;
+        self.refill_at
+            .store(refill_at.as_nanos() as u64, Ordering::Release);
+    }
+
+    fn set_rate(
+        &self,
+        elapsed: Duration,
+        tokens: &AtomicU64,
+        quantity: TokenCount,
+        interval: Duration,
+    ) -> Result<u64, Error> {
+        let mut rate = self
+            .rate
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        // Tokens due with the previous rate are added before it changes, while other refills
+        // wait on the lock
+        let added = self.refill_at_rate(*rate, elapsed, tokens);
+        *rate = Rate {
+            quantity: quantity.into(),
+            interval,
+        };
+
+        // The new rate applies from the next interval, instead of the one in progress
+        let refill_at = elapsed + interval;
+        self.refill_at
+            .store(refill_at.as_nanos() as u64, Ordering::Release);
+        Ok(added)
+    }
+
+    fn effective_rate_per_second(&self) -> Option<f64> {
+        Some(self.effective_rate())
+    }
 }
 
 /// Rate refill configuration
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RateConfig {
     /// Tokens to add per interval
-    pub quantity: u64,
+    pub quantity: TokenCount,
     /// Interval of time per refill
+    ///
+    /// With the `serde` feature, this is (de)serialized as a number of milliseconds.
+    #[cfg_attr(feature = "serde", serde(with = "crate::config::millis"))]
     pub interval: Duration,
     /// Maximum number of tokens in the bucket
-    pub max: u64,
+    pub max: TokenCount,
+}
+
+impl RateConfig {
+    /// Add `rate` tokens every second, up to `max` tokens
+    pub fn per_second(rate: u64, max: u64) -> Self {
+        Self {
+            quantity: rate.into(),
+            interval: Duration::from_secs(1),
+            max: max.into(),
+        }
+    }
+
+    /// Add `rate` tokens every minute, up to `max` tokens
+    pub fn per_minute(rate: u64, max: u64) -> Self {
+        Self {
+            quantity: rate.into(),
+            interval: Duration::from_secs(60),
+            max: max.into(),
+        }
+    }
+
+    /// Add `rate` tokens every hour, up to `max` tokens
+    pub fn per_hour(rate: u64, max: u64) -> Self {
+        Self {
+            quantity: rate.into(),
+            interval: Duration::from_secs(3600),
+            max: max.into(),
+        }
+    }
+
+    /// Number of tokens added per second, e.g. `0.1` for 1 token every 10 seconds
+    ///
+    /// This is infinite if `interval` is zero.
+    pub fn effective_rate(&self) -> f64 {
+        self.quantity.0 as f64 / self.interval.as_secs_f64()
+    }
+}
+
+#[cfg(feature = "governor")]
+impl TryFrom<RateConfig> for governor::Quota {
+    type Error = crate::BuildError;
+
+    /// Convert into a [`governor::Quota`] replenishing one cell every `interval / quantity`, with
+    /// a burst size of `max`.
+    fn try_from(config: RateConfig) -> Result<Self, Self::Error> {
+        let quantity = u32::try_from(config.quantity.0).unwrap_or(u32::MAX);
+        let period = config
+            .interval
+            .checked_div(quantity)
+            .ok_or(crate::BuildError::InvalidRate)?;
+        let burst = std::num::NonZeroU32::new(u32::try_from(config.max.0).unwrap_or(u32::MAX))
+            .ok_or(crate::BuildError::InvalidRate)?;
+
+        Ok(governor::Quota::with_period(period)
+            .ok_or(crate::BuildError::InvalidRate)?
+            .allow_burst(burst))
+    }
 }
@@ -0,0 +1,84 @@
+//! Maximum number of tokens shared with the outside of the bucket
+
+use super::Refill;
+use crate::{Error, TokenCount};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// Wrapper capping a [`Refill`] at a maximum number of tokens that can change at any time
+///
+/// The wrapped refill computes how many tokens are due as if the bucket were empty, so that its
+/// own maximum doesn't stop tokens from accumulating when the shared maximum is higher.
+pub(crate) struct SharedMaxRefill {
+    refill: Arc<dyn Refill + Send + Sync>,
+    max: Arc<AtomicU64>,
+}
+
+impl SharedMaxRefill {
+    pub(crate) fn new(refill: Arc<dyn Refill + Send + Sync>, max: Arc<AtomicU64>) -> Self {
+        Self { refill, max }
+    }
+}
+
+impl Refill for SharedMaxRefill {
+    fn refill(&self, elapsed: Duration, tokens: &AtomicU64) -> u64 {
+        let due = self.refill.refill(elapsed, &AtomicU64::new(0));
+        if due == 0 {
+            return 0;
+        }
+
+        // Nothing is added while the bucket holds more than a lowered maximum
+        let max = self.max.load(Ordering::Acquire);
+        super::add_tokens(tokens, due.into(), max)
+    }
+
+    fn wait_for(
+        &self,
+        elapsed: Duration,
+        available: TokenCount,
+        requested: TokenCount,
+    ) -> Option<Duration> {
+        if requested > self.max() {
+            return None;
+        }
+        self.refill.wait_for(elapsed, available, requested)
+    }
+
+    fn max(&self) -> u64 {
+        self.max.load(Ordering::Acquire)
+    }
+
+    fn next_refill_in(&self, elapsed: Duration) -> Option<Duration> {
+        self.refill.next_refill_in(elapsed)
+    }
+
+    fn reset(&self, elapsed: Duration) {
+        self.refill.reset(elapsed);
+    }
+
+    fn set_rate(
+        &self,
+        elapsed: Duration,
+        tokens: &AtomicU64,
+        quantity: TokenCount,
+        interval: Duration,
+    ) -> Result<u64, Error> {
+        let due = self
+            .refill
+            .set_rate(elapsed, &AtomicU64::new(0), quantity, interval)?;
+        if due == 0 {
+            return Ok(0);
+        }
+        let max = self.max.load(Ordering::Acquire);
+        Ok(super::add_tokens(tokens, due.into(), max))
+    }
+
+    fn effective_rate_per_second(&self) -> Option<f64> {
+        self.refill.effective_rate_per_second()
+    }
+}
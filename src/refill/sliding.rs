@@ -0,0 +1,117 @@
+//! Sliding window refill
+
+use super::Refill;
+use crate::TokenCount;
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+/// Sliding window refill
+///
+/// Instead of adding all tokens at the start of each interval, tokens are added continuously, so
+/// that any window of time of length `window` adds at most `quantity` tokens. This avoids
+/// double bursts around the boundaries of fixed intervals.
+pub(crate) struct SlidingWindowRefill {
+    quantity: u64,
+    window: Duration,
+    max: u64,
+
+    /// Number of tokens added since the start, including tokens that leaked out of a full bucket
+    credited: AtomicU64,
+}
+
+impl SlidingWindowRefill {
+    /// Create a new sliding window refill, without any tokens due for `elapsed`
+    pub(crate) fn new(config: SlidingWindowConfig, elapsed: Duration) -> Self {
+        let refill = Self {
+            quantity: config.quantity,
+            window: config.window,
+            max: config.max,
+            credited: AtomicU64::new(0),
+        };
+        refill
+            .credited
+            .store(refill.due(elapsed), Ordering::Relaxed);
+        refill
+    }
+
+    /// Number of tokens that should have been added after `elapsed`
+    fn due(&self, elapsed: Duration) -> u64 {
+        let due = elapsed.as_nanos() * self.quantity as u128 / self.window.as_nanos().max(1);
+        due.min(u64::MAX as u128) as u64
+    }
+}
+
+impl Refill for SlidingWindowRefill {
+    fn refill(&self, elapsed: Duration, tokens: &AtomicU64) -> u64 {
+        let due = self.due(elapsed);
+
+        // Only one thread can move `credited` past a given value, and adds the difference
+        let credited = self.credited.fetch_max(due, Ordering::AcqRel);
+        if due <= credited {
+            return 0;
+        }
+
+        let amount = due - credited;
+        super::add_tokens(tokens, amount.into(), self.max)
+    }
+
+    /// Calculate the duration until the requested number of tokens will be available
+    fn wait_for(
+        &self,
+        elapsed: Duration,
+        available: TokenCount,
+        requested: TokenCount,
+    ) -> Option<Duration> {
+        let (available, requested) = (u64::from(available), u64::from(requested));
+        if requested <= available {
+            return Some(Duration::ZERO);
+        }
+        if requested > self.max || self.quantity == 0 {
+            return None;
+        }
+
+        // Time at which enough tokens will have been added
+        let credited = self.credited.load(Ordering::Acquire).max(self.due(elapsed));
+        let target = credited as u128 + (requested - available) as u128;
+        let target_nanos = target
+            .saturating_mul(self.window.as_nanos())
+            .div_ceil(self.quantity as u128);
+        let target = Duration::from_nanos(target_nanos.min(u64::MAX as u128) as u64);
+
+        Some(target.saturating_sub(elapsed))
+    }
+
+    fn max(&self) -> u64 {
+        self.max
+    }
+
+    /// Tokens are added continuously, so this is the time until the next whole token is due
+    fn next_refill_in(&self, elapsed: Duration) -> Option<Duration> {
+        if self.quantity == 0 {
+            return None;
+        }
+
+        let target = (self.due(elapsed) as u128 + 1)
+            .saturating_mul(self.window.as_nanos())
+            .div_ceil(self.quantity as u128);
+        let target = Duration::from_nanos(target.min(u64::MAX as u128) as u64);
+        Some(target.saturating_sub(elapsed))
+    }
+
+    fn reset(&self, elapsed: Duration) {
+        self.credited.store(self.due(elapsed), Ordering::Release);
+    }
+}
+
+/// Sliding window refill configuration
+#[derive(Clone, Debug)]
+pub struct SlidingWindowConfig {
+    /// Tokens to add per window
+    pub quantity: u64,
+    /// Length of the sliding window
+    pub window: Duration,
+    /// Maximum number of tokens in the bucket
+    pub max: u64,
+}
@@ -0,0 +1,58 @@
+//! Retries with exponential backoff
+
+use std::time::Duration;
+
+/// Retry policy for [`Bucket::try_acquire_with_retry`](crate::Bucket::try_acquire_with_retry)
+///
+/// After a failed attempt, the bucket sleeps for `initial_backoff`, then for
+/// `initial_backoff * backoff_multiplier` after the next one, and so on, until it tried
+/// `max_retries` more times.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use threshold_bucket::RetryConfig;
+///
+/// let config = RetryConfig::default();
+/// assert_eq!(config.backoff(0), Duration::from_millis(10));
+/// assert_eq!(config.backoff(2), Duration::from_millis(40));
+///
+/// assert_eq!(RetryConfig::immediate(5).backoff(2), Duration::ZERO);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RetryConfig {
+    /// Number of attempts after the first one
+    pub max_retries: u8,
+    /// Duration to sleep before the first retry
+    pub initial_backoff: Duration,
+    /// Factor applied to the backoff after each retry
+    pub backoff_multiplier: f64,
+}
+
+impl RetryConfig {
+    /// Retry up to `max_retries` times, without sleeping in between
+    pub fn immediate(max_retries: u8) -> Self {
+        Self {
+            max_retries,
+            initial_backoff: Duration::ZERO,
+            backoff_multiplier: 1.0,
+        }
+    }
+
+    /// Duration to sleep before retry number `retry`, starting at `0`
+    pub fn backoff(&self, retry: u8) -> Duration {
+        let secs = self.initial_backoff.as_secs_f64() * self.backoff_multiplier.powi(retry.into());
+        Duration::try_from_secs_f64(secs.max(0.0)).unwrap_or(Duration::MAX)
+    }
+}
+
+/// Retry 3 times, after 10ms, 20ms and 40ms
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(10),
+            backoff_multiplier: 2.0,
+        }
+    }
+}
@@ -0,0 +1,46 @@
+//! Bucket state persisted across process restarts
+
+/// Size of a checkpoint written by [`Bucket::checkpoint`](crate::Bucket::checkpoint)
+pub(crate) const CHECKPOINT_LEN: usize = 24;
+
+/// State of a [`Bucket`](crate::Bucket) at a point in time
+///
+/// A snapshot can be persisted and used to restore the bucket with
+/// [`Builder::restore_from_snapshot`](crate::Builder::restore_from_snapshot), so that a process restart doesn't grant a free
+/// burst of tokens.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BucketSnapshot {
+    /// Number of tokens available
+    pub available: u64,
+    /// Milliseconds elapsed since the start of the bucket
+    pub elapsed_millis: u64,
+}
+
+impl BucketSnapshot {
+    /// Encode the snapshot and the maximum number of tokens of the bucket as a checkpoint
+    ///
+    /// Checkpoints are the available tokens, the elapsed milliseconds and the maximum number of
+    /// tokens, each as a little-endian `u64`.
+    pub(crate) fn to_checkpoint(self, max: u64) -> [u8; CHECKPOINT_LEN] {
+        let mut bytes = [0; CHECKPOINT_LEN];
+        bytes[..8].copy_from_slice(&self.available.to_le_bytes());
+        bytes[8..16].copy_from_slice(&self.elapsed_millis.to_le_bytes());
+        bytes[16..].copy_from_slice(&max.to_le_bytes());
+        bytes
+    }
+
+    /// Decode a checkpoint into the snapshot and the maximum number of tokens of the bucket
+    pub(crate) fn from_checkpoint(bytes: [u8; CHECKPOINT_LEN]) -> (Self, u64) {
+        let field = |i: usize| {
+            let mut field = [0; 8];
+            field.copy_from_slice(&bytes[i * 8..(i + 1) * 8]);
+            u64::from_le_bytes(field)
+        };
+        let snapshot = Self {
+            available: field(0),
+            elapsed_millis: field(1),
+        };
+        (snapshot, field(2))
+    }
+}
@@ -0,0 +1,66 @@
+//! Lifetime counters for a bucket
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::Error;
+
+/// Lifetime statistics of a [`Bucket`](crate::Bucket)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BucketStats {
+    /// Name of the bucket, if set with [`Bucket::with_name`](crate::Bucket::with_name)
+    pub name: Option<String>,
+    /// Number of tokens acquired
+    pub acquired_tokens: u64,
+    /// Number of acquisitions that failed because there weren't enough tokens, or because of
+    /// contention
+    pub denied_requests: u64,
+    /// Number of refills that added tokens
+    pub refill_count: u64,
+    /// Number of acquisitions that failed because of contention
+    pub contention_errors: u64,
+}
+
+/// Atomic counters backing [`BucketStats`]
+///
+/// These counters are only used for observability, so relaxed ordering is enough.
+#[derive(Default)]
+pub(crate) struct Stats {
+    acquired_tokens: AtomicU64,
+    denied_requests: AtomicU64,
+    refill_count: AtomicU64,
+    contention_errors: AtomicU64,
+}
+
+impl Stats {
+    /// Record the result of an acquisition
+    pub(crate) fn record_acquire(&self, result: &Result<u64, Error>) {
+        match result {
+            Ok(num) => {
+                self.acquired_tokens.fetch_add(*num, Ordering::Relaxed);
+            }
+            Err(Error::NotEnoughTokens { .. }) => {
+                self.denied_requests.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(Error::HighContention) => {
+                self.denied_requests.fetch_add(1, Ordering::Relaxed);
+                self.contention_errors.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(_) => (),
+        }
+    }
+
+    /// Record a refill that added tokens
+    pub(crate) fn record_refill(&self) {
+        self.refill_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> BucketStats {
+        BucketStats {
+            name: None,
+            acquired_tokens: self.acquired_tokens.load(Ordering::Relaxed),
+            denied_requests: self.denied_requests.load(Ordering::Relaxed),
+            refill_count: self.refill_count.load(Ordering::Relaxed),
+            contention_errors: self.contention_errors.load(Ordering::Relaxed),
+        }
+    }
+}
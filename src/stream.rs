@@ -0,0 +1,91 @@
+//! # Streams of token acquisitions
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_core::Stream;
+use tokio::time::Sleep;
+
+use crate::{Bucket, Error, Permit};
+
+/// [`Stream`] acquiring a number of tokens in chunks, see [`Bucket::acquire_n_as_stream`]
+///
+/// This uses [`tokio::time`], and must be polled from within a Tokio runtime.
+pub struct AcquireStream<F> {
+    bucket: Bucket,
+    permit_fn: F,
+    remaining: u64,
+    chunk_size: u64,
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl<F> AcquireStream<F> {
+    pub(crate) fn new(bucket: Bucket, permit_fn: F, num: u64, chunk_size: u64) -> Self {
+        Self {
+            bucket,
+            permit_fn,
+            remaining: num,
+            chunk_size: chunk_size.max(1),
+            sleep: None,
+        }
+    }
+}
+
+// `permit_fn` is never pinned, so the stream can be moved freely
+impl<F> Unpin for AcquireStream<F> {}
+
+impl<F> Stream for AcquireStream<F>
+where
+    F: Fn() -> Result<Permit, Error>,
+{
+    type Item = Result<u64, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(sleep) = self.sleep.as_mut() {
+                if sleep.as_mut().poll(cx).is_pending() {
+                    return Poll::Pending;
+                }
+                self.sleep = None;
+            }
+
+            if self.remaining == 0 {
+                return Poll::Ready(None);
+            }
+
+            let chunk = self.chunk_size.min(self.remaining);
+            let result =
+                (self.permit_fn)().and_then(|permit| self.bucket.try_acquire(permit, chunk));
+            match result {
+                Ok(acquired) => {
+                    self.remaining -= acquired;
+                    return Poll::Ready(Some(Ok(acquired)));
+                }
+                Err(Error::NotEnoughTokens {
+                    wait_for: Some(wait_for),
+                    ..
+                }) if wait_for > Duration::ZERO => {
+                    self.sleep = Some(Box::pin(tokio::time::sleep(wait_for)));
+                }
+                // The tokens should be available now, but another caller might have used them
+                // concurrently. Yield to retry later.
+                Err(Error::NotEnoughTokens {
+                    wait_for: Some(_), ..
+                })
+                | Err(Error::HighContention) => {
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+                // The remaining tokens can't be acquired, end the stream after the error
+                Err(err) => {
+                    self.remaining = 0;
+                    return Poll::Ready(Some(Err(err)));
+                }
+            }
+        }
+    }
+}
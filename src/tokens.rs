@@ -0,0 +1,104 @@
+//! Token amounts
+
+use std::{
+    cmp::Ordering,
+    fmt,
+    ops::{Add, AddAssign, Sub, SubAssign},
+};
+
+/// Number of tokens
+///
+/// This keeps token amounts apart from other integers, such as durations or priorities, in
+/// configurations and the [`Refill`](crate::refill::Refill) trait. Arithmetic saturates instead
+/// of overflowing, and plain integers convert with [`From`]:
+///
+/// ```rust
+/// use threshold_bucket::TokenCount;
+///
+/// let mut tokens = TokenCount::from(10);
+/// tokens -= 15.into();
+/// assert_eq!(tokens, 0);
+///
+/// tokens += TokenCount(3);
+/// assert_eq!(u64::from(tokens), 3);
+/// ```
+///
+/// Token counts are 64 bits wide, but a bucket holds at most [`TokenCount::MAX`], about
+/// 9.2 * 10^18 tokens, which [`Builder::validate`](crate::Builder::validate) checks for the
+/// maximum, initial and credit limit. For example, a bucket counting bytes on a 100 Gbps link
+/// holds about 1.08 * 10^15 tokens for 24 hours of traffic, far below that limit. Wider counts,
+/// such as `u128` or a generic token type, are out of scope: buckets are not generic over the
+/// token type.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct TokenCount(pub u64);
+
+impl TokenCount {
+    /// No tokens
+    pub const ZERO: Self = Self(0);
+
+    /// Largest number of tokens a [`Bucket`](crate::Bucket) can hold
+    ///
+    /// Larger counts are reserved for acquisitions about to be reverted, and are rejected when
+    /// building a bucket, with [`BuildError::TooManyTokens`](crate::BuildError::TooManyTokens).
+    pub const MAX: Self = Self(u64::MAX >> 1);
+}
+
+impl From<u64> for TokenCount {
+    fn from(tokens: u64) -> Self {
+        Self(tokens)
+    }
+}
+
+impl From<TokenCount> for u64 {
+    fn from(tokens: TokenCount) -> Self {
+        tokens.0
+    }
+}
+
+impl Add for TokenCount {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl AddAssign for TokenCount {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for TokenCount {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl SubAssign for TokenCount {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl PartialEq<u64> for TokenCount {
+    fn eq(&self, other: &u64) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialOrd<u64> for TokenCount {
+    fn partial_cmp(&self, other: &u64) -> Option<Ordering> {
+        self.0.partial_cmp(other)
+    }
+}
+
+impl fmt::Display for TokenCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
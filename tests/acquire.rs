@@ -0,0 +1,363 @@
+//! Acquiring tokens without handling permits explicitly.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use threshold_bucket::{
+    clock::ManualClock,
+    permit::{InnerPermit, Permit, PriorityConfig, ThresholdConfig},
+    refill::RateConfig,
+    Bucket, Error,
+};
+
+mod common;
+
+fn bucket(initial: u64) -> Bucket {
+    common::builder(initial, 10)
+        .threshold(ThresholdConfig {
+            threshold: 5.into(),
+        })
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn quick_acquire_above_threshold() {
+    let bucket = bucket(6);
+
+    assert_eq!(bucket.quick_acquire(2).unwrap(), 2);
+    assert_eq!(bucket.available(), 4);
+}
+
+#[test]
+fn try_permit_below_threshold() {
+    assert!(matches!(
+        bucket(4).try_permit(),
+        Err(Error::NotEnoughTokens {
+            available: 4,
+            wait_for: Some(_),
+        })
+    ));
+    assert!(bucket(5).try_permit().is_ok());
+}
+
+#[test]
+fn try_permit_refills_first() {
+    let clock = ManualClock::new();
+    let bucket = Bucket::builder()
+        .rate(RateConfig {
+            quantity: 1.into(),
+            interval: Duration::from_secs(1),
+            max: 10.into(),
+        })
+        .initial(3)
+        .threshold(ThresholdConfig {
+            threshold: 5.into(),
+        })
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        bucket.try_permit().unwrap_err(),
+        Error::NotEnoughTokens {
+            available: 3,
+            wait_for: Some(Duration::from_secs(2)),
+        }
+    );
+
+    // The refill is due, but no acquisition triggered it yet
+    clock.advance(Duration::from_millis(1500));
+    assert_eq!(
+        bucket.try_permit().unwrap_err(),
+        Error::NotEnoughTokens {
+            available: 4,
+            wait_for: Some(Duration::from_millis(500)),
+        }
+    );
+    clock.advance(Duration::from_millis(500));
+    assert!(bucket.try_permit().is_ok());
+}
+
+#[test]
+fn quick_acquire_below_threshold() {
+    let bucket = bucket(4);
+
+    assert!(matches!(
+        bucket.quick_acquire(1),
+        Err(Error::NotEnoughTokens { available: 4, .. })
+    ));
+    assert!(matches!(
+        bucket.quick_acquire_one(),
+        Err(Error::NotEnoughTokens { available: 4, .. })
+    ));
+    assert_eq!(bucket.available(), 4);
+}
+
+#[test]
+fn try_acquire_partial_rechecks_threshold() {
+    let bucket = bucket(8);
+    let permit = bucket.try_permit().unwrap();
+    assert_eq!(bucket.try_acquire_partial(permit, 3).unwrap(), 3);
+
+    // Other acquisitions take the bucket below the threshold while the permit is held
+    let permit = bucket.try_permit().unwrap();
+    bucket.quick_acquire(1).unwrap();
+    assert!(matches!(
+        bucket.try_acquire_partial(permit, 3),
+        Err(Error::NotEnoughTokens { available: 4, .. })
+    ));
+    assert_eq!(bucket.available(), 4);
+}
+
+#[test]
+fn try_acquire_range_rechecks_threshold() {
+    let bucket = bucket(8);
+    let permit = bucket.try_permit().unwrap();
+    assert_eq!(bucket.try_acquire_range(permit, 1..=2).unwrap(), 2);
+
+    let permit = bucket.try_permit().unwrap();
+    bucket.quick_acquire(2).unwrap();
+    assert!(matches!(
+        bucket.try_acquire_range(permit, 1..=2),
+        Err(Error::NotEnoughTokens { available: 4, .. })
+    ));
+    assert_eq!(bucket.available(), 4);
+}
+
+#[test]
+fn overdraft_rechecks_threshold() {
+    let bucket = bucket(8);
+    let permit = bucket.try_permit().unwrap();
+    bucket.quick_acquire(4).unwrap();
+
+    // Not borrowing either, even though the overdraft would cover the missing tokens
+    assert!(matches!(
+        bucket.overdraft(permit, 6, 10),
+        Err(Error::NotEnoughTokens { available: 4, .. })
+    ));
+    assert_eq!(bucket.available(), 4);
+
+    bucket.fill().unwrap();
+    let permit = bucket.try_permit().unwrap();
+    bucket.overdraft(permit, 12, 10).unwrap();
+    assert_eq!(bucket.available(), 0);
+}
+
+#[test]
+fn batch_permit_rechecks_threshold() {
+    let bucket = bucket(10);
+    let mut batch = bucket.get_batch_permit(5).unwrap();
+
+    assert_eq!(batch.try_acquire(3).unwrap(), 3);
+    assert_eq!(batch.try_acquire(3).unwrap(), 3);
+    assert!(matches!(
+        batch.try_acquire(3),
+        Err(Error::NotEnoughTokens { available: 4, .. })
+    ));
+    assert_eq!(bucket.available(), 4);
+}
+
+#[test]
+fn priority_bypasses_lower_thresholds() {
+    let bucket = Bucket::builder()
+        .rate(RateConfig {
+            quantity: 1.into(),
+            interval: Duration::from_secs(3600),
+            max: 100.into(),
+        })
+        .initial(30)
+        .priority(PriorityConfig {
+            tiers: vec![(0, 50), (5, 20), (10, 0)],
+        })
+        .build()
+        .unwrap();
+
+    assert!(matches!(
+        bucket.try_permit_with_priority(0),
+        Err(Error::NotEnoughTokens { .. })
+    ));
+    assert!(bucket.try_permit().is_err());
+
+    let permit = bucket.try_permit_with_priority(7).unwrap();
+    assert_eq!(permit.priority(), 7);
+    assert_eq!(bucket.try_acquire(permit, 15).unwrap(), 15);
+
+    // 15 tokens left, below the threshold for priority 5
+    assert!(bucket.try_permit_with_priority(5).is_err());
+    assert!(bucket.try_permit_with_priority(10).is_ok());
+}
+
+#[test]
+fn weighted_threshold_tiers() {
+    let weighted = |initial| {
+        Bucket::builder()
+            .rate(RateConfig {
+                quantity: 1.into(),
+                interval: Duration::from_secs(3600),
+                max: 100.into(),
+            })
+            .initial(initial)
+            .weighted_threshold(vec![(4, 80), (1, 10), (2, 30)])
+            .build()
+            .unwrap()
+    };
+
+    for (weight, threshold) in [(1, 10), (2, 30), (4, 80)] {
+        assert!(weighted(threshold).try_permit_weighted(weight).is_ok());
+        assert!(matches!(
+            weighted(threshold - 1).try_permit_weighted(weight),
+            Err(Error::NotEnoughTokens { .. })
+        ));
+    }
+
+    // Weights between tiers use the lighter tier, and weights above all tiers the heaviest
+    assert!(weighted(30).try_permit_weighted(3).is_ok());
+    assert!(weighted(79).try_permit_weighted(8).is_err());
+}
+
+#[test]
+fn expired_permit() {
+    let bucket = Bucket::builder()
+        .rate(RateConfig {
+            quantity: 1.into(),
+            interval: Duration::from_secs(3600),
+            max: 10.into(),
+        })
+        .initial(10)
+        .permit_ttl(Duration::from_millis(20))
+        .build()
+        .unwrap();
+
+    let permit = bucket.try_permit().unwrap();
+    assert!(permit.is_valid());
+    bucket.try_acquire(permit, 1).unwrap();
+
+    let permit = bucket.try_permit().unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+    assert!(!permit.is_valid());
+    assert!(matches!(
+        bucket.try_acquire(permit, 1),
+        Err(Error::ExpiredPermit)
+    ));
+    assert_eq!(bucket.available(), 9);
+}
+
+#[test]
+fn display_fill_level() {
+    let bucket = bucket(8);
+    assert_eq!(bucket.to_string(), "Bucket(8/10 tokens, threshold: 5)");
+
+    bucket.quick_acquire(3).unwrap();
+    assert_eq!(bucket.to_string(), "Bucket(5/10 tokens, threshold: 5)");
+
+    let bucket = Bucket::builder()
+        .refill_rate_per_hour(1, 200)
+        .initial(42)
+        .build()
+        .unwrap();
+    assert_eq!(bucket.threshold(), None);
+    assert_eq!(bucket.to_string(), "Bucket(42/200 tokens)");
+}
+
+#[test]
+fn capacity_and_used() {
+    let bucket = bucket(8);
+
+    assert_eq!(bucket.capacity(), 10);
+    assert_eq!(bucket.max(), bucket.capacity());
+    assert_eq!(bucket.used(), 2);
+
+    bucket.quick_acquire(3).unwrap();
+    assert_eq!(bucket.used(), 5);
+    assert!(format!("{bucket:?}").contains("capacity: 10"));
+}
+
+#[test]
+fn max_tokens_per_request() {
+    let bucket = Bucket::builder()
+        .rate(RateConfig {
+            quantity: 1.into(),
+            interval: Duration::from_secs(3600),
+            max: 200.into(),
+        })
+        .initial(200)
+        .build()
+        .unwrap();
+
+    let max = bucket.max_tokens_per_request();
+    assert_eq!(max, 200);
+    assert!(matches!(
+        bucket.quick_acquire(max + 1),
+        Err(Error::ExceedMaxTokens)
+    ));
+    assert_eq!(bucket.quick_acquire(max).unwrap(), 200);
+
+    // Requests can also borrow from the credit line
+    let bucket = Bucket::builder()
+        .refill_rate_per_hour(1, 200)
+        .credit_limit(50)
+        .build()
+        .unwrap();
+    assert_eq!(bucket.max_tokens_per_request(), 250);
+}
+
+#[test]
+fn cancel_permit() {
+    /// Permit recording the number of tokens it was notified with
+    struct Recording(Arc<Mutex<Vec<u64>>>);
+
+    impl InnerPermit for Recording {
+        fn notify(&self, num: u64) {
+            self.0.lock().unwrap().push(num);
+        }
+    }
+
+    let notified = Arc::new(Mutex::new(Vec::new()));
+    let permit = Permit::new(Recording(notified.clone()));
+    permit.cancel();
+    drop(Permit::new(Recording(notified.clone())));
+    assert_eq!(*notified.lock().unwrap(), vec![0]);
+
+    // Built-in permits ignore cancellation
+    let bucket = bucket(8);
+    bucket.try_permit().unwrap().cancel();
+    assert_eq!(bucket.available(), 8);
+}
+
+#[test]
+fn try_acquire_with_fallback() {
+    let bucket = bucket(8);
+    let calls = std::cell::Cell::new(0);
+
+    // The fallback isn't called on success
+    let result = bucket.try_acquire_with_fallback(bucket.try_permit().unwrap(), 2, |err| {
+        calls.set(calls.get() + 1);
+        Err(err)
+    });
+    assert_eq!(result, Ok(2));
+    assert_eq!(calls.get(), 0);
+
+    // The fallback gets the error, and can recover from it
+    let result = bucket.try_acquire_with_fallback(bucket.try_permit().unwrap(), 11, |err| {
+        assert_eq!(err, Error::ExceedMaxTokens);
+        Ok(0)
+    });
+    assert_eq!(result, Ok(0));
+
+    // Or replace it
+    let result = bucket.try_acquire_with_fallback(bucket.try_permit().unwrap(), 7, |err| {
+        assert!(matches!(err, Error::NotEnoughTokens { available: 6, .. }));
+        Err(Error::HighContention)
+    });
+    assert_eq!(result, Err(Error::HighContention));
+    assert_eq!(bucket.available(), 6);
+
+    // The fallback is only called once, so it can consume what it captured
+    let reserved = vec![3, 4];
+    let result = bucket.try_acquire_with_fallback(bucket.try_permit().unwrap(), 7, move |_| {
+        Ok(reserved.into_iter().sum())
+    });
+    assert_eq!(result, Ok(7));
+}
@@ -0,0 +1,67 @@
+//! Administrative actions on the number of available tokens, bypassing the permitter.
+
+use threshold_bucket::Bucket;
+
+mod common;
+
+fn bucket(initial: u64, max: u64) -> Bucket {
+    common::builder(initial, max).build().unwrap()
+}
+
+#[test]
+fn add_tokens_stays_within_max() {
+    let bucket = bucket(2, 10);
+
+    assert_eq!(bucket.add_tokens(5).unwrap(), 7);
+    assert_eq!(bucket.add_tokens(5).unwrap(), 10);
+    assert_eq!(bucket.add_tokens(u64::MAX).unwrap(), 10);
+    assert_eq!(bucket.available(), 10);
+}
+
+#[test]
+fn drain_and_fill() {
+    let bucket = bucket(4, 10);
+
+    assert_eq!(bucket.drain().unwrap(), 4);
+    assert_eq!(bucket.available(), 0);
+    assert_eq!(bucket.drain().unwrap(), 0);
+
+    assert_eq!(bucket.fill().unwrap(), 10);
+    assert_eq!(bucket.available(), 10);
+    assert_eq!(bucket.fill().unwrap(), 0);
+}
+
+#[test]
+fn reset_to_initial() {
+    let bucket = bucket(50, 200);
+
+    let permit = bucket.try_permit().unwrap();
+    bucket.try_acquire(permit, 30).unwrap();
+    assert_eq!(bucket.available(), 20);
+
+    bucket.reset().unwrap();
+    assert_eq!(bucket.available(), 50);
+
+    bucket.fill().unwrap();
+    bucket.reset().unwrap();
+    assert_eq!(bucket.available(), 50);
+}
+
+#[test]
+fn utilization() {
+    let bucket = bucket(10, 10);
+    assert_eq!(bucket.max(), 10);
+    assert_eq!(bucket.utilization(), 1.0);
+    assert!(bucket.is_full());
+    assert!(!bucket.is_empty());
+
+    let permit = bucket.try_permit().unwrap();
+    bucket.try_acquire(permit, 5).unwrap();
+    assert_eq!(bucket.utilization(), 0.5);
+    assert!(!bucket.is_full());
+    assert!(!bucket.is_empty());
+
+    bucket.drain().unwrap();
+    assert_eq!(bucket.utilization(), 0.0);
+    assert!(bucket.is_empty());
+}
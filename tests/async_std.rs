@@ -0,0 +1,76 @@
+//! Waiting for tokens and background refills on an async-std runtime.
+#![cfg(feature = "async-std")]
+
+use async_std::{future::timeout, task};
+use futures::future::join_all;
+use std::time::{Duration, Instant};
+use threshold_bucket::{refill::SlidingWindowConfig, Bucket, Error};
+
+fn bucket(initial: u64) -> Bucket {
+    Bucket::builder()
+        .sliding_window(SlidingWindowConfig {
+            quantity: 10,
+            window: Duration::from_millis(100),
+            max: 10,
+        })
+        .initial(initial)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn join_all_acquisitions() {
+    task::block_on(async {
+        let bucket = bucket(4);
+        let start = Instant::now();
+
+        let futures: Vec<_> = (0..3)
+            .map(|_| bucket.try_acquire_or_wait_async(bucket.try_permit().unwrap(), 2))
+            .collect();
+        let results = join_all(futures).await;
+
+        assert_eq!(results, vec![Ok(2), Ok(2), Ok(2)]);
+        // Only two acquisitions were possible right away
+        assert!(start.elapsed() >= Duration::from_millis(10));
+    });
+}
+
+#[test]
+fn outlives_the_bucket() {
+    task::block_on(async {
+        let bucket = bucket(4);
+        let future = bucket.try_acquire_or_wait_async(bucket.try_permit().unwrap(), 20);
+        drop(bucket);
+
+        assert!(matches!(
+            task::spawn(future).await,
+            Err(Error::ExceedMaxTokens)
+        ));
+    });
+}
+
+#[test]
+fn refills_without_acquiring() {
+    task::block_on(async {
+        let bucket = bucket(0);
+        let _task = bucket.start_background_refill();
+
+        task::sleep(Duration::from_millis(200)).await;
+
+        assert!(bucket.available() > 0);
+    });
+}
+
+#[test]
+fn stops_when_bucket_is_dropped() {
+    task::block_on(async {
+        let bucket = bucket(0);
+        let task = bucket.start_background_refill();
+
+        drop(bucket);
+
+        timeout(Duration::from_secs(1), task)
+            .await
+            .expect("background refill still running");
+    });
+}
@@ -0,0 +1,39 @@
+//! Background refills on a tokio runtime.
+#![cfg(feature = "tokio")]
+
+use std::time::Duration;
+use threshold_bucket::{refill::SlidingWindowConfig, Bucket};
+
+fn bucket() -> Bucket {
+    Bucket::builder()
+        .sliding_window(SlidingWindowConfig {
+            quantity: 10,
+            window: Duration::from_millis(100),
+            max: 100,
+        })
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn refills_without_acquiring() {
+    let bucket = bucket();
+    let _task = bucket.start_background_refill(&tokio::runtime::Handle::current());
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    assert!(bucket.available() > 0);
+}
+
+#[tokio::test]
+async fn stops_when_bucket_is_dropped() {
+    let bucket = bucket();
+    let task = bucket.start_background_refill(&tokio::runtime::Handle::current());
+
+    drop(bucket);
+
+    tokio::time::timeout(Duration::from_secs(1), task)
+        .await
+        .expect("background refill still running")
+        .unwrap();
+}
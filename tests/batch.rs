@@ -0,0 +1,60 @@
+//! Batched acquisitions from a single bucket.
+
+use threshold_bucket::Error;
+
+mod common;
+use common::bucket;
+
+#[test]
+fn all_succeed() {
+    let bucket = bucket(10);
+
+    let results = bucket.try_acquire_many([
+        (bucket.try_permit().unwrap(), 2),
+        (bucket.try_permit().unwrap(), 3),
+        (bucket.try_permit().unwrap(), 5),
+    ]);
+
+    assert_eq!(results, vec![Ok(2), Ok(3), Ok(5)]);
+    assert_eq!(bucket.available(), 0);
+    assert_eq!(bucket.stats().acquired_tokens, 10);
+}
+
+#[test]
+fn mixed_permits() {
+    let other = bucket(10);
+    let bucket = bucket(10);
+
+    let results = bucket.try_acquire_many([
+        (bucket.try_permit().unwrap(), 2),
+        (other.try_permit().unwrap(), 3),
+        (bucket.try_permit().unwrap(), 4),
+    ]);
+
+    assert_eq!(results, vec![Ok(2), Err(Error::InvalidPermit), Ok(4)]);
+    assert_eq!(bucket.available(), 4);
+    assert_eq!(other.available(), 10);
+}
+
+#[test]
+fn partial_success() {
+    let bucket = bucket(6);
+
+    let results = bucket.try_acquire_many([
+        (bucket.try_permit().unwrap(), 4),
+        (bucket.try_permit().unwrap(), 3),
+        (bucket.try_permit().unwrap(), 11),
+        (bucket.try_permit().unwrap(), 2),
+    ]);
+
+    assert!(matches!(
+        results[..],
+        [
+            Ok(4),
+            Err(Error::NotEnoughTokens { available: 2, .. }),
+            Err(Error::ExceedMaxTokens),
+            Ok(2),
+        ]
+    ));
+    assert_eq!(bucket.available(), 0);
+}
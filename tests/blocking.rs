@@ -0,0 +1,99 @@
+//! Blocking acquisitions for synchronous callers.
+#![cfg(feature = "blocking")]
+
+use std::time::{Duration, Instant};
+use threshold_bucket::{
+    clock::{Clock, ManualClock},
+    refill::{RateConfig, SlidingWindowConfig},
+    Bucket, Error, RetryConfig,
+};
+
+fn bucket() -> Bucket {
+    Bucket::builder()
+        .sliding_window(SlidingWindowConfig {
+            quantity: 10,
+            window: Duration::from_millis(100),
+            max: 10,
+        })
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn waits_until_tokens_are_available() {
+    let bucket = bucket();
+    let start = Instant::now();
+
+    let permit = bucket.try_permit().unwrap();
+    assert_eq!(bucket.acquire_blocking(permit, 5, None).unwrap(), 5);
+    assert!(start.elapsed() >= Duration::from_millis(40));
+}
+
+#[test]
+fn returns_after_timeout() {
+    let bucket = bucket();
+    let start = Instant::now();
+
+    let permit = bucket.try_permit().unwrap();
+    assert!(matches!(
+        bucket.acquire_blocking(permit, 10, Some(Duration::from_millis(20))),
+        Err(Error::NotEnoughTokens { .. })
+    ));
+    assert!(start.elapsed() >= Duration::from_millis(20));
+    assert!(start.elapsed() < Duration::from_millis(100));
+}
+
+fn rate_bucket(clock: &ManualClock) -> Bucket {
+    Bucket::builder()
+        .rate(RateConfig {
+            quantity: 1.into(),
+            interval: Duration::from_millis(100),
+            max: 10.into(),
+        })
+        .initial(0)
+        .with_clock(clock.clone())
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn retries_with_backoff() {
+    let clock = ManualClock::new();
+    let bucket = rate_bucket(&clock);
+
+    // Backoffs advance the manual clock, and the token is refilled during the third one
+    let permit = bucket.try_permit().unwrap();
+    let config = RetryConfig {
+        max_retries: 3,
+        initial_backoff: Duration::from_millis(20),
+        backoff_multiplier: 2.0,
+    };
+    assert_eq!(bucket.try_acquire_with_retry(permit, 1, config).unwrap(), 1);
+    // Slept for 20ms, 40ms, then 80ms
+    assert_eq!(clock.now(), Duration::from_millis(140));
+}
+
+#[test]
+fn returns_last_error_after_retries() {
+    let clock = ManualClock::new();
+    let bucket = rate_bucket(&clock);
+
+    let permit = bucket.try_permit().unwrap();
+    let config = RetryConfig {
+        max_retries: 3,
+        initial_backoff: Duration::from_millis(5),
+        backoff_multiplier: 2.0,
+    };
+    assert!(matches!(
+        bucket.try_acquire_with_retry(permit, 1, config),
+        Err(Error::NotEnoughTokens { .. })
+    ));
+    // Slept for 5ms, 10ms, then 20ms
+    assert_eq!(clock.now(), Duration::from_millis(35));
+
+    let permit = bucket.try_permit().unwrap();
+    assert_eq!(
+        bucket.try_acquire_with_retry(permit, 11, RetryConfig::immediate(3)),
+        Err(Error::ExceedMaxTokens)
+    );
+}
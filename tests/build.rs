@@ -0,0 +1,479 @@
+//! Validation of the bucket configuration when building.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use threshold_bucket::{
+    clock::ManualClock, map::BucketMap, permit::ThresholdConfig, refill::RateConfig, Bucket,
+    BuildError, TokenCount,
+};
+
+mod common;
+use common::builder;
+
+#[test]
+fn threshold_percent_of_max() {
+    let bucket = builder(100, 200).threshold_percent(50).build().unwrap();
+    assert!(bucket.try_permit().is_ok());
+
+    let permit = bucket.try_permit().unwrap();
+    bucket.try_acquire(permit, 1).unwrap();
+    assert!(bucket.try_permit().is_err());
+}
+
+#[test]
+fn invalid_threshold_percent() {
+    assert!(matches!(
+        builder(0, 200).threshold_percent(100).build(),
+        Err(BuildError::InvalidThreshold)
+    ));
+    assert!(matches!(
+        builder(0, 200).threshold_percent(101).build(),
+        Err(BuildError::InvalidThreshold)
+    ));
+}
+
+#[test]
+fn threshold_below_max() {
+    assert!(builder(0, 200)
+        .threshold(ThresholdConfig {
+            threshold: 199.into()
+        })
+        .build()
+        .is_ok());
+
+    for threshold in [200, 201] {
+        assert!(matches!(
+            builder(0, 200)
+                .threshold(ThresholdConfig {
+                    threshold: threshold.into(),
+                })
+                .build(),
+            Err(BuildError::ThresholdExceedsMax { max: 200, .. })
+        ));
+    }
+}
+
+#[test]
+fn quantity_exceeds_max() {
+    let result = Bucket::builder()
+        .rate(RateConfig {
+            quantity: 11.into(),
+            interval: Duration::from_secs(1),
+            max: 10.into(),
+        })
+        .build();
+    assert!(matches!(
+        result,
+        Err(BuildError::QuantityExceedsMax {
+            quantity: 11,
+            max: 10
+        })
+    ));
+}
+
+#[test]
+fn initial_full_and_empty() {
+    let bucket = builder(0, 100).initial_full().build().unwrap();
+    assert_eq!(bucket.available(), 100);
+
+    let bucket = builder(50, 100).initial_empty().build().unwrap();
+    assert_eq!(bucket.available(), 0);
+}
+
+#[test]
+fn rate_shorthands() {
+    assert_eq!(
+        RateConfig::per_second(5, 20),
+        RateConfig {
+            quantity: 5.into(),
+            interval: Duration::from_secs(1),
+            max: 20.into(),
+        }
+    );
+    assert_eq!(
+        RateConfig::per_minute(5, 20).interval,
+        Duration::from_secs(60)
+    );
+    assert_eq!(
+        RateConfig::per_hour(5, 20).interval,
+        Duration::from_secs(3600)
+    );
+
+    let shorthand = Bucket::builder()
+        .refill_rate_per_second(5, 20)
+        .build()
+        .unwrap();
+    let manual = Bucket::builder()
+        .rate(RateConfig {
+            quantity: 5.into(),
+            interval: Duration::from_secs(1),
+            max: 20.into(),
+        })
+        .build()
+        .unwrap();
+    assert_eq!(shorthand.available(), manual.available());
+    assert_eq!(
+        shorthand.wait_for(20).map(|wait_for| wait_for.as_secs()),
+        manual.wait_for(20).map(|wait_for| wait_for.as_secs())
+    );
+}
+
+#[test]
+fn effective_rate() {
+    assert_eq!(RateConfig::per_second(100, 10000).effective_rate(), 100.0);
+    let config = RateConfig {
+        quantity: 1.into(),
+        interval: Duration::from_secs(10),
+        max: 10.into(),
+    };
+    assert_eq!(config.effective_rate(), 0.1);
+
+    let bucket = Bucket::builder().rate(config).build().unwrap();
+    assert_eq!(bucket.effective_rate(), Some(0.1));
+    bucket.set_rate(10, Duration::from_secs(4)).unwrap();
+    assert_eq!(bucket.effective_rate(), Some(2.5));
+
+    let bucket = Bucket::builder()
+        .refill_rate(10, Duration::from_secs(60))
+        .max(20)
+        .shared_max(Arc::new(AtomicU64::new(20)))
+        .build()
+        .unwrap();
+    assert!((bucket.effective_rate().unwrap() - 0.1667).abs() < 1e-4);
+}
+
+#[test]
+fn clone_with_new_threshold() {
+    let a = builder(60, 200)
+        .threshold(ThresholdConfig {
+            threshold: 100.into(),
+        })
+        .build()
+        .unwrap();
+    let b = a.clone_with_new_threshold(50).unwrap();
+
+    assert!(a.try_permit().is_err());
+    let permit = b.try_permit().unwrap();
+    b.try_acquire(permit, 20).unwrap();
+    // Both buckets share the same tokens
+    assert_eq!(a.available(), 40);
+
+    assert!(matches!(
+        a.clone_with_new_threshold(200),
+        Err(BuildError::ThresholdExceedsMax {
+            threshold: 200,
+            max: 200
+        })
+    ));
+}
+
+#[test]
+fn set_threshold() {
+    let bucket = builder(60, 200)
+        .threshold(ThresholdConfig {
+            threshold: 100.into(),
+        })
+        .build()
+        .unwrap();
+    assert!(bucket.try_permit().is_err());
+
+    bucket.set_threshold(50).unwrap();
+    let permit = bucket.try_permit().unwrap();
+    assert_eq!(bucket.threshold(), Some(50));
+
+    // Permits keep the threshold they were granted at
+    bucket.set_threshold(70).unwrap();
+    assert!(bucket.try_permit().is_err());
+    bucket.try_acquire(permit, 5).unwrap();
+
+    assert_eq!(
+        bucket.set_threshold(200),
+        Err(BuildError::ThresholdExceedsMax {
+            threshold: 200,
+            max: 200
+        })
+    );
+    assert_eq!(
+        builder(60, 200).build().unwrap().set_threshold(50),
+        Err(BuildError::UnsupportedPermitter)
+    );
+}
+
+#[test]
+fn refill_rate_with_max() {
+    for builder in [
+        Bucket::builder()
+            .refill_rate(100, Duration::from_secs(10))
+            .max(200),
+        Bucket::builder()
+            .max(200)
+            .refill_rate(100, Duration::from_secs(10)),
+    ] {
+        let bucket = builder.initial_full().build().unwrap();
+        assert_eq!(bucket.max(), 200);
+        assert_eq!(bucket.available(), 200);
+    }
+
+    // Replacing a refill keeps its maximum
+    let bucket = Bucket::builder()
+        .refill_rate_per_second(1, 50)
+        .refill_rate(10, Duration::from_secs(1))
+        .build()
+        .unwrap();
+    assert_eq!(bucket.max(), 50);
+
+    assert!(matches!(
+        Bucket::builder()
+            .refill_rate(300, Duration::from_secs(10))
+            .max(200)
+            .build(),
+        Err(BuildError::QuantityExceedsMax {
+            quantity: 300,
+            max: 200
+        })
+    ));
+}
+
+#[test]
+fn max_conflicts_with_refill() {
+    for builder in [
+        builder(10, 100).max(200),
+        Bucket::builder().max(200).rate(RateConfig {
+            quantity: 1.into(),
+            interval: Duration::from_secs(1),
+            max: 100.into(),
+        }),
+        Bucket::builder()
+            .max(200)
+            .refill_quota(100, Duration::from_secs(60)),
+    ] {
+        assert!(matches!(
+            builder.build(),
+            Err(BuildError::ConflictingMax {
+                max: 200,
+                refill_max: 100
+            })
+        ));
+    }
+
+    // Same maximum, or replaced by `Builder::refill_rate`
+    assert_eq!(builder(10, 100).max(100).build().unwrap().max(), 100);
+    let bucket = builder(10, 100)
+        .max(200)
+        .refill_rate(1, Duration::from_secs(1))
+        .build()
+        .unwrap();
+    assert_eq!(bucket.max(), 200);
+}
+
+#[test]
+fn refill_rate_zero_quantity() {
+    assert_eq!(
+        Bucket::builder()
+            .refill_rate(0, Duration::from_secs(1))
+            .validate(),
+        Err(vec![BuildError::InvalidRate, BuildError::MissingMax])
+    );
+    assert_eq!(
+        Bucket::builder()
+            .refill_rate(0, Duration::from_secs(1))
+            .max(10)
+            .build()
+            .unwrap_err(),
+        BuildError::InvalidRate
+    );
+    assert_eq!(
+        Bucket::builder()
+            .refill_rate(0, Duration::ZERO)
+            .max(10)
+            .validate(),
+        Err(vec![BuildError::InvalidRate])
+    );
+}
+
+#[test]
+fn rate_config_zero_quantity() {
+    // Unlike `Builder::refill_rate`, this is a bucket that is never refilled
+    let clock = ManualClock::new();
+    let bucket = Bucket::builder()
+        .rate(RateConfig {
+            quantity: 0.into(),
+            interval: Duration::from_secs(1),
+            max: 10.into(),
+        })
+        .initial(4)
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+
+    clock.advance(Duration::from_secs(10));
+    assert_eq!(bucket.available_after_refill(), 4);
+}
+
+#[test]
+fn clone_isolated() {
+    let bucket = builder(8, 10).build().unwrap();
+    bucket.quick_acquire(3).unwrap();
+
+    let isolated = bucket.clone_isolated();
+    assert_eq!(isolated.available(), 8);
+    assert_eq!(isolated.max(), bucket.max());
+
+    isolated.quick_acquire(5).unwrap();
+    assert_eq!(isolated.available(), 3);
+    assert_eq!(bucket.available(), 5);
+}
+
+#[test]
+fn validate_incomplete_builder() {
+    assert_eq!(
+        Bucket::builder().threshold_percent(150).validate(),
+        Err(vec![
+            BuildError::MissingRefill,
+            BuildError::MissingMax,
+            BuildError::InvalidThreshold,
+        ])
+    );
+    assert_eq!(
+        Bucket::builder().refill_rate(5, Duration::ZERO).validate(),
+        Err(vec![BuildError::InvalidRate, BuildError::MissingMax])
+    );
+}
+
+#[test]
+fn validate_reports_all_errors() {
+    let misconfigured = Bucket::builder()
+        .rate(RateConfig {
+            quantity: 20.into(),
+            interval: Duration::ZERO,
+            max: 10.into(),
+        })
+        .threshold(ThresholdConfig {
+            threshold: 15.into(),
+        });
+
+    let errors = vec![
+        BuildError::InvalidRate,
+        BuildError::QuantityExceedsMax {
+            quantity: 20,
+            max: 10,
+        },
+        BuildError::ThresholdExceedsMax {
+            threshold: 15,
+            max: 10,
+        },
+    ];
+    assert_eq!(misconfigured.validate(), Err(errors));
+    assert_eq!(misconfigured.build().unwrap_err(), BuildError::InvalidRate);
+
+    assert_eq!(builder(5, 10).validate(), Ok(()));
+}
+
+#[test]
+fn lowered_shared_max_after_build() {
+    let max = Arc::new(AtomicU64::new(200));
+    let config = builder(60, 200)
+        .shared_max(max.clone())
+        .threshold(ThresholdConfig {
+            threshold: 100.into(),
+        });
+    let bucket = config.clone().build().unwrap();
+    let map = BucketMap::builder(config).build().unwrap();
+
+    // The threshold is now above the max, but buckets were already validated with it
+    max.store(50, Ordering::Release);
+    assert_eq!(bucket.clone_isolated().max(), 50);
+    assert_eq!(map.get_or_insert(&"a").max(), 50);
+}
+
+#[test]
+fn try_from_builder() {
+    let bucket = Bucket::try_from(builder(60, 200)).unwrap();
+    assert_eq!(bucket.available(), 60);
+    assert_eq!(bucket.capacity(), 200);
+
+    let bucket: Bucket = builder(20, 100).try_into().unwrap();
+    assert_eq!(bucket.available(), 20);
+
+    let result: Result<Bucket, _> = builder(60, 200)
+        .threshold(ThresholdConfig {
+            threshold: 201.into(),
+        })
+        .try_into();
+    assert!(matches!(
+        result,
+        Err(BuildError::ThresholdExceedsMax {
+            threshold: 201,
+            max: 200
+        })
+    ));
+}
+
+#[test]
+fn max_above_token_limit() {
+    let limit = u64::from(TokenCount::MAX);
+    assert!(builder(0, limit).build().is_ok());
+    assert_eq!(
+        builder(0, limit + 1).build().unwrap_err(),
+        BuildError::TooManyTokens { tokens: limit + 1 }
+    );
+    assert_eq!(
+        builder(0, u64::MAX).build().unwrap_err(),
+        BuildError::TooManyTokens { tokens: u64::MAX }
+    );
+
+    // Also for a shared maximum
+    let shared = Arc::new(AtomicU64::new(limit + 1));
+    assert_eq!(
+        builder(0, 10).shared_max(shared).build().unwrap_err(),
+        BuildError::TooManyTokens { tokens: limit + 1 }
+    );
+}
+
+#[test]
+fn initial_above_max() {
+    assert!(builder(10, 10).build().is_ok());
+    assert_eq!(
+        builder(11, 10).build().unwrap_err(),
+        BuildError::InitialExceedsMax {
+            initial: 11,
+            max: 10
+        }
+    );
+    assert_eq!(
+        builder(u64::MAX / 4 * 3, u64::MAX).validate(),
+        Err(vec![
+            BuildError::TooManyTokens { tokens: u64::MAX },
+            BuildError::TooManyTokens {
+                tokens: u64::MAX / 4 * 3
+            },
+        ])
+    );
+
+    // Including the default initial tokens of a burst refill
+    assert_eq!(
+        Bucket::builder()
+            .refill_burst(20, 1, Duration::from_secs(1), 10)
+            .build()
+            .unwrap_err(),
+        BuildError::InitialExceedsMax {
+            initial: 20,
+            max: 10
+        }
+    );
+}
+
+#[test]
+fn credit_limit_above_token_limit() {
+    let limit = u64::from(TokenCount::MAX);
+    assert!(builder(0, 10).credit_limit(limit).build().is_ok());
+    assert_eq!(
+        builder(0, 10).credit_limit(limit + 1).build().unwrap_err(),
+        BuildError::TooManyTokens { tokens: limit + 1 }
+    );
+}
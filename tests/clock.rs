@@ -0,0 +1,443 @@
+//! Time-dependent behaviour, driven by a manual clock instead of sleeping.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use threshold_bucket::{
+    clock::ManualClock,
+    refill::{ExponentialConfig, RateConfig, SlidingWindowConfig},
+    Bucket, Error,
+};
+
+#[test]
+fn refill_after_advancing_clock() {
+    let clock = ManualClock::new();
+    let bucket = Bucket::builder()
+        .sliding_window(SlidingWindowConfig {
+            quantity: 10,
+            window: Duration::from_secs(10),
+            max: 100,
+        })
+        .initial(3)
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+
+    assert_eq!(bucket.available_after_refill(), 3);
+
+    clock.advance(Duration::from_secs(5));
+    assert_eq!(bucket.available_after_refill(), 3 + 5);
+
+    // Refills never go above the maximum
+    clock.advance(Duration::from_secs(3600));
+    assert_eq!(bucket.available_after_refill(), 100);
+}
+
+#[test]
+fn sub_millisecond_rate_interval() {
+    let clock = ManualClock::new();
+    let bucket = Bucket::builder()
+        .rate(RateConfig {
+            quantity: 1.into(),
+            interval: Duration::from_micros(100),
+            max: 10.into(),
+        })
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+
+    clock.advance(Duration::from_micros(40));
+    let permit = bucket.try_permit().unwrap();
+    match bucket.try_acquire(permit, 1) {
+        Err(Error::NotEnoughTokens { wait_for, .. }) => {
+            assert_eq!(wait_for, Some(Duration::from_micros(60)))
+        }
+        result => panic!("unexpected result: {result:?}"),
+    }
+}
+
+#[test]
+fn rate_refill_adds_tokens_each_interval() {
+    let clock = ManualClock::new();
+    let bucket = Bucket::builder()
+        .rate(RateConfig {
+            quantity: 3.into(),
+            interval: Duration::from_millis(10),
+            max: 10.into(),
+        })
+        .initial(0)
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+
+    // A single thread never fails the compare-and-swap on `refill_at`
+    assert_eq!(bucket.available_after_refill(), 0);
+    clock.advance(Duration::from_millis(10));
+    assert_eq!(bucket.available_after_refill(), 3);
+    clock.advance(Duration::from_millis(10));
+    assert_eq!(bucket.available_after_refill(), 6);
+    clock.advance(Duration::from_millis(50));
+    assert_eq!(bucket.available_after_refill(), 10);
+}
+
+#[test]
+fn set_rate_at_runtime() {
+    let clock = ManualClock::new();
+    let bucket = Bucket::builder()
+        .rate(RateConfig {
+            quantity: 1.into(),
+            interval: Duration::from_millis(10),
+            max: 100.into(),
+        })
+        .initial(0)
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+
+    clock.advance(Duration::from_millis(5));
+    bucket.set_rate(5, Duration::from_millis(20)).unwrap();
+
+    // The schedule restarts from the change
+    clock.advance(Duration::from_millis(10));
+    assert_eq!(bucket.available_after_refill(), 0);
+    clock.advance(Duration::from_millis(10));
+    assert_eq!(bucket.available_after_refill(), 5);
+    clock.advance(Duration::from_millis(40));
+    assert_eq!(bucket.available_after_refill(), 15);
+
+    assert!(matches!(
+        bucket.set_rate(0, Duration::from_millis(20)),
+        Err(Error::InvalidRate)
+    ));
+}
+
+#[test]
+fn set_rate_keeps_due_tokens() {
+    let clock = ManualClock::new();
+    let bucket = Bucket::builder()
+        .rate(RateConfig::per_second(1, 10))
+        .initial(0)
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+
+    // Tokens due with the previous rate are added before it changes
+    clock.advance(Duration::from_secs(5));
+    bucket.set_rate(2, Duration::from_secs(1)).unwrap();
+    assert_eq!(bucket.available_after_refill(), 5);
+
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(bucket.available_after_refill(), 7);
+}
+
+#[test]
+fn set_rate_unsupported() {
+    let bucket = Bucket::builder()
+        .sliding_window(SlidingWindowConfig {
+            quantity: 10,
+            window: Duration::from_secs(1),
+            max: 10,
+        })
+        .build()
+        .unwrap();
+
+    assert!(matches!(
+        bucket.set_rate(1, Duration::from_secs(1)),
+        Err(Error::UnsupportedOperation)
+    ));
+    assert_eq!(bucket.effective_rate(), None);
+}
+
+#[test]
+fn byte_level_rate_over_a_day() {
+    // 100 Gbps, with one token per byte, for 24 hours
+    const BYTES_PER_SECOND: u64 = 100_000_000_000 / 8;
+    const DAY: u64 = 24 * 3600;
+
+    let clock = ManualClock::new();
+    let bucket = Bucket::builder()
+        .rate(RateConfig {
+            quantity: BYTES_PER_SECOND.into(),
+            interval: Duration::from_secs(1),
+            max: (BYTES_PER_SECOND * DAY).into(),
+        })
+        .initial(0)
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+
+    clock.advance(Duration::from_secs(DAY));
+    assert_eq!(bucket.available_after_refill(), BYTES_PER_SECOND * DAY);
+    assert_eq!(
+        bucket.quick_acquire(BYTES_PER_SECOND * DAY).unwrap(),
+        BYTES_PER_SECOND * DAY
+    );
+}
+
+#[test]
+fn shared_max_changes_capacity() {
+    let max = Arc::new(AtomicU64::new(10));
+    let clock = ManualClock::new();
+    let bucket = Bucket::builder()
+        .rate(RateConfig {
+            quantity: 5.into(),
+            interval: Duration::from_secs(1),
+            max: 10.into(),
+        })
+        .initial(8)
+        .shared_max(max.clone())
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+    assert_eq!(bucket.capacity(), 10);
+
+    // Lowered below the available tokens, nothing is added
+    max.store(6, Ordering::Release);
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(bucket.available_after_refill(), 8);
+    assert_eq!(bucket.capacity(), 6);
+    assert!(matches!(
+        bucket.quick_acquire(7),
+        Err(Error::ExceedMaxTokens)
+    ));
+
+    // Raised above the refill configuration, tokens accumulate up to the new maximum
+    max.store(20, Ordering::Release);
+    for _ in 0..3 {
+        clock.advance(Duration::from_secs(1));
+        bucket.available_after_refill();
+    }
+    assert_eq!(bucket.available(), 20);
+    assert_eq!(bucket.quick_acquire(15).unwrap(), 15);
+}
+
+#[test]
+fn next_refill_in() {
+    let clock = ManualClock::new();
+    let bucket = Bucket::builder()
+        .rate(RateConfig {
+            quantity: 1.into(),
+            interval: Duration::from_secs(10),
+            max: 10.into(),
+        })
+        .initial(10)
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+
+    assert_eq!(bucket.next_refill_in(), Some(Duration::from_secs(10)));
+    clock.advance(Duration::from_secs(4));
+    assert_eq!(bucket.next_refill_in(), Some(Duration::from_secs(6)));
+    clock.advance(Duration::from_secs(7));
+    assert_eq!(bucket.next_refill_in(), Some(Duration::ZERO));
+    assert_eq!(bucket.available(), 10);
+}
+
+#[test]
+fn rate_refill_after_years() {
+    let clock = ManualClock::new();
+    let bucket = Bucket::builder()
+        .rate(RateConfig {
+            quantity: 1.into(),
+            interval: Duration::from_nanos(1),
+            max: 10.into(),
+        })
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+
+    // Far more than `u32::MAX` intervals
+    clock.advance(Duration::from_secs(10 * 365 * 86400));
+    let permit = bucket.try_permit().unwrap();
+    assert_eq!(bucket.try_acquire(permit, 0).unwrap(), 0);
+    assert_eq!(bucket.available(), 10);
+}
+
+#[test]
+fn rate_wait_for_many_intervals() {
+    let builder = |interval| {
+        Bucket::builder()
+            .rate(RateConfig {
+                quantity: 1.into(),
+                interval,
+                max: 100_000_000_000.into(),
+            })
+            .initial(0)
+            .with_clock(ManualClock::new())
+            .build()
+            .unwrap()
+    };
+
+    // Far more than `u32::MAX` intervals
+    let bucket = builder(Duration::from_secs(3600));
+    assert_eq!(
+        bucket.wait_for(10_000_000_000),
+        Some(Duration::from_secs(10_000_000_000 * 3600))
+    );
+
+    // Saturates instead of overflowing
+    let bucket = builder(Duration::from_secs(u64::MAX / 2));
+    assert_eq!(bucket.wait_for(3), Some(Duration::MAX));
+}
+
+#[test]
+fn quota_resets_at_boundary() {
+    let clock = ManualClock::new();
+    let bucket = Bucket::builder()
+        .refill_quota(100, Duration::from_secs(3600))
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+
+    assert_eq!(bucket.available(), 100);
+    bucket.drain().unwrap();
+    assert_eq!(bucket.wait_for(1), Some(Duration::from_secs(3600)));
+
+    clock.advance(Duration::from_secs(3600) - Duration::from_nanos(1));
+    assert_eq!(bucket.available_after_refill(), 0);
+    clock.advance(Duration::from_nanos(1));
+    assert_eq!(bucket.available_after_refill(), 100);
+
+    // Missed resets only fill the bucket once, on the same schedule
+    bucket.drain().unwrap();
+    clock.advance(Duration::from_secs(3 * 3600 + 60));
+    assert_eq!(bucket.available_after_refill(), 100);
+    assert_eq!(bucket.wait_for(101), None);
+    bucket.drain().unwrap();
+    assert_eq!(bucket.wait_for(1), Some(Duration::from_secs(3600 - 60)));
+}
+
+#[test]
+fn wait_for_without_acquiring() {
+    let clock = ManualClock::new();
+    let bucket = Bucket::builder()
+        .sliding_window(SlidingWindowConfig {
+            quantity: 10,
+            window: Duration::from_secs(10),
+            max: 10,
+        })
+        .initial(2)
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+
+    assert_eq!(bucket.wait_for(2), Some(Duration::ZERO));
+    assert_eq!(bucket.wait_for(5), Some(Duration::from_secs(3)));
+    assert_eq!(bucket.wait_for(11), None);
+
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(bucket.wait_for(5), Some(Duration::from_secs(2)));
+    assert_eq!(bucket.available(), 3);
+}
+
+#[test]
+fn rate_wait_for_exactly_available() {
+    let clock = ManualClock::new();
+    let bucket = Bucket::builder()
+        .rate(RateConfig {
+            quantity: 1.into(),
+            interval: Duration::from_secs(10),
+            max: 10.into(),
+        })
+        .initial(4)
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+
+    assert_eq!(bucket.wait_for(4), Some(Duration::ZERO));
+    assert_eq!(bucket.wait_for(5), Some(Duration::from_secs(10)));
+}
+
+#[test]
+fn exponential_refill() {
+    let clock = ManualClock::new();
+    let bucket = Bucket::builder()
+        .refill_exponential(ExponentialConfig {
+            initial_quantity: 1,
+            max_quantity: 100,
+            multiplier: 2.0,
+            interval: Duration::from_secs(1),
+            max: 1000,
+        })
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+
+    let mut added = Vec::new();
+    for _ in 0..4 {
+        let before = bucket.available_after_refill();
+        clock.advance(Duration::from_secs(1));
+        added.push(bucket.available_after_refill() - before);
+    }
+    // The fourth interval adds `initial_quantity * multiplier^3` tokens
+    assert_eq!(added, [1, 2, 4, 8]);
+
+    // Back to the initial quantity after a reset
+    bucket.reset_refill();
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(bucket.available_after_refill(), 16);
+}
+
+#[test]
+fn burst_then_sustained_rate() {
+    let clock = ManualClock::new();
+    let builder = Bucket::builder()
+        .refill_burst(50, 2, Duration::from_secs(1), 100)
+        .with_clock(clock.clone());
+    let bucket = builder.clone().build().unwrap();
+
+    // The whole burst is available right away
+    assert_eq!(bucket.available(), 50);
+    assert_eq!(bucket.capacity(), 100);
+    assert_eq!(bucket.quick_acquire(50).unwrap(), 50);
+    assert!(matches!(
+        bucket.quick_acquire(1),
+        Err(Error::NotEnoughTokens { available: 0, .. })
+    ));
+
+    // Then tokens come back at the sustained rate
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(bucket.available_after_refill(), 2);
+    clock.advance(Duration::from_millis(2500));
+    assert_eq!(bucket.available_after_refill(), 6);
+
+    // The initial number of tokens overrides the burst
+    let bucket = builder.initial(5).build().unwrap();
+    assert_eq!(bucket.available(), 5);
+}
+
+#[test]
+fn refill_gives_up_above_underflow_guard() {
+    let clock = ManualClock::new();
+    let quarter = u64::MAX / 4;
+    let max = Arc::new(AtomicU64::new(quarter));
+    let bucket = Bucket::builder()
+        .rate(RateConfig {
+            quantity: quarter.into(),
+            interval: Duration::from_secs(1),
+            max: quarter.into(),
+        })
+        .shared_max(max.clone())
+        .initial(quarter)
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+
+    // Raising the shared maximum lets refills take the count past the underflow guard, where it
+    // looks like an acquisition about to be reverted
+    max.store(u64::MAX, Ordering::Release);
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(bucket.available_after_refill(), 2 * quarter);
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(bucket.available_after_refill(), 0);
+
+    // Later refills don't wait forever for the count to come back down
+    clock.advance(Duration::from_secs(1));
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || tx.send(bucket.available_after_refill()).unwrap());
+    assert_eq!(rx.recv_timeout(Duration::from_secs(10)), Ok(0));
+}
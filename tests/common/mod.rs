@@ -0,0 +1,23 @@
+//! Fixtures shared by the integration tests.
+
+// Each test crate only uses some of the fixtures
+#![allow(dead_code)]
+
+use std::time::Duration;
+use threshold_bucket::{refill::RateConfig, Bucket, Builder};
+
+/// Builder for a bucket with `initial` tokens out of `max`, without any refill during the test
+pub fn builder(initial: u64, max: u64) -> Builder {
+    Bucket::builder()
+        .rate(RateConfig {
+            quantity: 1.into(),
+            interval: Duration::from_secs(3600),
+            max: max.into(),
+        })
+        .initial(initial)
+}
+
+/// Bucket with `initial` tokens out of 10, without any refill during the test
+pub fn bucket(initial: u64) -> Bucket {
+    builder(initial, 10).build().unwrap()
+}
@@ -0,0 +1,306 @@
+//! Concurrent acquisitions, with all threads starting at the same time.
+//!
+//! Run with `cargo test -- --test-threads 1` to avoid interference from other tests.
+
+use std::{
+    sync::{Arc, Barrier},
+    thread,
+    time::{Duration, Instant},
+};
+use threshold_bucket::{
+    clock::ManualClock, permit::ThresholdConfig, refill::RateConfig, Bucket, Builder,
+    CheckedPermitError, ContentionStrategy, Error,
+};
+
+mod common;
+
+const THREADS: usize = 16;
+const ATTEMPTS: u64 = 1_000;
+
+/// Bucket without any refill during the test
+fn bucket(initial: u64) -> Bucket {
+    builder(initial).build().unwrap()
+}
+
+fn builder(initial: u64) -> Builder {
+    common::builder(initial, initial)
+}
+
+#[derive(Default)]
+struct Outcome {
+    acquired: u64,
+    denied: u64,
+    contention: u64,
+}
+
+/// Run `ATTEMPTS` acquisitions of `num` tokens on `threads` threads starting simultaneously
+fn run(bucket: &Bucket, threads: usize, num: u64) -> Outcome {
+    let barrier = Arc::new(Barrier::new(threads));
+
+    let handles: Vec<_> = (0..threads)
+        .map(|_| {
+            let bucket = bucket.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                let mut outcome = Outcome::default();
+                barrier.wait();
+                for _ in 0..ATTEMPTS {
+                    let permit = bucket.try_permit().unwrap();
+                    match bucket.try_acquire(permit, num) {
+                        Ok(acquired) => {
+                            assert_eq!(acquired, num, "acquired more tokens than requested");
+                            outcome.acquired += acquired;
+                        }
+                        Err(Error::NotEnoughTokens { .. }) => outcome.denied += 1,
+                        Err(Error::HighContention) => outcome.contention += 1,
+                        Err(err) => panic!("unexpected error: {err}"),
+                    }
+                }
+                outcome
+            })
+        })
+        .collect();
+
+    handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .fold(Outcome::default(), |total, outcome| Outcome {
+            acquired: total.acquired + outcome.acquired,
+            denied: total.denied + outcome.denied,
+            contention: total.contention + outcome.contention,
+        })
+}
+
+#[test]
+fn acquire_one_under_contention() {
+    let initial = 5_000;
+    let bucket = bucket(initial);
+
+    let outcome = run(&bucket, THREADS, 1);
+
+    assert_eq!(outcome.acquired + bucket.available(), initial);
+    assert_eq!(
+        outcome.acquired + outcome.denied + outcome.contention,
+        THREADS as u64 * ATTEMPTS
+    );
+    if outcome.contention == 0 {
+        let requested = THREADS as u64 * ATTEMPTS;
+        assert_eq!(outcome.acquired, requested.min(initial));
+    }
+}
+
+#[test]
+fn acquire_many_under_contention() {
+    let initial = 10_000;
+    let bucket = bucket(initial);
+
+    let outcome = run(&bucket, THREADS, 7);
+
+    assert_eq!(outcome.acquired + bucket.available(), initial);
+    assert_eq!(outcome.acquired % 7, 0);
+    if outcome.contention == 0 {
+        // Only the last few tokens cannot be acquired
+        assert!(bucket.available() < 7);
+    }
+}
+
+#[test]
+fn acquire_with_single_cas_retry() {
+    let initial = 1_000;
+    let bucket = builder(initial).max_cas_retries(1).build().unwrap();
+
+    let start = Instant::now();
+    let outcome = run(&bucket, 2, 3);
+    let elapsed = start.elapsed();
+
+    // Failed attempts return immediately instead of spinning
+    assert!(elapsed < Duration::from_secs(1), "took {elapsed:?}");
+    assert_eq!(outcome.acquired + bucket.available(), initial);
+    assert_eq!(
+        outcome.acquired / 3 + outcome.denied + outcome.contention,
+        2 * ATTEMPTS
+    );
+}
+
+#[test]
+fn acquire_with_mutex_fallback() {
+    let initial = 5_000;
+    let bucket = builder(initial)
+        .contention_strategy(ContentionStrategy::MutexFallback { threshold: 8 })
+        .build()
+        .unwrap();
+
+    let outcome = run(&bucket, THREADS, 1);
+
+    assert_eq!(outcome.acquired + bucket.available(), initial);
+    assert_eq!(outcome.contention, 0);
+    assert_eq!(outcome.acquired, initial);
+}
+
+#[test]
+fn threshold_rechecked_on_acquire() {
+    let (initial, threshold) = (200, 100);
+    let bucket = builder(initial)
+        .threshold(ThresholdConfig {
+            threshold: threshold.into(),
+        })
+        .build()
+        .unwrap();
+    let barrier = Arc::new(Barrier::new(THREADS));
+
+    let handles: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let bucket = bucket.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                barrier.wait();
+                for _ in 0..ATTEMPTS {
+                    let Ok(permit) = bucket.try_permit() else {
+                        continue;
+                    };
+                    // Let other threads drain the bucket while holding the permit
+                    thread::yield_now();
+                    let _ = bucket.try_acquire(permit, 3);
+                }
+            })
+        })
+        .collect();
+    handles
+        .into_iter()
+        .for_each(|handle| handle.join().unwrap());
+
+    // Every acquisition started at or above the threshold
+    let available = bucket.available();
+    assert!(
+        (threshold - 3..threshold).contains(&available),
+        "{available} tokens left"
+    );
+}
+
+#[test]
+fn failed_acquisitions_with_fill_and_drain() {
+    let (max, threshold) = (10, 8);
+    let bucket = builder(max)
+        .threshold(ThresholdConfig {
+            threshold: threshold.into(),
+        })
+        .build()
+        .unwrap();
+    let barrier = Arc::new(Barrier::new(THREADS));
+
+    let handles: Vec<_> = (0..THREADS)
+        .map(|i| {
+            let bucket = bucket.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                barrier.wait();
+                for _ in 0..ATTEMPTS {
+                    // One thread keeps overwriting the number of tokens, while the others fail
+                    // to acquire tokens below the threshold
+                    if i == 0 {
+                        bucket.fill().unwrap();
+                        assert!(bucket.available() <= max);
+                        bucket.drain().unwrap();
+                    } else if let Ok(permit) = bucket.try_permit() {
+                        let _ = bucket.try_acquire(permit, 1);
+                    }
+                    assert!(bucket.available() <= max);
+                }
+            })
+        })
+        .collect();
+    handles
+        .into_iter()
+        .for_each(|handle| handle.join().unwrap());
+
+    let available = bucket.available();
+    assert!(available <= max, "{available} tokens left");
+}
+
+#[test]
+fn refills_not_lost_under_contention() {
+    let clock = ManualClock::new();
+    let bucket = Bucket::builder()
+        .rate(RateConfig {
+            quantity: 1.into(),
+            interval: Duration::from_millis(1),
+            max: 1_000_000.into(),
+        })
+        .initial(0)
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+    let barrier = Arc::new(Barrier::new(THREADS));
+
+    let handles: Vec<_> = (0..THREADS)
+        .map(|i| {
+            let bucket = bucket.clone();
+            let clock = clock.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                barrier.wait();
+                let mut acquired = 0;
+                for _ in 0..ATTEMPTS {
+                    // One thread keeps refills due, while the others mostly fail to acquire
+                    // tokens, wrapping the counter around in the fast path
+                    if i == 0 {
+                        clock.advance(Duration::from_millis(1));
+                    } else if bucket.quick_acquire(2).is_ok() {
+                        acquired += 2;
+                    }
+                }
+                acquired
+            })
+        })
+        .collect();
+    let acquired: u64 = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .sum();
+
+    assert_eq!(acquired + bucket.available_after_refill(), ATTEMPTS);
+}
+
+#[test]
+fn checked_permit_denials_match_threshold() {
+    let threshold = 100;
+    let bucket = builder(2 * threshold)
+        .initial(threshold)
+        .threshold(ThresholdConfig {
+            threshold: threshold.into(),
+        })
+        .build()
+        .unwrap();
+    let barrier = Arc::new(Barrier::new(THREADS));
+
+    let handles: Vec<_> = (0..THREADS)
+        .map(|thread| {
+            let bucket = bucket.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                barrier.wait();
+                for _ in 0..ATTEMPTS {
+                    // Half of the threads take the bucket below the threshold and back above it
+                    if thread % 2 == 0 {
+                        if let Ok(permit) = bucket.try_permit() {
+                            let _ = bucket.acquire_guard(permit, 1);
+                        }
+                        continue;
+                    }
+                    match bucket.try_permit_checked() {
+                        Ok(_) => (),
+                        Err(CheckedPermitError::BelowThreshold {
+                            available,
+                            threshold,
+                            ..
+                        }) => assert!(available < threshold, "denied at {available} tokens"),
+                        Err(err) => panic!("unexpected error: {err:?}"),
+                    }
+                }
+            })
+        })
+        .collect();
+    handles
+        .into_iter()
+        .for_each(|handle| handle.join().unwrap());
+}
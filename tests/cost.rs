@@ -0,0 +1,57 @@
+//! Token costs of named operations.
+
+use std::time::Duration;
+use threshold_bucket::{refill::RateConfig, Bucket, Builder, CostMap};
+
+fn builder() -> Builder {
+    Bucket::builder()
+        .rate(RateConfig {
+            quantity: 1.into(),
+            interval: Duration::from_secs(60),
+            max: 20.into(),
+        })
+        .initial(20)
+}
+
+#[test]
+fn registered_op_costs() {
+    let bucket = builder()
+        .register_op_cost("read", 1)
+        .register_op_cost("bulk_write", 10)
+        .build()
+        .unwrap();
+
+    let permit = bucket.try_permit().unwrap();
+    assert_eq!(bucket.try_acquire_for_op(permit, "read").unwrap(), 1);
+    assert_eq!(bucket.available(), 19);
+
+    let permit = bucket.try_permit().unwrap();
+    assert_eq!(bucket.try_acquire_for_op(permit, "bulk_write").unwrap(), 10);
+    assert_eq!(bucket.available(), 9);
+}
+
+#[test]
+fn unregistered_op_uses_default_cost() {
+    let bucket = builder()
+        .with_cost_map(CostMap::new().with_cost("read", 1).with_default_cost(5))
+        .build()
+        .unwrap();
+
+    let permit = bucket.try_permit().unwrap();
+    assert_eq!(bucket.try_acquire_for_op(permit, "delete").unwrap(), 5);
+    assert_eq!(bucket.available(), 15);
+}
+
+#[test]
+fn cost_map_is_kept_by_isolated_clones() {
+    let bucket = builder()
+        .register_op_cost("bulk_write", 10)
+        .build()
+        .unwrap();
+
+    let clone = bucket.clone_isolated();
+    let permit = clone.try_permit().unwrap();
+    clone.try_acquire_for_op(permit, "bulk_write").unwrap();
+    assert_eq!(clone.available(), 10);
+    assert_eq!(bucket.available(), 20);
+}
@@ -0,0 +1,86 @@
+//! Deduplicated acquisitions.
+#![cfg(feature = "tokio")]
+
+use futures::future::join_all;
+use std::time::Duration;
+use threshold_bucket::{dedup::Dedup, refill::SlidingWindowConfig, Bucket, Error};
+
+fn bucket(initial: u64) -> Bucket {
+    Bucket::builder()
+        .sliding_window(SlidingWindowConfig {
+            quantity: 10,
+            window: Duration::from_millis(100),
+            max: 10,
+        })
+        .initial(initial)
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn concurrent_calls_share_one_acquisition() {
+    // Empty, so that the first acquisition waits while the other calls arrive
+    let bucket = bucket(0);
+    let dedup = Dedup::new(bucket.clone());
+
+    let results =
+        join_all((0..50).map(|_| dedup.try_acquire("key", || bucket.try_permit(), 1))).await;
+
+    assert_eq!(results, vec![Ok(1); 50]);
+    assert_eq!(bucket.stats().acquired_tokens, 1);
+    assert_eq!(dedup.in_flight(), 0);
+}
+
+#[tokio::test]
+async fn distinct_keys_acquire_separately() {
+    let bucket = bucket(0);
+    let dedup = Dedup::new(bucket.clone());
+
+    let results =
+        join_all(["a", "b", "a", "b"].map(|key| dedup.try_acquire(key, || bucket.try_permit(), 2)))
+            .await;
+
+    assert_eq!(results, vec![Ok(2); 4]);
+    assert_eq!(bucket.stats().acquired_tokens, 4);
+}
+
+#[tokio::test]
+async fn sequential_calls_acquire_again() {
+    let bucket = bucket(10);
+    let dedup = Dedup::new(bucket.clone());
+
+    for _ in 0..3 {
+        assert_eq!(dedup.try_acquire(1, || bucket.try_permit(), 2).await, Ok(2));
+    }
+    assert_eq!(bucket.available(), 4);
+}
+
+#[tokio::test]
+async fn errors_are_shared() {
+    let bucket = bucket(0);
+    let dedup = Dedup::new(bucket.clone());
+
+    let results = join_all((0..3).map(|_| dedup.try_acquire((), || bucket.try_permit(), 20))).await;
+
+    assert_eq!(results, vec![Err(Error::ExceedMaxTokens); 3]);
+}
+
+#[tokio::test]
+async fn cancelled_acquisition_is_taken_over() {
+    let bucket = bucket(0);
+    let dedup = Dedup::new(bucket.clone());
+
+    // The first call starts the acquisition, then gets cancelled while the second one waits
+    let (first, second) = tokio::join!(
+        tokio::time::timeout(
+            Duration::from_millis(1),
+            dedup.try_acquire("key", || bucket.try_permit(), 1)
+        ),
+        dedup.try_acquire("key", || bucket.try_permit(), 1),
+    );
+
+    assert!(first.is_err());
+    assert_eq!(second, Ok(1));
+    assert_eq!(bucket.stats().acquired_tokens, 1);
+    assert_eq!(dedup.in_flight(), 0);
+}
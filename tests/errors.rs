@@ -0,0 +1,109 @@
+//! Comparing errors in assertions.
+
+use std::time::Duration;
+use threshold_bucket::{
+    clock::ManualClock,
+    permit::{Permit, Permitter, ThresholdConfig},
+    refill::RateConfig,
+    Bucket, CheckedPermitError, Error,
+};
+
+#[test]
+fn checked_permit_errors() {
+    let bucket = |initial| {
+        Bucket::builder()
+            .rate(RateConfig {
+                quantity: 1.into(),
+                interval: Duration::from_secs(1),
+                max: 10.into(),
+            })
+            .threshold(ThresholdConfig {
+                threshold: 5.into(),
+            })
+            .initial(initial)
+            .with_clock(ManualClock::new())
+            .build()
+            .unwrap()
+    };
+
+    assert!(bucket(5).try_permit_checked().is_ok());
+
+    let err = bucket(2).try_permit_checked().unwrap_err();
+    assert_eq!(
+        err,
+        CheckedPermitError::BelowThreshold {
+            available: 2,
+            threshold: 5,
+            wait_for: Some(Duration::from_secs(3)),
+        }
+    );
+    assert_eq!(
+        Error::from(err),
+        Error::NotEnoughTokens {
+            available: 2,
+            wait_for: Some(Duration::from_secs(3)),
+        }
+    );
+}
+
+struct DenyAll;
+
+impl Permitter for DenyAll {
+    fn get_permit(&self) -> Option<Permit> {
+        None
+    }
+}
+
+#[test]
+fn checked_permit_denied() {
+    let bucket = Bucket::builder()
+        .rate(RateConfig {
+            quantity: 1.into(),
+            interval: Duration::from_secs(1),
+            max: 10.into(),
+        })
+        .initial(10)
+        .with_clock(ManualClock::new())
+        .with_permitter(DenyAll)
+        .build()
+        .unwrap();
+
+    assert!(matches!(
+        bucket.try_permit_checked(),
+        Err(CheckedPermitError::Denied { available: 10, .. })
+    ));
+}
+
+/// Permitter refusing permits as if only `0` tokens were available
+struct DenyAtZero;
+
+impl Permitter for DenyAtZero {
+    fn get_permit(&self) -> Option<Permit> {
+        None
+    }
+
+    fn get_permit_checked(&self) -> Result<Permit, Option<u64>> {
+        Err(Some(0))
+    }
+}
+
+#[test]
+fn checked_permit_denied_at() {
+    let bucket = Bucket::builder()
+        .rate(RateConfig {
+            quantity: 1.into(),
+            interval: Duration::from_secs(1),
+            max: 10.into(),
+        })
+        .initial(10)
+        .with_clock(ManualClock::new())
+        .with_permitter(DenyAtZero)
+        .build()
+        .unwrap();
+
+    // The number of tokens the permitter refused the permit at, not the current one
+    assert!(matches!(
+        bucket.try_permit_checked(),
+        Err(CheckedPermitError::Denied { available: 0, .. })
+    ));
+}
@@ -0,0 +1,130 @@
+//! Fair permit allocation across callers.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+use threshold_bucket::{clock::ManualClock, permit::caller_id, refill::RateConfig, Bucket};
+
+const TOKENS: u64 = 1_000;
+
+fn bucket(clock: &ManualClock) -> Bucket {
+    Bucket::builder()
+        .rate(RateConfig {
+            quantity: 1.into(),
+            interval: Duration::from_secs(3600),
+            max: TOKENS.into(),
+        })
+        .initial(TOKENS)
+        .with_clock(clock.clone())
+        .fair()
+        .build()
+        .unwrap()
+}
+
+fn acquire(bucket: &Bucket, caller: u64) {
+    let permit = bucket.try_permit_for(caller).unwrap();
+    bucket.try_acquire(permit, 1).unwrap();
+}
+
+#[test]
+fn alternates_between_callers() {
+    let clock = ManualClock::new();
+    let bucket = bucket(&clock);
+    let (a, b) = (caller_id("a"), caller_id("b"));
+
+    // `a` is the only caller so far
+    acquire(&bucket, a);
+    acquire(&bucket, a);
+
+    // `b` starts at the same virtual time as `a`, instead of catching up
+    acquire(&bucket, b);
+    assert!(bucket.try_permit_for(b).is_err());
+    acquire(&bucket, a);
+    acquire(&bucket, a);
+    assert!(bucket.try_permit_for(a).is_err());
+    assert!(bucket.try_permit_for(b).is_ok());
+
+    // Idle callers don't block the others
+    clock.advance(Duration::from_secs(2));
+    acquire(&bucket, a);
+    acquire(&bucket, a);
+}
+
+#[test]
+fn idle_callers_start_over() {
+    let clock = ManualClock::new();
+    let bucket = bucket(&clock);
+    let (a, b) = (caller_id("a"), caller_id("b"));
+    for _ in 0..5 {
+        acquire(&bucket, a);
+    }
+
+    // `a` went idle, and doesn't keep the tokens it requested before
+    clock.advance(Duration::from_secs(2));
+    acquire(&bucket, b);
+    acquire(&bucket, a);
+    assert!(bucket.try_permit_for(a).is_err());
+    acquire(&bucket, b);
+
+    // Many one-off callers don't slow down or block the others
+    for caller in 0..500u64 {
+        acquire(&bucket, caller_id(&caller));
+        clock.advance(Duration::from_millis(1));
+    }
+    clock.advance(Duration::from_secs(2));
+    acquire(&bucket, a);
+    acquire(&bucket, b);
+}
+
+#[test]
+fn shares_tokens_under_contention() {
+    let clock = ManualClock::new();
+    let bucket = bucket(&clock);
+    let callers = [caller_id("a"), caller_id("b")];
+    // Register both callers before they start competing
+    for caller in callers {
+        drop(bucket.try_permit_for(caller).unwrap());
+    }
+
+    let acquired: Vec<_> = callers
+        .into_iter()
+        .map(|caller| {
+            let bucket = bucket.clone();
+            let count = Arc::new(AtomicU64::new(0));
+            let handle = thread::spawn({
+                let count = count.clone();
+                move || {
+                    while !bucket.is_empty() {
+                        match bucket.try_permit_for(caller) {
+                            Ok(permit) => {
+                                if bucket.try_acquire(permit, 1).is_ok() {
+                                    count.fetch_add(1, Ordering::Relaxed);
+                                }
+                            }
+                            // Let the other caller catch up
+                            Err(_) => thread::yield_now(),
+                        }
+                    }
+                }
+            });
+            (handle, count)
+        })
+        .collect();
+
+    let counts: Vec<_> = acquired
+        .into_iter()
+        .map(|(handle, count)| {
+            handle.join().unwrap();
+            count.load(Ordering::Relaxed)
+        })
+        .collect();
+    assert_eq!(counts.iter().sum::<u64>(), TOKENS);
+    for count in counts {
+        assert!(count.abs_diff(TOKENS / 2) <= TOKENS / 20, "{count}");
+    }
+}
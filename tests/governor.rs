@@ -0,0 +1,46 @@
+//! Conversion of rate refills into `governor` quotas.
+#![cfg(feature = "governor")]
+
+use std::{num::NonZeroU32, time::Duration};
+use threshold_bucket::{refill::RateConfig, BuildError};
+
+fn quota(quantity: u64, interval: Duration, max: u64) -> Result<governor::Quota, BuildError> {
+    RateConfig {
+        quantity: quantity.into(),
+        interval,
+        max: max.into(),
+    }
+    .try_into()
+}
+
+#[test]
+fn rate_into_quota() {
+    let quota = quota(10, Duration::from_secs(1), 20).unwrap();
+    assert_eq!(quota.replenish_interval(), Duration::from_millis(100));
+    assert_eq!(quota.burst_size(), NonZeroU32::new(20).unwrap());
+    assert_eq!(
+        quota,
+        governor::Quota::per_second(NonZeroU32::new(10).unwrap())
+            .allow_burst(NonZeroU32::new(20).unwrap())
+    );
+}
+
+#[test]
+fn rate_into_quota_saturates() {
+    let quota = quota(1, Duration::from_secs(60), u64::MAX).unwrap();
+    assert_eq!(quota.replenish_interval(), Duration::from_secs(60));
+    assert_eq!(quota.burst_size(), NonZeroU32::MAX);
+}
+
+#[test]
+fn invalid_rate_into_quota() {
+    assert_eq!(
+        quota(0, Duration::from_secs(1), 20),
+        Err(BuildError::InvalidRate)
+    );
+    assert_eq!(
+        quota(10, Duration::from_secs(1), 0),
+        Err(BuildError::InvalidRate)
+    );
+    assert_eq!(quota(10, Duration::ZERO, 20), Err(BuildError::InvalidRate));
+}
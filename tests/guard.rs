@@ -0,0 +1,51 @@
+//! Tokens given back to the bucket unless committed.
+
+mod common;
+use common::bucket;
+
+#[test]
+fn returns_tokens_on_drop() {
+    let bucket = bucket(8);
+    let guard = bucket
+        .acquire_guard(bucket.try_permit().unwrap(), 5)
+        .unwrap();
+    assert_eq!(guard.tokens(), 5);
+    assert_eq!(bucket.available(), 3);
+
+    drop(guard);
+    assert_eq!(bucket.available(), 8);
+}
+
+#[test]
+fn returns_tokens_up_to_max() {
+    let bucket = bucket(8);
+    let guard = bucket
+        .acquire_guard(bucket.try_permit().unwrap(), 5)
+        .unwrap();
+    bucket.fill().unwrap();
+
+    drop(guard);
+    assert_eq!(bucket.available(), 10);
+}
+
+#[test]
+fn keeps_committed_tokens() {
+    let bucket = bucket(8);
+    let guard = bucket
+        .acquire_guard(bucket.try_permit().unwrap(), 5)
+        .unwrap();
+
+    guard.commit();
+    assert_eq!(bucket.available(), 3);
+}
+
+#[test]
+fn outlives_bucket() {
+    let bucket = bucket(8);
+    let guard = bucket
+        .acquire_guard(bucket.try_permit().unwrap(), 5)
+        .unwrap();
+
+    drop(bucket);
+    drop(guard);
+}
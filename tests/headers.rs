@@ -0,0 +1,141 @@
+//! Rate limit header values for HTTP responses.
+#![cfg(feature = "http")]
+
+use std::time::Duration;
+use threshold_bucket::{
+    clock::ManualClock, refill::SlidingWindowConfig, Bucket, BucketHeaders, Error,
+};
+
+fn bucket(clock: &ManualClock) -> Bucket {
+    Bucket::builder()
+        .sliding_window(SlidingWindowConfig {
+            quantity: 10,
+            window: Duration::from_secs(10),
+            max: 10,
+        })
+        .initial(4)
+        .with_clock(clock.clone())
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn retry_after_rounds_up() {
+    let clock = ManualClock::new();
+    let bucket = bucket(&clock);
+    clock.advance(Duration::from_millis(500));
+
+    let err = bucket.quick_acquire(6).unwrap_err();
+    assert_eq!(
+        err,
+        Error::NotEnoughTokens {
+            available: 4,
+            wait_for: Some(Duration::from_millis(1500)),
+        }
+    );
+    assert_eq!(err.to_retry_after_header().as_deref(), Some("2"));
+
+    let err = Error::NotEnoughTokens {
+        available: 0,
+        wait_for: Some(Duration::from_secs(3)),
+    };
+    assert_eq!(err.to_retry_after_header().as_deref(), Some("3"));
+    assert_eq!(Error::InvalidPermit.to_retry_after_header(), None);
+}
+
+#[test]
+fn bucket_headers() {
+    let clock = ManualClock::new();
+    let bucket = bucket(&clock);
+
+    let headers = bucket.to_http_headers();
+    assert_eq!(
+        headers,
+        BucketHeaders {
+            limit: 10,
+            remaining: 4,
+            reset: 6,
+        }
+    );
+    assert_eq!(
+        headers.to_header_values(),
+        [
+            ("X-RateLimit-Limit", "10".to_string()),
+            ("X-RateLimit-Remaining", "4".to_string()),
+            ("X-RateLimit-Reset", "6".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn bucket_headers_after_refill() {
+    let clock = ManualClock::new();
+    let bucket = bucket(&clock);
+    clock.advance(Duration::from_millis(3500));
+
+    // Both values come from the same refill, even though no acquisition triggered it
+    assert_eq!(
+        bucket.to_http_headers(),
+        BucketHeaders {
+            limit: 10,
+            remaining: 7,
+            reset: 3,
+        }
+    );
+}
+
+#[test]
+fn rate_limit_headers_after_refill() {
+    use threshold_bucket::http::{rate_limit_headers, RateLimitHeaders};
+
+    let clock = ManualClock::new();
+    let bucket = bucket(&clock);
+    clock.advance(Duration::from_secs(3));
+
+    // Same values as `Bucket::to_http_headers`, so the reset is relative as well
+    let headers = rate_limit_headers(&bucket);
+    assert_eq!(
+        headers,
+        RateLimitHeaders {
+            x_ratelimit_limit: 10,
+            x_ratelimit_remaining: 7,
+            x_ratelimit_reset: 3,
+        }
+    );
+    assert_eq!(
+        headers.to_header_pairs(),
+        bucket.to_http_headers().to_header_values()
+    );
+}
+
+#[test]
+fn rate_limit_header_pairs() {
+    use threshold_bucket::http::{HeaderMapExt, RateLimitHeaders};
+
+    let headers = RateLimitHeaders {
+        x_ratelimit_limit: 10,
+        x_ratelimit_remaining: 4,
+        x_ratelimit_reset: 6,
+    };
+    assert_eq!(
+        headers.to_header_pairs(),
+        [
+            ("X-RateLimit-Limit", "10".to_string()),
+            ("X-RateLimit-Remaining", "4".to_string()),
+            ("X-RateLimit-Reset", "6".to_string()),
+        ]
+    );
+
+    let mut pairs = vec![("x-ratelimit-limit", "1".to_string())];
+    headers.insert_into(&mut pairs);
+    assert_eq!(pairs.len(), 3);
+    assert!(pairs.contains(&("X-RateLimit-Limit", "10".to_string())));
+
+    let mut map = http::HeaderMap::new();
+    map.insert_header("X-RateLimit-Limit", "1".to_string());
+    headers.insert_into(&mut map);
+    assert_eq!(map.len(), 3);
+    assert_eq!(map["x-ratelimit-limit"], "10");
+    assert_eq!(map["x-ratelimit-remaining"], "4");
+    assert_eq!(map["x-ratelimit-reset"], "6");
+}
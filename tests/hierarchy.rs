@@ -0,0 +1,58 @@
+//! Child buckets sharing a parent bucket.
+
+use threshold_bucket::{permit::ThresholdConfig, Builder, Error};
+
+mod common;
+
+fn builder(initial: u64) -> Builder {
+    common::builder(initial, 100)
+}
+
+#[test]
+fn parent_limits_children() {
+    let parent = builder(15).build().unwrap();
+    let first = builder(10).build_child(&parent).unwrap();
+    let second = builder(10).build_child(&parent).unwrap();
+
+    first.try_acquire(first.try_permit().unwrap(), 10).unwrap();
+    assert!(matches!(
+        second.try_acquire(second.try_permit().unwrap(), 10),
+        Err(Error::NotEnoughTokens { .. })
+    ));
+    assert_eq!(second.available(), 10);
+    assert_eq!(parent.available(), 5);
+}
+
+#[test]
+fn child_failure_refunds_parent() {
+    let parent = builder(50).build().unwrap();
+    let child = builder(5).build_child(&parent).unwrap();
+
+    assert!(matches!(
+        child.try_acquire(child.try_permit().unwrap(), 10),
+        Err(Error::NotEnoughTokens { .. })
+    ));
+    assert_eq!(child.available(), 5);
+    assert_eq!(parent.available(), 50);
+}
+
+#[test]
+fn child_rechecks_threshold() {
+    let parent = builder(100).build().unwrap();
+    let child = builder(100)
+        .threshold(ThresholdConfig {
+            threshold: 90.into(),
+        })
+        .build_child(&parent)
+        .unwrap();
+
+    // Other acquisitions take the child below the threshold while the permit is held
+    let permit = child.try_permit().unwrap();
+    child.try_acquire(child.try_permit().unwrap(), 15).unwrap();
+    assert!(matches!(
+        child.try_acquire(permit, 5),
+        Err(Error::NotEnoughTokens { available: 85, .. })
+    ));
+    assert_eq!(child.available(), 85);
+    assert_eq!(parent.available(), 85);
+}
@@ -0,0 +1,56 @@
+//! Acquiring tokens from the shared pool, without permits.
+
+use std::{sync::Arc, thread, time::Duration};
+use threshold_bucket::{permit::ThresholdConfig, refill::RateConfig, Bucket, BucketInner, Error};
+
+fn bucket() -> Bucket {
+    Bucket::builder()
+        .rate(RateConfig {
+            quantity: 1.into(),
+            interval: Duration::from_secs(3600),
+            max: 10.into(),
+        })
+        .initial(4)
+        .threshold(ThresholdConfig {
+            threshold: 5.into(),
+        })
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn bypasses_threshold() {
+    let bucket = bucket();
+    assert!(bucket.try_permit().is_err());
+
+    let inner = bucket.inner();
+    assert_eq!(inner.capacity(), 10);
+    assert_eq!(inner.try_acquire(3).unwrap(), 3);
+    assert_eq!(inner.available(), 1);
+    assert_eq!(bucket.available(), 1);
+    assert_eq!(bucket.stats().acquired_tokens, 3);
+}
+
+#[test]
+fn errors() {
+    let inner = bucket().inner();
+
+    assert!(matches!(
+        inner.try_acquire(5),
+        Err(Error::NotEnoughTokens { available: 4, .. })
+    ));
+    assert_eq!(inner.try_acquire(11), Err(Error::ExceedMaxTokens));
+}
+
+#[test]
+fn shared_across_threads() {
+    let bucket = bucket();
+    let inner: Arc<BucketInner> = bucket.inner();
+
+    let shared = Arc::clone(&inner);
+    thread::spawn(move || shared.try_acquire(2).unwrap())
+        .join()
+        .unwrap();
+    assert_eq!(inner.available(), 2);
+    assert_eq!(bucket.available(), 2);
+}
@@ -0,0 +1,64 @@
+//! Per-key rate limiting with the high-level limiter.
+
+use std::time::Duration;
+use threshold_bucket::{
+    limiter::{RateLimitError, RateLimiter},
+    refill::RateConfig,
+};
+
+fn limiter() -> RateLimiter<String> {
+    RateLimiter::builder(RateConfig::per_hour(1, 10))
+        .initial(5)
+        .threshold(2)
+        .max_keys(2)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn keys_are_limited_separately() {
+    let limiter = limiter();
+    let (alice, bob) = ("alice".to_string(), "bob".to_string());
+
+    limiter.check_n(&alice, 3).unwrap();
+    // 2 tokens left, at the threshold
+    limiter.check(&alice).unwrap();
+    match limiter.check(&alice) {
+        Err(RateLimitError::Exceeded {
+            key_display,
+            retry_after,
+        }) => {
+            assert_eq!(key_display, "alice");
+            assert!(retry_after.unwrap() <= Duration::from_secs(3600));
+        }
+        result => panic!("unexpected result: {result:?}"),
+    }
+
+    limiter.check_n(&bob, 5).unwrap();
+    assert_eq!(limiter.len(), 2);
+}
+
+#[test]
+fn oversized_requests_are_exceeded() {
+    let limiter = limiter();
+
+    assert!(matches!(
+        limiter.check_n(&"carol".to_string(), 11),
+        Err(RateLimitError::Exceeded {
+            retry_after: None,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn least_recently_used_keys_are_forgotten() {
+    let limiter = limiter();
+    for key in ["a", "b", "c"] {
+        limiter.check_n(&key.to_string(), 3).unwrap();
+    }
+    assert_eq!(limiter.len(), 2);
+
+    // "a" starts over with a new bucket
+    limiter.check_n(&"a".to_string(), 3).unwrap();
+}
@@ -0,0 +1,91 @@
+//! Per-key buckets.
+
+use std::time::Duration;
+use threshold_bucket::{
+    map::BucketMap, permit::ThresholdConfig, refill::RateConfig, Bucket, Builder, Error,
+};
+
+fn config(initial: u64) -> Builder {
+    Bucket::builder()
+        .rate(RateConfig {
+            quantity: 1.into(),
+            interval: Duration::from_secs(3600),
+            max: 10.into(),
+        })
+        .initial(initial)
+}
+
+#[test]
+fn creates_buckets_lazily() {
+    let map = BucketMap::builder(config(5)).build().unwrap();
+    assert!(map.is_empty());
+    assert_eq!(map.available(&"a"), None);
+    assert!(map.get(&"a").is_none());
+
+    assert_eq!(map.try_acquire(&"a", 2).unwrap(), 2);
+    assert_eq!(map.len(), 1);
+    assert_eq!(map.available(&"a"), Some(3));
+    assert_eq!(map.available(&"b"), None);
+}
+
+#[test]
+fn keys_share_config_not_tokens() {
+    let map = BucketMap::builder(config(5)).build().unwrap();
+
+    assert_eq!(map.try_acquire(&"a", 5).unwrap(), 5);
+    assert!(matches!(
+        map.try_acquire(&"a", 1),
+        Err(Error::NotEnoughTokens { available: 0, .. })
+    ));
+
+    // Same configuration, but its own tokens
+    let bucket = map.get_or_insert(&"b");
+    assert_eq!(bucket.available(), 5);
+    assert_eq!(bucket.capacity(), 10);
+    assert_eq!(map.try_acquire(&"b", 5).unwrap(), 5);
+    assert_eq!(map.available(&"a"), Some(0));
+
+    // The same bucket is returned for the same key
+    assert_eq!(map.get_or_insert(&"b").available(), 0);
+    assert_eq!(map.len(), 2);
+}
+
+#[test]
+fn evicts_least_recently_used() {
+    let map = BucketMap::builder(config(5))
+        .max_entries(3)
+        .build()
+        .unwrap();
+    for key in ["a", "b", "c"] {
+        map.try_acquire(&key, 1).unwrap();
+    }
+
+    // "a" is now more recently used than "b" and "c"
+    map.get(&"a").unwrap();
+    map.get_or_insert(&"d");
+    assert_eq!(map.len(), 3);
+    assert!(map.get(&"b").is_none());
+
+    // "c" is the least recently used, with "a" and "d" used after it
+    map.get_or_insert(&"e");
+    assert!(map.get(&"c").is_none());
+    for key in ["a", "d", "e"] {
+        assert!(map.get(&key).is_some(), "{key} was evicted");
+    }
+
+    // Evicted buckets are created again with fresh tokens
+    assert_eq!(map.available(&"a"), Some(4));
+    map.get(&"d").unwrap();
+    map.get(&"e").unwrap();
+    assert_eq!(map.try_acquire(&"b", 1).unwrap(), 1);
+    assert!(map.get(&"a").is_none());
+    assert_eq!(map.available(&"b"), Some(4));
+}
+
+#[test]
+fn invalid_config() {
+    let config = config(5).threshold(ThresholdConfig {
+        threshold: 11.into(),
+    });
+    assert!(BucketMap::<&str>::builder(config).build().is_err());
+}
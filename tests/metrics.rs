@@ -0,0 +1,84 @@
+//! Metrics observer hooks.
+
+use std::{sync::Arc, time::Duration};
+use threshold_bucket::{
+    clock::ManualClock,
+    metrics::{MetricEvent, RecordingObserver},
+    permit::ThresholdConfig,
+    refill::SlidingWindowConfig,
+    Bucket,
+};
+
+#[test]
+fn records_acquisitions() {
+    let observer = Arc::new(RecordingObserver::new());
+    let bucket = Bucket::builder()
+        .sliding_window(SlidingWindowConfig {
+            quantity: 10,
+            window: Duration::from_secs(10),
+            max: 10,
+        })
+        .initial(10)
+        .with_metrics(observer.clone())
+        .build()
+        .unwrap();
+
+    for num in [1, 2, 3] {
+        bucket
+            .try_acquire(bucket.try_permit().unwrap(), num)
+            .unwrap();
+    }
+
+    let acquisitions: Vec<_> = observer
+        .events()
+        .into_iter()
+        .filter(|event| matches!(event, MetricEvent::Acquire { .. }))
+        .collect();
+    assert_eq!(
+        acquisitions,
+        vec![
+            MetricEvent::Acquire { tokens: 1 },
+            MetricEvent::Acquire { tokens: 2 },
+            MetricEvent::Acquire { tokens: 3 },
+        ]
+    );
+}
+
+#[test]
+fn records_permits_and_refills() {
+    let clock = ManualClock::new();
+    let observer = Arc::new(RecordingObserver::new());
+    let bucket = Bucket::builder()
+        .sliding_window(SlidingWindowConfig {
+            quantity: 10,
+            window: Duration::from_secs(10),
+            max: 10,
+        })
+        .threshold(ThresholdConfig {
+            threshold: 5.into(),
+        })
+        .initial(4)
+        .with_clock(clock.clone())
+        .with_metrics(observer.clone())
+        .build()
+        .unwrap();
+
+    assert!(bucket.try_permit().is_err());
+    assert!(matches!(
+        observer.events().as_slice(),
+        [MetricEvent::PermitDenied { .. }]
+    ));
+    observer.clear();
+
+    clock.advance(Duration::from_secs(2));
+    assert_eq!(bucket.available_after_refill(), 6);
+    bucket.quick_acquire(1).unwrap();
+    assert_eq!(
+        observer.events(),
+        vec![
+            MetricEvent::Refill { tokens_added: 2 },
+            MetricEvent::PermitGranted,
+            MetricEvent::Acquire { tokens: 1 },
+        ]
+    );
+}
@@ -0,0 +1,22 @@
+//! Acquisitions across multiple buckets.
+
+use threshold_bucket::{try_acquire_all_or_nothing, Error};
+
+mod common;
+use common::bucket;
+
+#[test]
+fn rollback_on_failure() {
+    let (first, second, third) = (bucket(10), bucket(2), bucket(10));
+
+    let result = try_acquire_all_or_nothing([
+        (&first, first.try_permit().unwrap(), 4),
+        (&second, second.try_permit().unwrap(), 5),
+        (&third, third.try_permit().unwrap(), 6),
+    ]);
+
+    assert!(matches!(result, Err((1, Error::NotEnoughTokens { .. }))));
+    assert_eq!(first.available(), 10);
+    assert_eq!(second.available(), 2);
+    assert_eq!(third.available(), 10);
+}
@@ -0,0 +1,67 @@
+//! Named buckets.
+
+use std::time::Duration;
+use threshold_bucket::{refill::RateConfig, Bucket};
+
+fn bucket() -> Bucket {
+    Bucket::builder()
+        .rate(RateConfig {
+            quantity: 1.into(),
+            interval: Duration::from_secs(3600),
+            max: 10.into(),
+        })
+        .initial(2)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn errors_are_attributable() {
+    let api = bucket().with_name("api");
+    let uploads = bucket().with_name("uploads");
+
+    let logs: Vec<_> = [&api, &uploads]
+        .into_iter()
+        .map(|bucket| {
+            let err = bucket.quick_acquire(5).unwrap_err();
+            format!("{bucket}: {err}")
+        })
+        .collect();
+
+    assert_eq!(
+        logs,
+        vec![
+            "Bucket(api: 2/10 tokens): not enough tokens available (2 available)",
+            "Bucket(uploads: 2/10 tokens): not enough tokens available (2 available)",
+        ]
+    );
+    assert!(format!("{api:?}").contains(r#"name: Some("api")"#));
+}
+
+#[test]
+fn name_is_per_handle() {
+    let bucket = bucket();
+    let named = bucket.clone().with_name("api");
+
+    assert_eq!(bucket.name(), None);
+    assert_eq!(named.name(), Some("api"));
+    assert_eq!(named.clone().name(), Some("api"));
+    assert_eq!(bucket.to_string(), "Bucket(2/10 tokens)");
+
+    named.quick_acquire(1).unwrap();
+    let stats = named.stats();
+    assert_eq!(stats.name.as_deref(), Some("api"));
+    assert_eq!(stats.acquired_tokens, 1);
+    assert_eq!(bucket.stats().name, None);
+}
+
+#[test]
+fn owned_names() {
+    let tenant = 42;
+    let named = bucket().with_name(format!("tenant-{tenant}"));
+    assert_eq!(named.name(), Some("tenant-42"));
+
+    let shared: std::sync::Arc<str> = "shared".into();
+    let named = bucket().with_name(shared.clone());
+    assert_eq!(named.name(), Some(&*shared));
+}
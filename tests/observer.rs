@@ -0,0 +1,86 @@
+//! Refill observers added after building the bucket.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use threshold_bucket::{clock::ManualClock, observer::RefillObserver, refill::RateConfig, Bucket};
+
+#[derive(Default)]
+struct CountingObserver {
+    calls: AtomicU64,
+    last: Mutex<Option<(u64, u64, Option<Duration>)>>,
+}
+
+impl RefillObserver for CountingObserver {
+    fn on_refill(&self, tokens_added: u64, new_available: u64, next_refill_in: Option<Duration>) {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        *self.last.lock().unwrap() = Some((tokens_added, new_available, next_refill_in));
+    }
+}
+
+fn bucket(clock: &ManualClock) -> Bucket {
+    Bucket::builder()
+        .rate(RateConfig {
+            quantity: 2.into(),
+            interval: Duration::from_secs(1),
+            max: 10.into(),
+        })
+        .initial(0)
+        .with_clock(clock.clone())
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn called_on_each_refill() {
+    let clock = ManualClock::new();
+    let bucket = bucket(&clock);
+    let observer = Arc::new(CountingObserver::default());
+    bucket.observe_refill(observer.clone());
+
+    // No tokens added yet
+    bucket.available_after_refill();
+    assert_eq!(observer.calls.load(Ordering::Relaxed), 0);
+
+    for _ in 0..2 {
+        clock.advance(Duration::from_secs(1));
+        bucket.available_after_refill();
+    }
+    assert_eq!(observer.calls.load(Ordering::Relaxed), 2);
+    assert_eq!(
+        *observer.last.lock().unwrap(),
+        Some((2, 4, Some(Duration::from_secs(1))))
+    );
+}
+
+#[test]
+fn multiple_observers() {
+    let clock = ManualClock::new();
+    let bucket = bucket(&clock);
+    let first = Arc::new(CountingObserver::default());
+    let second = Arc::new(CountingObserver::default());
+    bucket.observe_refill(first.clone());
+    bucket.clone().observe_refill(second.clone());
+
+    clock.advance(Duration::from_secs(1));
+    bucket.available_after_refill();
+    assert_eq!(first.calls.load(Ordering::Relaxed), 1);
+    assert_eq!(second.calls.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn not_called_when_full() {
+    let clock = ManualClock::new();
+    let bucket = bucket(&clock);
+    bucket.add_tokens(10).unwrap();
+    let observer = Arc::new(CountingObserver::default());
+    bucket.observe_refill(observer.clone());
+
+    clock.advance(Duration::from_secs(1));
+    bucket.available_after_refill();
+    assert_eq!(observer.calls.load(Ordering::Relaxed), 0);
+}
@@ -0,0 +1,51 @@
+//! Acquisitions going into debt.
+
+use std::time::Duration;
+use threshold_bucket::{clock::ManualClock, refill::SlidingWindowConfig, Bucket, Error};
+
+#[test]
+fn overdraft_is_repaid_before_refilling() {
+    let clock = ManualClock::new();
+    let bucket = Bucket::builder()
+        .sliding_window(SlidingWindowConfig {
+            quantity: 10,
+            window: Duration::from_secs(10),
+            max: 10,
+        })
+        .initial(3)
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+
+    let token = bucket
+        .overdraft(bucket.try_permit().unwrap(), 5, 4)
+        .unwrap();
+    assert_eq!(token.amount(), 2);
+    assert_eq!(bucket.available(), 0);
+    assert_eq!(bucket.debt(), 2);
+
+    // The total debt can't exceed the overdraft limit
+    assert!(matches!(
+        bucket.overdraft(bucket.try_permit().unwrap(), 3, 4),
+        Err(Error::NotEnoughTokens { .. })
+    ));
+    let token = bucket
+        .overdraft(bucket.try_permit().unwrap(), 2, 4)
+        .unwrap();
+    assert_eq!(token.amount(), 2);
+    assert_eq!(bucket.debt(), 4);
+
+    // Refills repay the debt first
+    clock.advance(Duration::from_secs(3));
+    assert_eq!(bucket.available_after_refill(), 0);
+    assert_eq!(bucket.debt(), 1);
+    clock.advance(Duration::from_secs(3));
+    assert_eq!(bucket.available_after_refill(), 2);
+    assert_eq!(bucket.debt(), 0);
+
+    // No debt when there are enough tokens
+    let token = bucket
+        .overdraft(bucket.try_permit().unwrap(), 1, 4)
+        .unwrap();
+    assert_eq!(token.amount(), 0);
+}
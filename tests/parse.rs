@@ -0,0 +1,47 @@
+//! Parsing rates from human-readable strings.
+
+use std::time::Duration;
+use threshold_bucket::{refill::RateConfig, ParseError, TokenCount};
+
+#[test]
+fn parse_rate_config() {
+    let cases = [
+        ("10/s", 10, Duration::from_secs(1), 600),
+        ("500/min", 500, Duration::from_secs(60), 30_000),
+        ("50000/hr", 50_000, Duration::from_secs(3600), 3_000_000),
+        ("  10 / s ", 10, Duration::from_secs(1), 600),
+        ("100/s:max=5000", 100, Duration::from_secs(1), 5000),
+    ];
+
+    for (input, quantity, interval, max) in cases {
+        assert_eq!(
+            input.parse::<RateConfig>().unwrap(),
+            RateConfig {
+                quantity: TokenCount(quantity),
+                interval,
+                max: TokenCount(max),
+            },
+            "{input:?}"
+        );
+    }
+}
+
+#[test]
+fn parse_invalid_rate_config() {
+    assert!(matches!(
+        "abc/s".parse::<RateConfig>(),
+        Err(ParseError::InvalidQuantity(_))
+    ));
+    assert!(matches!(
+        "10/year".parse::<RateConfig>(),
+        Err(ParseError::InvalidInterval(_))
+    ));
+    assert!(matches!(
+        "10/s:max=lots".parse::<RateConfig>(),
+        Err(ParseError::InvalidMax(_))
+    ));
+    assert!(matches!(
+        "10".parse::<RateConfig>(),
+        Err(ParseError::InvalidFormat(_))
+    ));
+}
@@ -0,0 +1,66 @@
+//! Polling acquisitions from a hand-rolled future.
+#![cfg(feature = "tokio")]
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use threshold_bucket::{refill::SlidingWindowConfig, Bucket, Error};
+
+/// Future acquiring `num` tokens with [`Bucket::poll_acquire`]
+struct Acquire<'a> {
+    bucket: &'a Bucket,
+    num: u64,
+    polls: usize,
+}
+
+impl Future for Acquire<'_> {
+    type Output = Result<u64, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.polls += 1;
+        let permit = self.bucket.try_permit().unwrap();
+        self.bucket.poll_acquire(cx, permit, self.num)
+    }
+}
+
+fn bucket() -> Bucket {
+    Bucket::builder()
+        .sliding_window(SlidingWindowConfig {
+            quantity: 10,
+            window: Duration::from_millis(100),
+            max: 10,
+        })
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn wakes_when_tokens_are_available() {
+    let bucket = bucket();
+    let start = Instant::now();
+
+    let mut future = Acquire {
+        bucket: &bucket,
+        num: 5,
+        polls: 0,
+    };
+    assert_eq!((&mut future).await.unwrap(), 5);
+
+    // Woken by the timer instead of being polled in a loop
+    assert!(start.elapsed() >= Duration::from_millis(40));
+    assert!(future.polls < 10, "polled {} times", future.polls);
+}
+
+#[tokio::test]
+async fn ready_on_other_errors() {
+    let bucket = bucket();
+    let future = Acquire {
+        bucket: &bucket,
+        num: 11,
+        polls: 0,
+    };
+    assert!(matches!(future.await, Err(Error::ExceedMaxTokens)));
+}
@@ -0,0 +1,40 @@
+//! Exporting bucket metrics to Prometheus.
+#![cfg(feature = "prometheus")]
+
+use prometheus::{Registry, TextEncoder};
+use threshold_bucket::Bucket;
+
+fn gather(registry: &Registry) -> String {
+    TextEncoder::new()
+        .encode_to_string(&registry.gather())
+        .unwrap()
+}
+
+#[test]
+fn gauges_follow_acquisitions() {
+    let bucket = Bucket::builder()
+        .refill_rate_per_hour(1, 10)
+        .initial(10)
+        .build()
+        .unwrap();
+    let registry = Registry::new();
+    bucket.register_metrics(&registry, "api").unwrap();
+
+    bucket.quick_acquire(3).unwrap();
+    bucket.quick_acquire(2).unwrap();
+    assert!(bucket.quick_acquire(6).is_err());
+
+    let output = gather(&registry);
+    for line in [
+        "api_available_tokens 5",
+        "api_capacity 10",
+        "api_utilization 0.5",
+        "api_acquired_total 5",
+        "api_denied_total 1",
+    ] {
+        assert!(output.lines().any(|l| l == line), "{line:?} in {output}");
+    }
+
+    // Metrics can only be registered once
+    assert!(bucket.register_metrics(&Registry::new(), "other").is_err());
+}
@@ -0,0 +1,93 @@
+//! Acquiring a range of tokens.
+
+use threshold_bucket::Error;
+
+mod common;
+use common::bucket;
+
+fn acquire_range(
+    initial: u64,
+    range: impl std::ops::RangeBounds<u64>,
+) -> (Result<u64, Error>, u64) {
+    let bucket = bucket(initial);
+    let result = bucket.try_acquire_range(bucket.try_permit().unwrap(), range);
+    (result, bucket.available())
+}
+
+#[test]
+fn below_min() {
+    let (result, available) = acquire_range(6, 7..=9);
+    assert!(matches!(
+        result,
+        Err(Error::NotEnoughTokens { available: 6, .. })
+    ));
+    assert_eq!(available, 6);
+}
+
+#[test]
+fn at_min() {
+    assert_eq!(acquire_range(7, 7..=9), (Ok(7), 0));
+}
+
+#[test]
+fn between_min_and_max() {
+    assert_eq!(acquire_range(8, 7..=9), (Ok(8), 0));
+}
+
+#[test]
+fn above_max() {
+    assert_eq!(acquire_range(10, 7..=9), (Ok(9), 1));
+    assert_eq!(acquire_range(10, 7..9), (Ok(8), 2));
+}
+
+#[test]
+fn unbounded_ranges() {
+    assert_eq!(acquire_range(4, ..=6), (Ok(4), 0));
+    assert_eq!(acquire_range(0, ..=6), (Ok(0), 0));
+    assert_eq!(acquire_range(4, 2..), (Ok(4), 0));
+    assert_eq!(acquire_range(4, ..), (Ok(4), 0));
+}
+
+#[test]
+fn empty_range() {
+    assert_eq!(acquire_range(4, 3..3), (Ok(0), 4));
+    assert_eq!(acquire_range(4, ..0), (Ok(0), 4));
+}
+
+#[test]
+fn min_exceeds_max_tokens() {
+    assert_eq!(
+        acquire_range(10, 11..=20),
+        (Err(Error::ExceedMaxTokens), 10)
+    );
+}
+
+fn acquire_partial(initial: u64, max_num: u64) -> (Result<u64, Error>, u64) {
+    let bucket = bucket(initial);
+    let result = bucket.try_acquire_partial(bucket.try_permit().unwrap(), max_num);
+    (result, bucket.available())
+}
+
+#[test]
+fn partial_returns_available() {
+    assert_eq!(acquire_partial(4, 6), (Ok(4), 0));
+    assert_eq!(acquire_partial(0, 6), (Ok(0), 0));
+}
+
+#[test]
+fn partial_up_to_max() {
+    assert_eq!(acquire_partial(10, 6), (Ok(6), 4));
+    assert_eq!(acquire_partial(10, 0), (Ok(0), 10));
+    // More than the maximum number of tokens is fine, as it only takes what is available
+    assert_eq!(acquire_partial(10, 20), (Ok(10), 0));
+}
+
+#[test]
+fn partial_invalid_permit() {
+    let (first, second) = (bucket(10), bucket(10));
+    assert_eq!(
+        first.try_acquire_partial(second.try_permit().unwrap(), 5),
+        Err(Error::InvalidPermit)
+    );
+    assert_eq!(first.available(), 10);
+}
@@ -0,0 +1,64 @@
+//! Reads and writes with different costs, from the same bucket.
+
+use threshold_bucket::{Error, ReadWriteBucket};
+
+mod common;
+
+fn bucket(initial: u64) -> ReadWriteBucket {
+    common::builder(initial, 10).build_read_write(1, 4).unwrap()
+}
+
+#[test]
+fn reads_and_writes_share_tokens() {
+    let bucket = bucket(10);
+
+    assert_eq!(
+        bucket
+            .try_acquire_read(bucket.try_permit().unwrap())
+            .unwrap(),
+        1
+    );
+    assert_eq!(bucket.bucket().available(), 9);
+    assert_eq!(
+        bucket
+            .try_acquire_write(bucket.try_permit().unwrap())
+            .unwrap(),
+        4
+    );
+    assert_eq!(bucket.bucket().available(), 5);
+    assert_eq!(
+        bucket
+            .try_acquire_write(bucket.try_permit().unwrap())
+            .unwrap(),
+        4
+    );
+    assert_eq!(bucket.bucket().available(), 1);
+
+    // Not enough tokens left for a write, but enough for a read
+    assert!(matches!(
+        bucket.try_acquire_write(bucket.try_permit().unwrap()),
+        Err(Error::NotEnoughTokens { available: 1, .. })
+    ));
+    assert_eq!(
+        bucket
+            .try_acquire_read(bucket.try_permit().unwrap())
+            .unwrap(),
+        1
+    );
+    assert_eq!(bucket.bucket().available(), 0);
+}
+
+#[test]
+fn shares_tokens_with_underlying_bucket() {
+    let bucket = bucket(10);
+    let clone = bucket.clone();
+
+    bucket.bucket().quick_acquire(3).unwrap();
+    assert_eq!(
+        clone
+            .try_acquire_write(clone.try_permit().unwrap())
+            .unwrap(),
+        4
+    );
+    assert_eq!(bucket.bucket().available(), 3);
+}
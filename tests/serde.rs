@@ -0,0 +1,38 @@
+//! Serialization of the bucket configuration.
+#![cfg(feature = "serde")]
+
+use std::time::Duration;
+use threshold_bucket::{refill::RateConfig, BucketConfig};
+
+#[test]
+fn rate_config_roundtrip() {
+    let config = RateConfig {
+        quantity: 10.into(),
+        interval: Duration::from_millis(1500),
+        max: 100.into(),
+    };
+
+    let json = serde_json::to_string(&config).unwrap();
+    assert_eq!(json, r#"{"quantity":10,"interval":1500,"max":100}"#);
+
+    let config: RateConfig = serde_json::from_str(&json).unwrap();
+    assert_eq!(config.quantity, 10);
+    assert_eq!(config.interval, Duration::from_millis(1500));
+    assert_eq!(config.max, 100);
+}
+
+#[test]
+fn bucket_config_into_builder() {
+    let config: BucketConfig = serde_json::from_str(
+        r#"{
+            "rate": { "quantity": 1, "interval": 3600000, "max": 10 },
+            "threshold": { "threshold": 5 },
+            "initial": 4
+        }"#,
+    )
+    .unwrap();
+    let bucket = config.into_builder().build().unwrap();
+
+    assert_eq!(bucket.available(), 4);
+    assert!(bucket.try_permit().is_err());
+}
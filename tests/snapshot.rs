@@ -0,0 +1,165 @@
+//! Restoring buckets from snapshots.
+
+use std::{env, fs, io, path::PathBuf, thread, time::Duration};
+use threshold_bucket::{clock::ManualClock, refill::RateConfig, Bucket, Builder};
+
+fn config() -> RateConfig {
+    RateConfig {
+        quantity: 1.into(),
+        interval: Duration::from_secs(10),
+        max: 10.into(),
+    }
+}
+
+fn builder(clock: &ManualClock) -> Builder {
+    Bucket::builder()
+        .rate(config())
+        .initial(10)
+        .with_clock(clock.clone())
+}
+
+#[test]
+fn restore_from_snapshot() {
+    let clock = ManualClock::new();
+    let bucket = builder(&clock).build().unwrap();
+    let permit = bucket.try_permit().unwrap();
+    bucket.try_acquire(permit, 7).unwrap();
+    clock.advance(Duration::from_secs(25));
+
+    let snapshot = bucket.snapshot();
+    assert_eq!(snapshot.elapsed_millis, 25_000);
+
+    let restored = builder(&ManualClock::new())
+        .restore_from_snapshot(snapshot)
+        .build()
+        .unwrap();
+    assert_eq!(restored.available(), bucket.available());
+    assert_eq!(restored.snapshot(), snapshot);
+}
+
+#[test]
+fn restore_keeps_refill_boundaries() {
+    let clock = ManualClock::new();
+    let bucket = builder(&clock).build().unwrap();
+    bucket.quick_acquire(5).unwrap();
+    clock.advance(Duration::from_secs(13));
+
+    let snapshot = bucket.snapshot();
+    let restored = builder(&ManualClock::new())
+        .restore_from_snapshot(snapshot)
+        .build()
+        .unwrap();
+    assert_eq!(restored.available(), 6);
+    assert_eq!(restored.next_refill_in(), Some(Duration::from_secs(7)));
+    assert_eq!(restored.next_refill_in(), bucket.next_refill_in());
+}
+
+#[test]
+fn drain_to_snapshot() {
+    let clock = ManualClock::new();
+    let bucket = builder(&clock).build().unwrap();
+    bucket.quick_acquire(4).unwrap();
+    clock.advance(Duration::from_secs(15));
+
+    let snapshot = bucket.drain_to_snapshot().unwrap();
+    assert_eq!(snapshot.available, 7);
+    assert_eq!(snapshot.elapsed_millis, 15_000);
+    assert_eq!(bucket.available(), 0);
+    assert_eq!(bucket.next_refill_in(), Some(Duration::from_secs(5)));
+}
+
+#[test]
+fn concurrent_drain_to_snapshot() {
+    for _ in 0..100 {
+        let bucket = builder(&ManualClock::new()).build().unwrap();
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                let bucket = bucket.clone();
+                thread::spawn(move || bucket.drain_to_snapshot().unwrap().available)
+            })
+            .collect();
+
+        let drained: u64 = handles.into_iter().map(|h| h.join().unwrap()).sum();
+        assert_eq!(drained, 10);
+        assert_eq!(bucket.available(), 0);
+    }
+}
+
+/// Path of a checkpoint file unique to this test process
+fn checkpoint_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("threshold-bucket-{}-{name}", std::process::id()))
+}
+
+#[test]
+fn restore_checkpoint() {
+    let path = checkpoint_path("restore");
+    let clock = ManualClock::new();
+    let bucket = builder(&clock).build().unwrap();
+    bucket.quick_acquire(6).unwrap();
+    clock.advance(Duration::from_secs(13));
+    bucket.checkpoint(&path).unwrap();
+
+    let bytes = fs::read(&path).unwrap();
+    assert_eq!(bytes.len(), 24);
+    assert_eq!(bytes[..8], 5u64.to_le_bytes());
+    assert_eq!(bytes[8..16], 13_000u64.to_le_bytes());
+    assert_eq!(bytes[16..], 10u64.to_le_bytes());
+
+    let restored = Builder::restore_checkpoint(&path, config()).unwrap();
+    assert_eq!(restored.available(), 5);
+
+    // The restored bucket uses the system clock, which keeps running after the restore
+    let next_refill_in = restored.next_refill_in().unwrap();
+    assert!(next_refill_in <= Duration::from_secs(7));
+    assert!(next_refill_in > Duration::from_secs(6));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn restore_invalid_checkpoint() {
+    let path = checkpoint_path("invalid");
+
+    fs::write(&path, [0; 16]).unwrap();
+    let err = Builder::restore_checkpoint(&path, config()).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    // Written by a bucket with a different max
+    let bucket = Bucket::builder()
+        .rate(RateConfig {
+            quantity: 1.into(),
+            interval: Duration::from_secs(10),
+            max: 20.into(),
+        })
+        .build()
+        .unwrap();
+    bucket.checkpoint(&path).unwrap();
+    let err = Builder::restore_checkpoint(&path, config()).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    fs::remove_file(&path).unwrap();
+
+    let err = Builder::restore_checkpoint(&path, config()).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn restore_from_serialized_snapshot() {
+    let clock = ManualClock::new();
+    let bucket = builder(&clock).build().unwrap();
+    bucket.quick_acquire(8).unwrap();
+    clock.advance(Duration::from_secs(4));
+
+    let json = serde_json::to_string(&bucket.snapshot()).unwrap();
+    assert_eq!(json, r#"{"available":2,"elapsed_millis":4000}"#);
+
+    let clock = ManualClock::new();
+    let restored = builder(&clock)
+        .restore_from_snapshot(serde_json::from_str(&json).unwrap())
+        .build()
+        .unwrap();
+    assert_eq!(restored.available(), 2);
+    assert_eq!(restored.next_refill_in(), Some(Duration::from_secs(6)));
+
+    clock.advance(Duration::from_secs(6));
+    assert_eq!(restored.available_after_refill(), 3);
+}
@@ -0,0 +1,39 @@
+//! Lifetime statistics of a bucket.
+
+use std::time::Duration;
+use threshold_bucket::{clock::ManualClock, refill::SlidingWindowConfig, Bucket, BucketStats};
+
+#[test]
+fn stats_after_acquisitions() {
+    let clock = ManualClock::new();
+    let bucket = Bucket::builder()
+        .sliding_window(SlidingWindowConfig {
+            quantity: 10,
+            window: Duration::from_secs(10),
+            max: 10,
+        })
+        .initial(5)
+        .with_clock(clock.clone())
+        .build()
+        .unwrap();
+
+    for num in [2, 3, 1] {
+        let _ = bucket.try_acquire(bucket.try_permit().unwrap(), num);
+    }
+    assert_eq!(
+        bucket.stats(),
+        BucketStats {
+            name: None,
+            acquired_tokens: 5,
+            denied_requests: 1,
+            refill_count: 0,
+            contention_errors: 0,
+        }
+    );
+
+    clock.advance(Duration::from_secs(4));
+    bucket.try_acquire(bucket.try_permit().unwrap(), 4).unwrap();
+    let stats = bucket.stats();
+    assert_eq!(stats.acquired_tokens, 9);
+    assert_eq!(stats.refill_count, 1);
+}
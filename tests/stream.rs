@@ -0,0 +1,54 @@
+//! Acquiring tokens in chunks as a stream.
+#![cfg(all(feature = "tokio", feature = "futures"))]
+
+use futures_core::Stream;
+use std::{
+    future::poll_fn,
+    pin::Pin,
+    time::{Duration, Instant},
+};
+use threshold_bucket::{refill::SlidingWindowConfig, Bucket, Error};
+
+async fn next<S: Stream + Unpin>(stream: &mut S) -> Option<S::Item> {
+    poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)).await
+}
+
+fn bucket() -> Bucket {
+    Bucket::builder()
+        .sliding_window(SlidingWindowConfig {
+            quantity: 10,
+            window: Duration::from_millis(100),
+            max: 10,
+        })
+        .initial(4)
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn yields_chunks_over_time() {
+    let bucket = bucket();
+    let start = Instant::now();
+
+    let mut stream = bucket.acquire_n_as_stream(|| bucket.try_permit(), 10, 4);
+    let mut chunks = Vec::new();
+    while let Some(chunk) = next(&mut stream).await {
+        chunks.push(chunk.unwrap());
+    }
+
+    assert_eq!(chunks, vec![4, 4, 2]);
+    // Only the first chunk was available right away
+    assert!(start.elapsed() >= Duration::from_millis(40));
+}
+
+#[tokio::test]
+async fn ends_after_other_errors() {
+    let bucket = bucket();
+
+    let mut stream = bucket.acquire_n_as_stream(|| bucket.try_permit(), 20, 20);
+    assert!(matches!(
+        next(&mut stream).await,
+        Some(Err(Error::ExceedMaxTokens))
+    ));
+    assert!(next(&mut stream).await.is_none());
+}
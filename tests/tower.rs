@@ -0,0 +1,45 @@
+//! Rate limiting HTTP requests with the `tower` middleware.
+#![cfg(feature = "tower")]
+
+use std::{convert::Infallible, time::Duration};
+
+use http::{header::RETRY_AFTER, Request, Response, StatusCode};
+use threshold_bucket::{middleware::tower::RateLimitLayer, Bucket};
+use tower::{service_fn, Layer, ServiceExt};
+
+mod common;
+
+/// Bucket with `initial` tokens out of 10, refilling 1 token every 10 seconds
+fn bucket(initial: u64) -> Bucket {
+    common::builder(initial, 10)
+        .refill_rate(1, Duration::from_secs(10))
+        .build()
+        .unwrap()
+}
+
+async fn handle(_req: Request<()>) -> Result<Response<String>, Infallible> {
+    Ok(Response::new("ok".to_string()))
+}
+
+#[tokio::test]
+async fn allows_requests_with_tokens() {
+    let bucket = bucket(1);
+    let service = RateLimitLayer::new(bucket.clone()).layer(service_fn(handle));
+
+    let response = service.oneshot(Request::new(())).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body(), "ok");
+    assert_eq!(bucket.available(), 0);
+}
+
+#[tokio::test]
+async fn rejects_requests_without_tokens() {
+    let service = RateLimitLayer::new(bucket(0)).layer(service_fn(handle));
+
+    let response = service.oneshot(Request::new(())).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()[RETRY_AFTER], "10");
+    assert_eq!(response.body(), "");
+}
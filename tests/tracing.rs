@@ -0,0 +1,111 @@
+//! Tracing events emitted by acquisitions and refills.
+#![cfg(feature = "tracing")]
+
+use std::{
+    io,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use threshold_bucket::{clock::ManualClock, refill::RateConfig, Bucket};
+use tracing::Level;
+
+/// Writer capturing the formatted events
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Capture {
+    fn output(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+#[test]
+fn events_include_fields() {
+    let capture = Capture::default();
+    let writer = capture.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(Level::TRACE)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish();
+
+    let clock = ManualClock::new();
+    let bucket = Bucket::builder()
+        .rate(RateConfig {
+            quantity: 2.into(),
+            interval: Duration::from_secs(1),
+            max: 10.into(),
+        })
+        .initial(5)
+        .with_clock(clock.clone())
+        .with_trace_name("uploads")
+        .build()
+        .unwrap();
+
+    tracing::subscriber::with_default(subscriber, || {
+        bucket.quick_acquire(3).unwrap();
+        bucket.quick_acquire(4).unwrap_err();
+        clock.advance(Duration::from_secs(1));
+        bucket.quick_acquire(1).unwrap();
+    });
+
+    let output = capture.output();
+    let lines: Vec<_> = output.lines().collect();
+    assert_eq!(lines.len(), 4, "{output}");
+
+    assert!(
+        lines[0].contains("DEBUG bucket.try_acquire{bucket=\"uploads\" requested=3 available=5}")
+    );
+    assert!(lines[0].contains("acquired=3 remaining=2"));
+    assert!(lines[1].contains("INFO"));
+    assert!(lines[1].contains("requested=4 denied=true wait_for_ms=Some(1000)"));
+    assert!(lines[2].contains("TRACE"));
+    assert!(lines[2].contains("tokens_added=2 new_available=4"));
+    assert!(lines[3].contains("requested=1 available=4}"));
+    assert!(lines[3].contains("acquired=1 remaining=3"));
+}
+
+#[test]
+fn contention_is_a_warning() {
+    let capture = Capture::default();
+    let writer = capture.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(Level::TRACE)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish();
+
+    // Without any attempts, acquisitions that can't take the fast path fail with contention
+    let bucket = Bucket::builder()
+        .rate(RateConfig {
+            quantity: 1.into(),
+            interval: Duration::from_secs(1),
+            max: 10.into(),
+        })
+        .initial(0)
+        .with_clock(ManualClock::new())
+        .max_cas_retries(0)
+        .build()
+        .unwrap();
+
+    tracing::subscriber::with_default(subscriber, || {
+        bucket.quick_acquire(1).unwrap_err();
+    });
+
+    let output = capture.output();
+    assert!(output.contains("WARN bucket.try_acquire{"), "{output}");
+    assert!(
+        output.contains("requested=1 contention=true retries=0"),
+        "{output}"
+    );
+}
@@ -0,0 +1,33 @@
+//! Compile-time assertions on the auto traits implemented by public types.
+//!
+//! [`Bucket`] is `Send + Sync` only if its `Inner` and `Permitter` are, so these assertions also
+//! cover the internal types.
+
+use static_assertions::{assert_impl_all, assert_not_impl_any};
+use threshold_bucket::{
+    clock::{ManualClock, SystemClock},
+    permit::{BatchPermit, Permit, PriorityConfig, ThresholdConfig},
+    refill::RateConfig,
+    Bucket, BucketInner, BuildError, Builder, Error, NoMax, NoRefill,
+};
+
+assert_impl_all!(Bucket: Send, Sync, Clone, std::fmt::Debug);
+assert_impl_all!(Builder: Send, Sync, std::fmt::Debug);
+assert_impl_all!(BucketInner: Send, Sync, Clone, std::fmt::Debug);
+
+// An empty builder can't build a bucket, so it isn't a sensible default
+assert_not_impl_any!(Builder<NoRefill, NoMax>: Default);
+
+// Permits can be held across `.await` points in multi-threaded executors
+assert_impl_all!(Permit: Send, Sync, std::fmt::Debug);
+assert_impl_all!(BatchPermit: Send, Sync);
+
+assert_impl_all!(SystemClock: Send, Sync, Clone);
+assert_impl_all!(ManualClock: Send, Sync, Clone);
+
+assert_impl_all!(RateConfig: Send, Sync, Clone);
+assert_impl_all!(ThresholdConfig: Send, Sync, Clone);
+assert_impl_all!(PriorityConfig: Send, Sync, Clone);
+
+assert_impl_all!(Error: Send, Sync, Clone, std::error::Error);
+assert_impl_all!(BuildError: Send, Sync, Clone, std::error::Error);
@@ -0,0 +1,107 @@
+//! Waiting for tokens with owned futures.
+#![cfg(feature = "tokio")]
+
+use futures::future::join_all;
+use std::{
+    future::Future,
+    pin::Pin,
+    time::{Duration, Instant},
+};
+use threshold_bucket::{
+    permit::ThresholdConfig,
+    refill::{RateConfig, SlidingWindowConfig},
+    Bucket, Error,
+};
+
+type AcquireFuture = Pin<Box<dyn Future<Output = Result<u64, Error>> + Send>>;
+
+fn bucket() -> Bucket {
+    Bucket::builder()
+        .sliding_window(SlidingWindowConfig {
+            quantity: 10,
+            window: Duration::from_millis(100),
+            max: 10,
+        })
+        .initial(4)
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn join_all_acquisitions() {
+    let bucket = bucket();
+    let start = Instant::now();
+
+    let futures: Vec<AcquireFuture> = (0..3)
+        .map(|_| bucket.try_acquire_or_wait_async(bucket.try_permit().unwrap(), 2))
+        .collect();
+    let results = join_all(futures).await;
+
+    assert_eq!(results, vec![Ok(2), Ok(2), Ok(2)]);
+    // Only two acquisitions were possible right away
+    assert!(start.elapsed() >= Duration::from_millis(10));
+}
+
+#[tokio::test]
+async fn outlives_the_bucket() {
+    let bucket = bucket();
+    let future = bucket.try_acquire_or_wait_async(bucket.try_permit().unwrap(), 20);
+    drop(bucket);
+
+    assert!(matches!(
+        tokio::spawn(future).await.unwrap(),
+        Err(Error::ExceedMaxTokens)
+    ));
+}
+
+fn threshold_bucket() -> Bucket {
+    Bucket::builder()
+        .rate(RateConfig {
+            quantity: 1.into(),
+            interval: Duration::from_millis(50),
+            max: 10.into(),
+        })
+        .initial(4)
+        .threshold(ThresholdConfig {
+            threshold: 5.into(),
+        })
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn async_permit_across_refill() {
+    let bucket = threshold_bucket();
+    let start = Instant::now();
+
+    let permit = tokio::time::timeout(Duration::from_secs(2), bucket.get_async_permit())
+        .await
+        .expect("permit not granted after the refill");
+    assert!(permit.is_ok());
+    assert!(start.elapsed() >= Duration::from_millis(50));
+}
+
+#[tokio::test]
+async fn wait_acquire_after_drain() {
+    let bucket = Bucket::builder()
+        .rate(RateConfig {
+            quantity: 1.into(),
+            interval: Duration::from_millis(50),
+            max: 10.into(),
+        })
+        .initial(4)
+        .threshold(ThresholdConfig {
+            threshold: 1.into(),
+        })
+        .build()
+        .unwrap();
+    let permit = bucket.try_permit().unwrap();
+    bucket.drain().unwrap();
+    let start = Instant::now();
+
+    let result = tokio::time::timeout(Duration::from_secs(2), bucket.wait_acquire(permit, 1))
+        .await
+        .expect("tokens not acquired after the refill");
+    assert_eq!(result, Ok(1));
+    assert!(start.elapsed() >= Duration::from_millis(50));
+}